@@ -0,0 +1,97 @@
+// external crates
+use criterion::{criterion_group, criterion_main, Criterion};
+use paint_server::memory;
+use paint_server::products::{Color, ColorIndex, Finish, Paint};
+
+/// roughly how many paints a catalog covering every brand on the market would hold
+const CATALOG_SIZE: usize = 50_000;
+
+/// the product lines in the fixtures, which the made up paints are spread across
+const LINES: i32 = 6;
+
+/// a cheap deterministic source of colors so every run measures the same catalog
+struct Colors(u64);
+
+impl Colors {
+    fn next(&mut self) -> Color {
+        // xorshift is plenty random for spreading colors around
+        let mut channel = || {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % 256) as u8
+        };
+
+        Color {
+            red: channel(),
+            green: channel(),
+            blue: channel(),
+        }
+    }
+}
+
+/// the fixtures with the paints swapped out for a catalog of made up ones
+fn catalog() -> memory::Store {
+    let store = memory::Store::seeded();
+    let mut colors = Colors(0x2545_f491_4f6c_dd1d);
+    store.write().paints = (0..CATALOG_SIZE as i32)
+        .map(|id| Paint {
+            id: id + 1,
+            line_id: id % LINES + 1,
+            name: format!("Paint {}", id + 1),
+            sku: None,
+            color: colors.next(),
+            finish: Finish::Matte,
+            volume_ml: 12.0,
+            discontinued: false,
+            released_on: None,
+        })
+        .collect();
+
+    store
+}
+
+fn nearest(c: &mut Criterion) {
+    let store = catalog();
+    let index = ColorIndex::new();
+    let mut targets = Colors(0x9e37_79b9_7f4a_7c15);
+
+    // the first lookup builds the index, which every request after it shares
+    index.nearest(&store, targets.next(), 10, |_| true).unwrap();
+    c.bench_function("nearest 10 paints", move |b| {
+        b.iter(|| index.nearest(&store, targets.next(), 10, |_| true).unwrap())
+    });
+}
+
+fn nearest_filtered(c: &mut Criterion) {
+    let store = catalog();
+    let index = ColorIndex::new();
+    let mut targets = Colors(0x9e37_79b9_7f4a_7c15);
+
+    // the brand with a single product line makes up a sixth of the catalog
+    index.nearest(&store, targets.next(), 10, |_| true).unwrap();
+    c.bench_function("nearest 10 paints from one brand", move |b| {
+        b.iter(|| {
+            index
+                .nearest(&store, targets.next(), 10, |entry| entry.brand_id == 3)
+                .unwrap()
+        })
+    });
+}
+
+fn rebuild(c: &mut Criterion) {
+    let store = catalog();
+    let index = ColorIndex::new();
+    let target = Colors(0x9e37_79b9_7f4a_7c15).next();
+
+    // what the first lookup after the catalog changes pays
+    c.bench_function("rebuild the index", move |b| {
+        b.iter(|| {
+            index.invalidate();
+            index.nearest(&store, target, 10, |_| true).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, nearest, nearest_filtered, rebuild);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+// external crates
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// the server is a binary so we pull the modules we need in directly
+#[path = "../src/trie.rs"]
+mod trie;
+
+use trie::Trie;
+
+/// roughly how many paints a catalog covering every brand on the market would hold
+const CATALOG_SIZE: usize = 50_000;
+
+/// the words paint names are made of, more or less
+const WORDS: &[&str] = &[
+    "abaddon", "black", "red", "blood", "angels", "mephiston", "wraithbone", "armour", "nuln",
+    "oil", "agrax", "earthshade", "templar", "white", "dead", "flat", "matt", "pure", "bloody",
+    "leadbelcher", "retributor", "khorne", "averland", "sunset", "caliban", "green", "ushabti",
+    "bone", "stormvermin", "fur", "zandri", "dust", "macragge", "blue", "naggaroth", "night",
+];
+
+/// build a catalog of made up names, keyed the way the server keys them
+fn catalog() -> Trie<usize> {
+    // xorshift is plenty random for picking words
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut word = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        WORDS[(state % WORDS.len() as u64) as usize]
+    };
+
+    Trie::build(
+        (0..CATALOG_SIZE)
+            .map(|id| (format!("{} {} {}", word(), word(), id), id))
+            .collect(),
+    )
+}
+
+fn suggest(c: &mut Criterion) {
+    let trie = catalog();
+
+    // the shortest prefixes match the most names, which is the worst case
+    c.bench_function("suggest 10 names for a two letter prefix", move |b| {
+        b.iter(|| trie.with_prefix("bl", 10, |_| true))
+    });
+}
+
+fn suggest_filtered(c: &mut Criterion) {
+    let trie = catalog();
+
+    // roughly what skipping the paints that already matched by their whole name looks like
+    c.bench_function("suggest 10 names skipping most of them", move |b| {
+        b.iter(|| trie.with_prefix("bl", 10, |id| id % 50 == 0))
+    });
+}
+
+fn build(c: &mut Criterion) {
+    c.bench_function("build the trie", |b| b.iter(catalog));
+}
+
+criterion_group!(benches, suggest, suggest_filtered, build);
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+CREATE TABLE paints (
+    id           INTEGER PRIMARY KEY,
+    brand        TEXT    NOT NULL,
+    product_line TEXT    NOT NULL,
+    name         TEXT    NOT NULL,
+    sku          TEXT,
+    hex          TEXT    NOT NULL,
+    finish       TEXT    NOT NULL,
+    volume_ml    REAL    NOT NULL,
+    discontinued INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX paints_brand ON paints (brand, product_line);
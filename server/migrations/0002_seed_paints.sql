@@ -0,0 +1,19 @@
+INSERT INTO paints (brand, product_line, name, sku, hex, finish, volume_ml, discontinued) VALUES
+    ('Citadel', 'Base', 'Abaddon Black', NULL, '#231f20', 'matte', 12.0, 0),
+    ('Citadel', 'Base', 'Mephiston Red', NULL, '#9a1115', 'matte', 12.0, 0),
+    ('Citadel', 'Base', 'Wraithbone', NULL, '#dbd1b2', 'matte', 12.0, 0),
+    ('Citadel', 'Base', 'Retributor Armour', NULL, '#b98e35', 'metallic', 12.0, 0),
+    ('Citadel', 'Base', 'Leadbelcher', NULL, '#888d8f', 'metallic', 12.0, 0),
+    ('Citadel', 'Shade', 'Nuln Oil', NULL, '#14100e', 'wash', 18.0, 0),
+    ('Citadel', 'Shade', 'Agrax Earthshade', NULL, '#5a4a30', 'wash', 18.0, 0),
+    ('Citadel', 'Contrast', 'Blood Angels Red', NULL, '#b7121b', 'contrast', 18.0, 0),
+    ('Citadel', 'Contrast', 'Black Templar', NULL, '#1f1f21', 'contrast', 18.0, 0),
+    ('Vallejo', 'Model Color', 'Black', '70.950', '#171717', 'matte', 17.0, 0),
+    ('Vallejo', 'Model Color', 'White', '70.951', '#f7f7f5', 'matte', 17.0, 0),
+    ('Vallejo', 'Model Color', 'Flat Red', '70.957', '#a51a1e', 'matte', 17.0, 0),
+    ('Vallejo', 'Game Color', 'Dead White', '72.001', '#fcfcfc', 'matte', 17.0, 0),
+    ('Vallejo', 'Game Color', 'Bloody Red', '72.010', '#c21e27', 'matte', 17.0, 0),
+    ('Vallejo', 'Game Color', 'Black', '72.051', '#0b0b0b', 'matte', 17.0, 0),
+    ('The Army Painter', 'Warpaints', 'Matt Black', NULL, '#0d0d0d', 'matte', 18.0, 0),
+    ('The Army Painter', 'Warpaints', 'Matt White', NULL, '#f4f4f4', 'matte', 18.0, 0),
+    ('The Army Painter', 'Warpaints', 'Pure Red', NULL, '#c3161b', 'matte', 18.0, 0);
@@ -0,0 +1,46 @@
+CREATE TABLE brands (
+    id   INTEGER PRIMARY KEY,
+    name TEXT    NOT NULL UNIQUE
+);
+
+CREATE TABLE product_lines (
+    id       INTEGER PRIMARY KEY,
+    brand_id INTEGER NOT NULL REFERENCES brands (id),
+    name     TEXT    NOT NULL,
+    UNIQUE (brand_id, name)
+);
+
+-- pull the brands and lines out of the paints in the order they were first seen
+INSERT INTO brands (name)
+    SELECT brand FROM paints GROUP BY brand ORDER BY MIN(id);
+
+INSERT INTO product_lines (brand_id, name)
+    SELECT brands.id, paints.product_line
+    FROM paints JOIN brands ON brands.name = paints.brand
+    GROUP BY brands.id, paints.product_line
+    ORDER BY MIN(paints.id);
+
+-- sqlite can't drop columns so the paints table has to be rebuilt to point at its line
+CREATE TABLE paints_with_lines (
+    id              INTEGER PRIMARY KEY,
+    product_line_id INTEGER NOT NULL REFERENCES product_lines (id),
+    name            TEXT    NOT NULL,
+    sku             TEXT,
+    hex             TEXT    NOT NULL,
+    finish          TEXT    NOT NULL,
+    volume_ml       REAL    NOT NULL,
+    discontinued    INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO paints_with_lines
+    SELECT paints.id, product_lines.id, paints.name, paints.sku, paints.hex, paints.finish,
+           paints.volume_ml, paints.discontinued
+    FROM paints
+    JOIN brands ON brands.name = paints.brand
+    JOIN product_lines ON product_lines.brand_id = brands.id
+                      AND product_lines.name = paints.product_line;
+
+DROP TABLE paints;
+ALTER TABLE paints_with_lines RENAME TO paints;
+
+CREATE INDEX paints_product_line ON paints (product_line_id);
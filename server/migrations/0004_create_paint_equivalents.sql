@@ -0,0 +1,33 @@
+-- equivalences go both ways so each pair is only stored once, lowest id first
+CREATE TABLE paint_equivalents (
+    paint_id      INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    equivalent_id INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    source        TEXT    NOT NULL,
+    PRIMARY KEY (paint_id, equivalent_id),
+    CHECK (paint_id < equivalent_id)
+);
+
+CREATE INDEX paint_equivalents_equivalent ON paint_equivalents (equivalent_id);
+
+-- the paints are looked up by name since their ids depend on the order they went in
+WITH catalog (id, brand, line, name) AS (
+    SELECT paints.id, brands.name, product_lines.name, paints.name
+    FROM paints
+    JOIN product_lines ON product_lines.id = paints.product_line_id
+    JOIN brands ON brands.id = product_lines.brand_id
+), pairs (brand, line, name, equivalent_brand, equivalent_line, equivalent_name, source) AS (
+    VALUES
+        ('Citadel', 'Base', 'Abaddon Black', 'Vallejo', 'Game Color', 'Black', 'official'),
+        ('Citadel', 'Base', 'Abaddon Black', 'The Army Painter', 'Warpaints', 'Matt Black', 'community'),
+        ('Citadel', 'Base', 'Mephiston Red', 'Vallejo', 'Game Color', 'Bloody Red', 'official'),
+        ('Citadel', 'Base', 'Mephiston Red', 'The Army Painter', 'Warpaints', 'Pure Red', 'community')
+)
+INSERT INTO paint_equivalents (paint_id, equivalent_id, source)
+    SELECT MIN(paint.id, equivalent.id), MAX(paint.id, equivalent.id), pairs.source
+    FROM pairs
+    JOIN catalog AS paint ON paint.brand = pairs.brand
+                         AND paint.line = pairs.line
+                         AND paint.name = pairs.name
+    JOIN catalog AS equivalent ON equivalent.brand = pairs.equivalent_brand
+                              AND equivalent.line = pairs.equivalent_line
+                              AND equivalent.name = pairs.equivalent_name;
@@ -0,0 +1,51 @@
+CREATE TABLE product_videos (
+    id               INTEGER PRIMARY KEY,
+    title            TEXT    NOT NULL,
+    channel          TEXT    NOT NULL,
+    url              TEXT    NOT NULL,
+    duration_seconds INTEGER NOT NULL,
+    published_on     TEXT    NOT NULL
+);
+
+-- a paint can show up more than once in the same video
+CREATE TABLE paint_usages (
+    video_id      INTEGER NOT NULL REFERENCES product_videos (id) ON DELETE CASCADE,
+    paint_id      INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    start_seconds INTEGER NOT NULL,
+    note          TEXT,
+    PRIMARY KEY (video_id, paint_id, start_seconds)
+);
+
+CREATE INDEX paint_usages_paint ON paint_usages (paint_id);
+
+INSERT INTO product_videos (title, channel, url, duration_seconds, published_on) VALUES
+    ('Blood Angels Armour in Three Steps', 'Brushwork Basics', 'https://videos.example.com/blood-angels-armour', 754, '2019-03-14'),
+    ('Black Armour That Still Reads as Black', 'Tabletop Standard', 'https://videos.example.com/black-armour', 1260, '2019-05-02'),
+    ('Speed Painting Skeletons', 'Tabletop Standard', 'https://videos.example.com/speed-skeletons', 540, '2019-06-20');
+
+-- the videos and paints are looked up by their url and name since their ids depend on the
+-- order they went in
+WITH catalog (id, brand, line, name) AS (
+    SELECT paints.id, brands.name, product_lines.name, paints.name
+    FROM paints
+    JOIN product_lines ON product_lines.id = paints.product_line_id
+    JOIN brands ON brands.id = product_lines.brand_id
+), usages (url, brand, line, name, start_seconds, note) AS (
+    VALUES
+        ('https://videos.example.com/blood-angels-armour', 'Citadel', 'Base', 'Mephiston Red', 45, 'basecoat over a black primer'),
+        ('https://videos.example.com/blood-angels-armour', 'Citadel', 'Contrast', 'Blood Angels Red', 210, 'glazed over the basecoat'),
+        ('https://videos.example.com/blood-angels-armour', 'Citadel', 'Shade', 'Nuln Oil', 420, 'recess shade'),
+        ('https://videos.example.com/black-armour', 'Vallejo', 'Model Color', 'Black', 30, 'basecoat'),
+        ('https://videos.example.com/black-armour', 'Vallejo', 'Model Color', 'White', 300, 'edge highlights'),
+        ('https://videos.example.com/black-armour', 'Vallejo', 'Game Color', 'Dead White', 900, 'final spot highlights'),
+        ('https://videos.example.com/speed-skeletons', 'Citadel', 'Base', 'Wraithbone', 20, NULL),
+        ('https://videos.example.com/speed-skeletons', 'Citadel', 'Shade', 'Agrax Earthshade', 120, 'all over shade'),
+        ('https://videos.example.com/speed-skeletons', 'The Army Painter', 'Warpaints', 'Matt White', 400, 'drybrush')
+)
+INSERT INTO paint_usages (video_id, paint_id, start_seconds, note)
+    SELECT product_videos.id, catalog.id, usages.start_seconds, usages.note
+    FROM usages
+    JOIN product_videos ON product_videos.url = usages.url
+    JOIN catalog ON catalog.brand = usages.brand
+                AND catalog.line = usages.line
+                AND catalog.name = usages.name;
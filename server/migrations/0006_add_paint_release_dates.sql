@@ -0,0 +1,15 @@
+-- the day each paint went on sale. we only know it for some of the catalog so far
+ALTER TABLE paints ADD COLUMN released_on TEXT;
+
+-- the lines are matched by name since their ids depend on what the catalog held when the
+-- brands were pulled out of it
+UPDATE paints SET released_on = '2012-06-02' WHERE product_line_id IN (
+    SELECT product_lines.id
+    FROM product_lines JOIN brands ON brands.id = product_lines.brand_id
+    WHERE brands.name = 'Citadel' AND product_lines.name IN ('Base', 'Shade')
+);
+UPDATE paints SET released_on = '2019-06-08' WHERE product_line_id IN (
+    SELECT product_lines.id
+    FROM product_lines JOIN brands ON brands.id = product_lines.brand_id
+    WHERE brands.name = 'Citadel' AND product_lines.name = 'Contrast'
+);
@@ -0,0 +1,3 @@
+-- what each user is allowed to do. everyone who signed up before roles existed is a
+-- regular user, the first admin is handed out with `paint-server grant-admin`
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
@@ -0,0 +1,19 @@
+-- keys are stored as their hash so a copy of the database doesn't hand them out
+CREATE TABLE api_keys (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT    NOT NULL,
+    hint        TEXT    NOT NULL,
+    key_hash    TEXT    NOT NULL UNIQUE,
+    daily_quota INTEGER NOT NULL,
+    created_by  INTEGER NOT NULL REFERENCES users (id),
+    created_at  TEXT    NOT NULL,
+    revoked_at  TEXT
+);
+
+-- the number of requests each key made on each UTC day it was used
+CREATE TABLE api_key_usage (
+    api_key_id INTEGER NOT NULL REFERENCES api_keys (id),
+    date       TEXT    NOT NULL,
+    requests   INTEGER NOT NULL,
+    PRIMARY KEY (api_key_id, date)
+);
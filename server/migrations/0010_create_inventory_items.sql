@@ -0,0 +1,14 @@
+-- the paints each user owns. the same paint can show up more than once (ie, a fresh pot
+-- alongside one that's half used)
+CREATE TABLE inventory_items (
+    id           INTEGER PRIMARY KEY,
+    user_id      INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    paint_id     INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    quantity     INTEGER NOT NULL,
+    condition    TEXT    NOT NULL,
+    purchased_on TEXT,
+    notes        TEXT,
+    added_at     TEXT    NOT NULL
+);
+
+CREATE INDEX inventory_items_user ON inventory_items (user_id);
@@ -0,0 +1,9 @@
+-- the paints each user wants to buy. wanting the same paint again just wants more of it
+CREATE TABLE wishlist_items (
+    id       INTEGER PRIMARY KEY,
+    user_id  INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    paint_id INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL,
+    added_at TEXT    NOT NULL,
+    UNIQUE (user_id, paint_id)
+);
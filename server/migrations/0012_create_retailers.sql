@@ -0,0 +1,19 @@
+CREATE TABLE retailers (
+    id   INTEGER PRIMARY KEY,
+    name TEXT    NOT NULL,
+    url  TEXT
+);
+
+-- a price seen for a paint at a retailer. observations are never updated so the table
+-- doubles as the price history. prices are kept in hundredths of the currency's unit
+CREATE TABLE price_observations (
+    id          INTEGER PRIMARY KEY,
+    retailer_id INTEGER NOT NULL REFERENCES retailers (id) ON DELETE CASCADE,
+    paint_id    INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    price_cents INTEGER NOT NULL,
+    currency    TEXT    NOT NULL,
+    in_stock    INTEGER NOT NULL,
+    observed_at TEXT    NOT NULL
+);
+
+CREATE INDEX price_observations_paint ON price_observations (paint_id, observed_at);
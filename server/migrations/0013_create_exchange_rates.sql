@@ -0,0 +1,9 @@
+-- how many units of each currency a US dollar bought on a given day. every import adds
+-- the rates for its day so the history of each currency is kept, and prices are converted
+-- with the latest one
+CREATE TABLE exchange_rates (
+    currency     TEXT NOT NULL,
+    effective_on TEXT NOT NULL,
+    rate         REAL NOT NULL,
+    PRIMARY KEY (currency, effective_on)
+);
@@ -0,0 +1,33 @@
+-- a price someone is waiting for a paint to drop to. an alert fires when the cheapest
+-- price in stock crosses from above the threshold to at or below it, and is armed again
+-- once the price goes back up
+CREATE TABLE price_alerts (
+    id              INTEGER PRIMARY KEY,
+    user_id         INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    paint_id        INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    threshold_cents INTEGER NOT NULL,
+    currency        TEXT    NOT NULL,
+    created_at      TEXT    NOT NULL,
+    triggered_at    TEXT,
+    UNIQUE (user_id, paint_id)
+);
+
+CREATE INDEX price_alerts_paint ON price_alerts (paint_id);
+
+-- everything we have to tell users about. the table doubles as the outbox for sending
+-- them on, so a notification is only ever written here and delivered later
+CREATE TABLE notifications (
+    id           INTEGER PRIMARY KEY,
+    user_id      INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    paint_id     INTEGER REFERENCES paints (id) ON DELETE SET NULL,
+    title        TEXT    NOT NULL,
+    body         TEXT    NOT NULL,
+    created_at   TEXT    NOT NULL,
+    read_at      TEXT,
+    delivered_at TEXT,
+    attempts     INTEGER NOT NULL DEFAULT 0,
+    last_error   TEXT
+);
+
+CREATE INDEX notifications_user ON notifications (user_id, created_at);
+CREATE INDEX notifications_outbox ON notifications (delivered_at, attempts);
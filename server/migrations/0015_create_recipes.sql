@@ -0,0 +1,46 @@
+-- a color scheme someone has written up for painting a miniature, step by step
+CREATE TABLE recipes (
+    id          INTEGER PRIMARY KEY,
+    user_id     INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    title       TEXT    NOT NULL,
+    description TEXT,
+    video_id    INTEGER REFERENCES product_videos (id) ON DELETE SET NULL,
+    created_at  TEXT    NOT NULL,
+    updated_at  TEXT    NOT NULL
+);
+
+CREATE INDEX recipes_user ON recipes (user_id);
+CREATE INDEX recipes_video ON recipes (video_id);
+
+-- the steps of a recipe, numbered from 1 in the order they're painted
+CREATE TABLE recipe_steps (
+    recipe_id INTEGER NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    position  INTEGER NOT NULL,
+    stage     TEXT    NOT NULL,
+    technique TEXT,
+    notes     TEXT,
+    PRIMARY KEY (recipe_id, position)
+);
+
+-- the paints used in a step, in the order they're listed. a paint leaving the catalog
+-- drops out of the step but leaves the rest of the recipe alone
+CREATE TABLE recipe_step_paints (
+    recipe_id      INTEGER NOT NULL,
+    position       INTEGER NOT NULL,
+    paint_position INTEGER NOT NULL,
+    paint_id       INTEGER NOT NULL REFERENCES paints (id) ON DELETE CASCADE,
+    PRIMARY KEY (recipe_id, position, paint_id),
+    FOREIGN KEY (recipe_id, position) REFERENCES recipe_steps (recipe_id, position) ON DELETE CASCADE
+);
+
+CREATE INDEX recipe_step_paints_paint ON recipe_step_paints (paint_id);
+
+-- the miniatures and factions a recipe is meant for
+CREATE TABLE recipe_tags (
+    recipe_id INTEGER NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    kind      TEXT    NOT NULL,
+    name      TEXT    NOT NULL,
+    PRIMARY KEY (recipe_id, kind, name)
+);
+
+CREATE INDEX recipe_tags_name ON recipe_tags (kind, name);
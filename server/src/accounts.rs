@@ -0,0 +1,203 @@
+// external crates
+use chrono::NaiveDate;
+use std::sync::Arc;
+
+use crate::{db, errors, loaders::Loader};
+
+// the types that make up the domain
+mod api_keys;
+mod users;
+// how accounts prove who they are
+mod passwords;
+mod sessions;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::api_keys::*;
+pub use self::sessions::{Session, Sessions};
+pub use self::users::*;
+
+/// the operations a storage backend has to support in order to serve accounts
+pub trait Repository: Send + Sync {
+    /// the users with the matching ids, in the same order as the ids
+    fn users_by_id(&self, ids: &[i32]) -> Result<Vec<Option<User>>, db::Error>;
+
+    /// the user registered with the (already normalized) email address, if there is one
+    fn user_by_email(&self, email: &str) -> Result<Option<User>, db::Error>;
+
+    /// store a new user, ignoring its id, and return it with the id it was given
+    fn insert_user(&self, user: &User) -> Result<User, db::Error>;
+
+    /// overwrite the user with the same id, returning false if there isn't one
+    fn update_user(&self, user: &User) -> Result<bool, db::Error>;
+
+    /// every key that has been handed out, oldest first
+    fn all_api_keys(&self) -> Result<Vec<ApiKey>, db::Error>;
+
+    /// the keys with the matching ids, in the same order as the ids
+    fn api_keys_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ApiKey>>, db::Error>;
+
+    /// the key with the given hash, if there is one
+    fn api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, db::Error>;
+
+    /// store a new key, ignoring its id, and return it with the id it was given
+    fn insert_api_key(&self, api_key: &ApiKey) -> Result<ApiKey, db::Error>;
+
+    /// overwrite the key with the same id, returning false if there isn't one
+    fn update_api_key(&self, api_key: &ApiKey) -> Result<bool, db::Error>;
+
+    /// count a request made with the key on the given day unless it has already made
+    /// `quota` requests that day, returning whether the request was counted. checking and
+    /// counting have to happen together so concurrent requests can't sneak past the quota
+    fn count_api_key_use(&self, id: i32, date: NaiveDate, quota: i32) -> Result<bool, db::Error>;
+
+    /// the requests made with the key on each day since the given one, oldest first.
+    /// days without any requests are left out
+    fn api_key_usage(&self, id: i32, since: NaiveDate) -> Result<Vec<ApiKeyDailyUsage>, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    sessions: Sessions,
+    /// the session the request was made with, if it was made by someone signed in
+    session: Option<Session>,
+    /// the key the request was made with, if it was made with one
+    api_key: Option<ApiKey>,
+    users: Loader<i32, Option<User>>,
+    api_keys: Loader<i32, Option<ApiKey>>,
+}
+
+impl Client {
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        sessions: Sessions,
+        session: Option<Session>,
+        api_key: Option<ApiKey>,
+    ) -> Client {
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            users: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.users_by_id(ids)
+            }),
+            api_keys: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.api_keys_by_id(ids)
+            }),
+            repo,
+            sessions,
+            session,
+            api_key,
+        }
+    }
+
+    pub fn user(&self, id: i32) -> Result<Option<User>, db::Error> {
+        self.users.load(id)
+    }
+
+    /// the user making the request, if they're signed in
+    pub fn viewer(&self) -> Result<Option<User>, db::Error> {
+        match &self.session {
+            // the account might have gone away since the session was handed out
+            Some(session) => self.user(session.user_id),
+            None => Ok(None),
+        }
+    }
+
+    /// the user making the request, as long as they have at least the given role. anyone
+    /// else gets a FORBIDDEN error, whether they're signed in or not
+    pub fn authorize(&self, role: Role) -> Result<User, errors::Error> {
+        match self.viewer()? {
+            Some(user) if user.role >= role => Ok(user),
+            Some(_) => Err(errors::Error::Forbidden(format!(
+                "you need the {} role to do that",
+                role.as_str()
+            ))),
+            None => Err(errors::Error::Forbidden(
+                "you need to sign in to do that".to_string(),
+            )),
+        }
+    }
+
+    /// whether the user making the request is an admin
+    fn is_admin(&self) -> Result<bool, db::Error> {
+        Ok(self.viewer()?.map(|user| user.role) == Some(Role::Admin))
+    }
+
+    /// create an account and sign into it
+    pub fn register(&self, input: RegisterInput) -> Result<AuthPayload, errors::Error> {
+        let user = input.into_user(self)?;
+        let user = self.repo.insert_user(&user)?;
+
+        self.users.replace(user.id, Some(user.clone()));
+        Ok(self.sign_in(user))
+    }
+
+    /// sign into an existing account
+    pub fn log_in(&self, input: LogInInput) -> Result<AuthPayload, errors::Error> {
+        let user = self.repo.user_by_email(&normalize_email(&input.email))?;
+
+        // we don't let on whether it was the email or the password that was wrong
+        match user {
+            Some(user) if passwords::verify(&user.password_hash, &input.password) => {
+                self.users.prime(user.id, Some(user.clone()));
+                Ok(self.sign_in(user))
+            }
+            _ => Err(errors::Error::Unauthenticated(
+                "the email or password is incorrect".to_string(),
+            )),
+        }
+    }
+
+    /// change what a user is allowed to do on behalf of an admin
+    pub fn set_role(&self, admin: &User, id: i32, role: Role) -> Result<User, errors::Error> {
+        // someone has to be left who can hand the role back out
+        if id == admin.id && role != Role::Admin {
+            let mut validation = errors::Validation::new();
+            validation.add("role", "you can't take away your own admin role");
+            validation.finish()?;
+        }
+
+        let user = match self.user(id)? {
+            Some(user) => User { role, ..user },
+            None => return Err(errors::Error::NotFound(format!("user {}", id))),
+        };
+        if !self.repo.update_user(&user)? {
+            return Err(errors::Error::NotFound(format!("user {}", id)));
+        }
+
+        self.users.replace(id, Some(user.clone()));
+        Ok(user)
+    }
+
+    /// hand out a session for the user
+    fn sign_in(&self, user: User) -> AuthPayload {
+        AuthPayload {
+            token: self.sessions.issue(user.id),
+            viewer: Viewer { user },
+        }
+    }
+}
+
+/// make the user registered with the email address an admin, without checking who's asking.
+/// this is how the very first admin is handed out, from the command line
+pub fn grant_admin(repo: &dyn Repository, email: &str) -> Result<User, errors::Error> {
+    let user = match repo.user_by_email(&normalize_email(email))? {
+        Some(user) => User {
+            role: Role::Admin,
+            ..user
+        },
+        None => return Err(errors::Error::NotFound(format!("user {}", email.trim()))),
+    };
+    if !repo.update_user(&user)? {
+        return Err(errors::Error::NotFound(format!("user {}", email.trim())));
+    }
+
+    Ok(user)
+}
+
+/// the form we store and look up email addresses in
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
@@ -0,0 +1,435 @@
+// external crates
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use juniper::{FieldResult, ID};
+use sha2::{Digest, Sha256};
+
+use super::{Client, Repository, User};
+use crate::node::{self, Kind, Node};
+use crate::{api, db, errors};
+
+/// what every key starts with, so a key pasted somewhere it shouldn't be is easy to spot
+const KEY_PREFIX: &str = "pk_";
+
+/// how many random bytes go into a key
+const KEY_LENGTH: usize = 24;
+
+/// how much of a key we keep in the clear so admins can tell keys apart
+const HINT_LENGTH: usize = 10;
+
+/// the most days of usage we'll hand back at once
+const MAX_USAGE_DAYS: i32 = 90;
+
+/// lets a partner query the API without signing in as anyone. every key can make a
+/// limited number of requests a day, counted in UTC days.
+#[derive(Clone, Debug)]
+pub struct ApiKey {
+    pub id: i32,
+    /// who the key was handed out to
+    pub name: String,
+    /// the first few characters of the key
+    pub hint: String,
+    /// the SHA-256 hash of the key. keys are long and random so there's nothing to gain
+    /// from a slow hash, and a fast one lets us look keys up by it on every request
+    pub key_hash: String,
+    pub daily_quota: i32,
+    /// the admin that issued the key
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl ApiKey {
+    /// the globally unique identifier of the key
+    fn id(&self) -> ID {
+        node::global_id(Kind::ApiKey, self.id)
+    }
+
+    /// who the key was handed out to
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the first few characters of the key, to tell it apart from the others
+    fn hint(&self) -> &str {
+        &self.hint
+    }
+
+    /// how many requests the key can make in a day
+    fn dailyQuota(&self) -> i32 {
+        self.daily_quota
+    }
+
+    /// the admin that issued the key
+    fn createdBy(&self, context: &api::Context) -> FieldResult<Option<User>> {
+        Ok(context.accounts.user(self.created_by)?)
+    }
+
+    fn createdAt(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// when the key stopped working, if it has
+    fn revokedAt(&self) -> Option<DateTime<Utc>> {
+        self.revoked_at
+    }
+}
+
+connection!(ApiKeyConnection, ApiKeyEdge, ApiKey);
+
+/// the number of requests a key made on a single day
+#[derive(Clone, Debug)]
+pub struct ApiKeyDailyUsage {
+    pub api_key_id: i32,
+    pub date: NaiveDate,
+    pub requests: i32,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ApiKeyDailyUsage {
+    /// the UTC day the requests were made on
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    fn requests(&self) -> i32 {
+        self.requests
+    }
+}
+
+/// how much of its quota a key has been using
+pub struct ApiKeyUsage {
+    pub api_key: ApiKey,
+    /// every day in the period asked for, oldest first and ending today
+    pub days: Vec<ApiKeyDailyUsage>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ApiKeyUsage {
+    fn apiKey(&self) -> &ApiKey {
+        &self.api_key
+    }
+
+    /// how many requests the key can make in a day
+    fn dailyQuota(&self) -> i32 {
+        self.api_key.daily_quota
+    }
+
+    /// how many requests the key has made so far today
+    fn usedToday(&self) -> i32 {
+        self.used_today()
+    }
+
+    /// how many more requests the key can make today
+    fn remainingToday(&self) -> i32 {
+        (self.api_key.daily_quota - self.used_today()).max(0)
+    }
+
+    /// when the count for today starts over
+    fn resetsAt(&self) -> DateTime<Utc> {
+        // midnight always exists so this can't fail
+        let midnight = (today() + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap();
+        Utc.from_utc_datetime(&midnight)
+    }
+
+    /// the requests made on each day, oldest first and ending today
+    fn days(&self) -> &[ApiKeyDailyUsage] {
+        &self.days
+    }
+}
+
+impl ApiKeyUsage {
+    fn used_today(&self) -> i32 {
+        self.days.last().map(|day| day.requests).unwrap_or(0)
+    }
+}
+
+/// everything needed to hand out a key
+#[derive(juniper::GraphQLInputObject)]
+pub struct IssueApiKeyInput {
+    /// who the key is for
+    pub name: String,
+    /// how many requests the key can make in a day
+    pub daily_quota: i32,
+}
+
+/// a freshly issued key. this is the only time the key itself is ever shown
+pub struct IssuedApiKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl IssuedApiKey {
+    /// the key to send as the `X-Api-Key` header with every request. it can't be looked
+    /// up again so it has to be stored somewhere safe now
+    fn key(&self) -> &str {
+        &self.key
+    }
+
+    fn apiKey(&self) -> &ApiKey {
+        &self.api_key
+    }
+}
+
+/// what came of a request made with a key
+pub enum KeyUse {
+    /// the request was counted against the key's quota and can go ahead
+    Accepted(ApiKey),
+    /// we don't know the key or it has been revoked
+    Invalid,
+    /// the key has used up its quota for the day
+    OverQuota,
+}
+
+/// check the key a request was made with and count the request against its quota
+pub fn use_key(repo: &dyn Repository, key: &str) -> Result<KeyUse, db::Error> {
+    let api_key = match repo.api_key_by_hash(&hash(key.trim()))? {
+        Some(api_key) if api_key.revoked_at.is_none() => api_key,
+        _ => return Ok(KeyUse::Invalid),
+    };
+
+    if repo.count_api_key_use(api_key.id, today(), api_key.daily_quota)? {
+        Ok(KeyUse::Accepted(api_key))
+    } else {
+        Ok(KeyUse::OverQuota)
+    }
+}
+
+impl IssueApiKeyInput {
+    /// check the input over and turn it into a key for the admin, along with the key itself
+    pub(super) fn into_api_key(self, admin: &User) -> Result<(String, ApiKey), errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("name", &self.name);
+        if self.daily_quota < 1 {
+            validation.add("dailyQuota", "must be at least 1");
+        }
+        validation.finish()?;
+
+        let bytes: [u8; KEY_LENGTH] = rand::random();
+        let key = format!(
+            "{}{}",
+            KEY_PREFIX,
+            base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)
+        );
+
+        Ok((
+            key.clone(),
+            ApiKey {
+                id: 0,
+                name: self.name.trim().to_string(),
+                hint: key[..HINT_LENGTH].to_string(),
+                key_hash: hash(&key),
+                daily_quota: self.daily_quota,
+                created_by: admin.id,
+                created_at: Utc::now(),
+                revoked_at: None,
+            },
+        ))
+    }
+}
+
+impl Client {
+    /// a key by its id. keys are only visible to admins and to requests made with the key
+    pub fn api_key(&self, id: i32) -> Result<Option<ApiKey>, db::Error> {
+        let own_key = self.api_key.as_ref().map(|key| key.id) == Some(id);
+        if !own_key && !self.is_admin()? {
+            return Ok(None);
+        }
+
+        self.api_keys.load(id)
+    }
+
+    /// the key the request was made with, if there was one
+    pub fn request_api_key(&self) -> Option<&ApiKey> {
+        self.api_key.as_ref()
+    }
+
+    /// every key that has been handed out, oldest first
+    pub fn all_api_keys(&self) -> Result<Vec<ApiKey>, db::Error> {
+        let keys = self.repo.all_api_keys()?;
+        for key in &keys {
+            self.api_keys.prime(key.id, Some(key.clone()));
+        }
+
+        Ok(keys)
+    }
+
+    /// hand out a new key on behalf of an admin
+    pub fn issue_api_key(
+        &self,
+        admin: &User,
+        input: IssueApiKeyInput,
+    ) -> Result<IssuedApiKey, errors::Error> {
+        let (key, api_key) = input.into_api_key(admin)?;
+        let api_key = self.repo.insert_api_key(&api_key)?;
+
+        self.api_keys.replace(api_key.id, Some(api_key.clone()));
+        Ok(IssuedApiKey { key, api_key })
+    }
+
+    /// stop a key from working. revoking a key twice leaves it as it was
+    pub fn revoke_api_key(&self, id: i32) -> Result<ApiKey, errors::Error> {
+        let api_key = match self.api_keys.load(id)? {
+            Some(api_key) if api_key.revoked_at.is_some() => return Ok(api_key),
+            Some(api_key) => ApiKey {
+                revoked_at: Some(Utc::now()),
+                ..api_key
+            },
+            None => return Err(errors::Error::NotFound(format!("api key {}", id))),
+        };
+        if !self.repo.update_api_key(&api_key)? {
+            return Err(errors::Error::NotFound(format!("api key {}", id)));
+        }
+
+        self.api_keys.replace(id, Some(api_key.clone()));
+        Ok(api_key)
+    }
+
+    /// how many requests the key made on each of the last few days, ending today
+    pub fn api_key_usage(&self, api_key: ApiKey, days: i32) -> Result<ApiKeyUsage, errors::Error> {
+        let mut validation = errors::Validation::new();
+        if !(1..=MAX_USAGE_DAYS).contains(&days) {
+            validation.add("days", &format!("must be between 1 and {}", MAX_USAGE_DAYS));
+        }
+        validation.finish()?;
+
+        let since = today() - Duration::days(i64::from(days) - 1);
+        let counted = self.repo.api_key_usage(api_key.id, since)?;
+
+        // days without any requests aren't stored but still belong in the list
+        let days = (0..i64::from(days))
+            .map(|offset| since + Duration::days(offset))
+            .map(|date| ApiKeyDailyUsage {
+                api_key_id: api_key.id,
+                date,
+                requests: counted
+                    .iter()
+                    .find(|usage| usage.date == date)
+                    .map(|usage| usage.requests)
+                    .unwrap_or(0),
+            })
+            .collect();
+
+        Ok(ApiKeyUsage { api_key, days })
+    }
+}
+
+/// the form we store keys in
+fn hash(key: &str) -> String {
+    base64::encode_config(&Sha256::digest(key.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+/// quotas reset at midnight UTC
+fn today() -> NaiveDate {
+    Utc::now().naive_utc().date()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::Role;
+    use crate::storage;
+
+    /// both backends, since the quota is counted in a single statement in sqlite and under
+    /// a lock in memory. the database is a fresh file named after the test
+    fn backends(test: &str) -> Vec<storage::Backend> {
+        let path =
+            std::env::temp_dir().join(format!("paint-server-{}-{}.db", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = db::connect(path.to_str().unwrap()).unwrap();
+
+        vec![storage::Backend::memory(), storage::Backend::Sqlite(pool)]
+    }
+
+    /// a key with the quota, handed out by a freshly registered admin
+    fn issued(backend: &storage::Backend, daily_quota: i32) -> (String, ApiKey) {
+        let repo = backend.accounts();
+        let admin = repo
+            .insert_user(&User {
+                id: 0,
+                email: "admin@example.com".to_string(),
+                display_name: "Admin".to_string(),
+                password_hash: String::new(),
+                role: Role::Admin,
+                created_at: Utc::now(),
+            })
+            .unwrap();
+        let input = IssueApiKeyInput {
+            name: "Shop Sync".to_string(),
+            daily_quota,
+        };
+        let (key, api_key) = input.into_api_key(&admin).unwrap();
+
+        (key, repo.insert_api_key(&api_key).unwrap())
+    }
+
+    #[test]
+    fn requests_past_the_quota_are_refused() {
+        for backend in backends("quota") {
+            let repo = backend.accounts();
+            let (key, _) = issued(&backend, 2);
+
+            assert!(matches!(
+                use_key(&*repo, &key).unwrap(),
+                KeyUse::Accepted(_)
+            ));
+            assert!(matches!(
+                use_key(&*repo, &key).unwrap(),
+                KeyUse::Accepted(_)
+            ));
+            assert!(matches!(use_key(&*repo, &key).unwrap(), KeyUse::OverQuota));
+            assert!(matches!(use_key(&*repo, &key).unwrap(), KeyUse::OverQuota));
+        }
+    }
+
+    #[test]
+    fn quotas_reset_the_next_day() {
+        for backend in backends("reset") {
+            let repo = backend.accounts();
+            let (_, api_key) = issued(&backend, 1);
+            let today = today();
+            let tomorrow = today + Duration::days(1);
+
+            assert!(repo.count_api_key_use(api_key.id, today, 1).unwrap());
+            assert!(!repo.count_api_key_use(api_key.id, today, 1).unwrap());
+            assert!(repo.count_api_key_use(api_key.id, tomorrow, 1).unwrap());
+            assert!(!repo.count_api_key_use(api_key.id, tomorrow, 1).unwrap());
+
+            // the refused requests aren't counted
+            let usage = repo.api_key_usage(api_key.id, today).unwrap();
+            let days: Vec<(NaiveDate, i32)> =
+                usage.iter().map(|day| (day.date, day.requests)).collect();
+            assert_eq!(days, vec![(today, 1), (tomorrow, 1)]);
+        }
+    }
+
+    #[test]
+    fn revoked_keys_are_rejected() {
+        for backend in backends("revoked") {
+            let repo = backend.accounts();
+            let (key, api_key) = issued(&backend, 10);
+            let revoked = ApiKey {
+                revoked_at: Some(Utc::now()),
+                ..api_key
+            };
+            assert!(repo.update_api_key(&revoked).unwrap());
+
+            assert!(matches!(use_key(&*repo, &key).unwrap(), KeyUse::Invalid));
+            assert!(matches!(
+                use_key(&*repo, "pk_made_up").unwrap(),
+                KeyUse::Invalid
+            ));
+            assert!(repo.api_key_usage(revoked.id, today()).unwrap().is_empty());
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// external crates
+use chrono::NaiveDate;
+
+use super::{ApiKey, ApiKeyDailyUsage, Repository, User};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn users_by_id(&self, ids: &[i32]) -> Result<Vec<Option<User>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.users.iter().find(|user| user.id == *id).cloned())
+            .collect())
+    }
+
+    fn user_by_email(&self, email: &str) -> Result<Option<User>, db::Error> {
+        Ok(self.read().users.iter().find(|user| user.email == email).cloned())
+    }
+
+    fn insert_user(&self, user: &User) -> Result<User, db::Error> {
+        let mut tables = self.write();
+
+        let user = User {
+            id: memory::next_id(tables.users.iter().map(|user| user.id)),
+            ..user.clone()
+        };
+        tables.users.push(user.clone());
+
+        Ok(user)
+    }
+
+    fn update_user(&self, user: &User) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().users, user, |user| user.id))
+    }
+
+    fn all_api_keys(&self) -> Result<Vec<ApiKey>, db::Error> {
+        Ok(self.read().api_keys.clone())
+    }
+
+    fn api_keys_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ApiKey>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.api_keys.iter().find(|api_key| api_key.id == *id).cloned())
+            .collect())
+    }
+
+    fn api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, db::Error> {
+        Ok(self
+            .read()
+            .api_keys
+            .iter()
+            .find(|api_key| api_key.key_hash == key_hash)
+            .cloned())
+    }
+
+    fn insert_api_key(&self, api_key: &ApiKey) -> Result<ApiKey, db::Error> {
+        let mut tables = self.write();
+
+        let api_key = ApiKey {
+            id: memory::next_id(tables.api_keys.iter().map(|api_key| api_key.id)),
+            ..api_key.clone()
+        };
+        tables.api_keys.push(api_key.clone());
+
+        Ok(api_key)
+    }
+
+    fn update_api_key(&self, api_key: &ApiKey) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().api_keys, api_key, |api_key| api_key.id))
+    }
+
+    fn count_api_key_use(&self, id: i32, date: NaiveDate, quota: i32) -> Result<bool, db::Error> {
+        // holding the write lock keeps concurrent requests from both taking the last one
+        let mut tables = self.write();
+
+        let usage = tables
+            .api_key_usage
+            .iter_mut()
+            .find(|usage| usage.api_key_id == id && usage.date == date);
+        match usage {
+            Some(usage) if usage.requests >= quota => Ok(false),
+            Some(usage) => {
+                usage.requests += 1;
+                Ok(true)
+            }
+            None => {
+                tables.api_key_usage.push(ApiKeyDailyUsage {
+                    api_key_id: id,
+                    date,
+                    requests: 1,
+                });
+                Ok(true)
+            }
+        }
+    }
+
+    fn api_key_usage(&self, id: i32, since: NaiveDate) -> Result<Vec<ApiKeyDailyUsage>, db::Error> {
+        let mut usage: Vec<ApiKeyDailyUsage> = self
+            .read()
+            .api_key_usage
+            .iter()
+            .filter(|usage| usage.api_key_id == id && usage.date >= since)
+            .cloned()
+            .collect();
+        usage.sort_by_key(|usage| usage.date);
+
+        Ok(usage)
+    }
+}
@@ -0,0 +1,44 @@
+// external crates
+use argon2::{Config, Variant};
+
+/// how many random bytes go into the salt of every hash
+const SALT_LENGTH: usize = 16;
+
+/// hash a password with Argon2id and a fresh salt. the result is in the standard encoded
+/// form so it carries the parameters it was made with and can be checked later on even
+/// if we change them
+pub fn hash(password: &str) -> String {
+    let salt: [u8; SALT_LENGTH] = rand::random();
+    let config = Config {
+        variant: Variant::Argon2id,
+        ..Config::default()
+    };
+
+    // the default parameters are always valid so this can't fail
+    argon2::hash_encoded(password.as_bytes(), &salt, &config).expect("could not hash password")
+}
+
+/// whether the password is the one the hash was made from
+pub fn verify(hash: &str, password: &str) -> bool {
+    argon2::verify_encoded(hash, password.as_bytes()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_check_the_password() {
+        let hashed = hash("correct horse battery staple");
+
+        assert!(hashed.starts_with("$argon2id$"));
+        assert!(verify(&hashed, "correct horse battery staple"));
+        assert!(!verify(&hashed, "correct horse battery stapler"));
+        assert!(!verify("not a hash", "correct horse battery staple"));
+    }
+
+    #[test]
+    fn hashes_are_salted() {
+        assert_ne!(hash("hunter2"), hash("hunter2"));
+    }
+}
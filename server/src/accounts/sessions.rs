@@ -0,0 +1,148 @@
+// external crates
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+
+/// how long someone stays signed in before they have to log in again
+const SESSION_DAYS: i64 = 30;
+
+/// how many random bytes go into a key we make up ourselves
+const KEY_LENGTH: usize = 32;
+
+/// a signed in user, as vouched for by a token we handed out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Session {
+    pub user_id: i32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// hands out and checks session tokens. a token is the user's id and when the session
+/// ends, signed with HMAC-SHA256 so nobody can make one up or change it. nothing is kept
+/// on the server so sessions survive restarts as long as the key stays the same.
+#[derive(Clone)]
+pub struct Sessions {
+    key: Arc<Vec<u8>>,
+}
+
+impl Sessions {
+    /// sign sessions with the given secret
+    pub fn new(secret: &[u8]) -> Sessions {
+        Sessions {
+            key: Arc::new(secret.to_vec()),
+        }
+    }
+
+    /// sign sessions with a key that only lives as long as the process, which signs
+    /// everyone out on every restart
+    pub fn ephemeral() -> Sessions {
+        let key: [u8; KEY_LENGTH] = rand::random();
+        Sessions::new(&key)
+    }
+
+    /// a token for a new session for the user
+    pub fn issue(&self, user_id: i32) -> String {
+        let expires_at = Utc::now() + Duration::days(SESSION_DAYS);
+        self.sign(&format!("{}:{}", user_id, expires_at.timestamp()))
+    }
+
+    /// the session a token stands for, if we signed it and it hasn't ended yet
+    pub fn verify(&self, token: &str) -> Option<Session> {
+        let mut parts = token.trim().splitn(2, '.');
+        let payload = decode(parts.next()?)?;
+        let signature = decode(parts.next()?)?;
+        // the comparison takes the same time however much of the signature is right
+        self.mac(&payload).verify(&signature).ok()?;
+
+        let payload = String::from_utf8(payload).ok()?;
+        let mut fields = payload.splitn(2, ':');
+        let user_id = fields.next()?.parse().ok()?;
+        let expires_at = Utc
+            .timestamp_opt(fields.next()?.parse().ok()?, 0)
+            .single()?;
+        if expires_at <= Utc::now() {
+            return None;
+        }
+
+        Some(Session { user_id, expires_at })
+    }
+
+    /// the payload along with its signature, ready to hand out
+    fn sign(&self, payload: &str) -> String {
+        format!(
+            "{}.{}",
+            encode(payload.as_bytes()),
+            encode(&self.mac(payload.as_bytes()).result().code())
+        )
+    }
+
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        // HMAC takes keys of any length
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.key).expect("invalid key length");
+        mac.input(payload);
+        mac
+    }
+}
+
+/// tokens end up in headers so they stick to characters that are safe there
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    base64::decode_config(text, base64::URL_SAFE_NO_PAD).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions() -> Sessions {
+        Sessions::new(b"a secret that is long enough to sign with")
+    }
+
+    #[test]
+    fn issued_tokens_verify() {
+        let sessions = sessions();
+        let session = sessions.verify(&sessions.issue(42)).unwrap();
+
+        assert_eq!(session.user_id, 42);
+        assert!(session.expires_at > Utc::now() + Duration::days(SESSION_DAYS - 1));
+    }
+
+    #[test]
+    fn tampered_tokens_are_rejected() {
+        let sessions = sessions();
+        let token = sessions.issue(42);
+        let (payload, signature) = token.split_at(token.find('.').unwrap());
+
+        // someone else's id under our signature
+        let forged = String::from_utf8(decode(payload).unwrap()).unwrap();
+        let forged = encode(forged.replacen("42", "1", 1).as_bytes());
+        let forged = format!("{}{}", forged, signature);
+        assert_eq!(sessions.verify(&forged), None);
+
+        // a signature that's been fiddled with
+        let mut bytes = decode(&signature[1..]).unwrap();
+        bytes[0] ^= 1;
+        let forged = format!("{}.{}", payload, encode(&bytes));
+        assert_eq!(sessions.verify(&forged), None);
+
+        // a token signed with another key
+        let other = Sessions::new(b"some other secret that is just as long");
+        assert_eq!(sessions.verify(&other.issue(42)), None);
+        assert_eq!(sessions.verify("not a token"), None);
+    }
+
+    #[test]
+    fn expired_tokens_are_rejected() {
+        let sessions = sessions();
+        let ended = Utc::now() - Duration::seconds(1);
+        let expired = sessions.sign(&format!("42:{}", ended.timestamp()));
+        assert_eq!(sessions.verify(&expired), None);
+
+        // a time too far out to represent doesn't take the server down
+        let endless = sessions.sign(&format!("42:{}", i64::MAX));
+        assert_eq!(sessions.verify(&endless), None);
+    }
+}
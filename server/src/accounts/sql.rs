@@ -0,0 +1,203 @@
+// external crates
+use chrono::NaiveDate;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, OptionalExtension, Row, NO_PARAMS};
+
+use super::{ApiKey, ApiKeyDailyUsage, Repository, Role, User};
+use crate::db;
+
+/// the columns we need to select in order to build a User
+const USER_COLUMNS: &str = "id, email, display_name, password_hash, role, created_at";
+
+impl Repository for db::Pool {
+    fn users_by_id(&self, ids: &[i32]) -> Result<Vec<Option<User>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM users WHERE id IN ({{keys}})", USER_COLUMNS),
+            ids,
+            user_from_row,
+            |user| user.id,
+        )
+    }
+
+    fn user_by_email(&self, email: &str) -> Result<Option<User>, db::Error> {
+        let conn = self.get()?;
+        let user = conn
+            .query_row(
+                &format!("SELECT {} FROM users WHERE email = ?", USER_COLUMNS),
+                &[email],
+                user_from_row,
+            )
+            .optional()?;
+
+        Ok(user)
+    }
+
+    fn insert_user(&self, user: &User) -> Result<User, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO users (email, display_name, password_hash, role, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![user.email, user.display_name, user.password_hash, user.role, user.created_at],
+        )?;
+
+        Ok(User {
+            id: conn.last_insert_rowid() as i32,
+            ..user.clone()
+        })
+    }
+
+    fn update_user(&self, user: &User) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE users SET email = ?, display_name = ?, password_hash = ?, role = ? WHERE id = ?",
+            params![user.email, user.display_name, user.password_hash, user.role, user.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn all_api_keys(&self) -> Result<Vec<ApiKey>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!("SELECT {} FROM api_keys ORDER BY id", API_KEY_COLUMNS))?;
+        let keys = statement
+            .query_map(NO_PARAMS, api_key_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(keys)
+    }
+
+    fn api_keys_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ApiKey>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM api_keys WHERE id IN ({{keys}})", API_KEY_COLUMNS),
+            ids,
+            api_key_from_row,
+            |api_key| api_key.id,
+        )
+    }
+
+    fn api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, db::Error> {
+        let conn = self.get()?;
+        let api_key = conn
+            .query_row(
+                &format!("SELECT {} FROM api_keys WHERE key_hash = ?", API_KEY_COLUMNS),
+                &[key_hash],
+                api_key_from_row,
+            )
+            .optional()?;
+
+        Ok(api_key)
+    }
+
+    fn insert_api_key(&self, api_key: &ApiKey) -> Result<ApiKey, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO api_keys
+                 (name, hint, key_hash, daily_quota, created_by, created_at, revoked_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                api_key.name,
+                api_key.hint,
+                api_key.key_hash,
+                api_key.daily_quota,
+                api_key.created_by,
+                api_key.created_at,
+                api_key.revoked_at,
+            ],
+        )?;
+
+        Ok(ApiKey {
+            id: conn.last_insert_rowid() as i32,
+            ..api_key.clone()
+        })
+    }
+
+    fn update_api_key(&self, api_key: &ApiKey) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE api_keys SET name = ?, daily_quota = ?, revoked_at = ? WHERE id = ?",
+            params![api_key.name, api_key.daily_quota, api_key.revoked_at, api_key.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn count_api_key_use(&self, id: i32, date: NaiveDate, quota: i32) -> Result<bool, db::Error> {
+        // the upsert only bumps the count while it's under the quota, and sqlite runs the
+        // whole statement at once so two requests can't both take the last one
+        let changed = self.get()?.execute(
+            "INSERT INTO api_key_usage (api_key_id, date, requests) VALUES (?1, ?2, 1)
+             ON CONFLICT (api_key_id, date) DO UPDATE SET requests = requests + 1
+             WHERE requests < ?3",
+            params![id, date, quota],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn api_key_usage(&self, id: i32, since: NaiveDate) -> Result<Vec<ApiKeyDailyUsage>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(
+            "SELECT api_key_id, date, requests FROM api_key_usage
+             WHERE api_key_id = ? AND date >= ?
+             ORDER BY date",
+        )?;
+        let usage = statement
+            .query_map(params![id, since], |row| {
+                Ok(ApiKeyDailyUsage {
+                    api_key_id: row.get(0)?,
+                    date: row.get(1)?,
+                    requests: row.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(usage)
+    }
+}
+
+/// the columns we need to select in order to build an ApiKey
+const API_KEY_COLUMNS: &str =
+    "id, name, hint, key_hash, daily_quota, created_by, created_at, revoked_at";
+
+/// build a user out of a row containing USER_COLUMNS
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        email: row.get(1)?,
+        display_name: row.get(2)?,
+        password_hash: row.get(3)?,
+        role: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// build a key out of a row containing API_KEY_COLUMNS
+fn api_key_from_row(row: &Row) -> rusqlite::Result<ApiKey> {
+    Ok(ApiKey {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        hint: row.get(2)?,
+        key_hash: row.get(3)?,
+        daily_quota: row.get(4)?,
+        created_by: row.get(5)?,
+        created_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+    })
+}
+
+impl ToSql for Role {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Role {
+    fn column_result(value: ValueRef) -> FromSqlResult<Role> {
+        match value.as_str()? {
+            "user" => Ok(Role::User),
+            "curator" => Ok(Role::Curator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
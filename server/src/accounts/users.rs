@@ -0,0 +1,246 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use super::{normalize_email, passwords, Client};
+use crate::node::{self, Kind, Node};
+use crate::pagination::{paginate, Page};
+use crate::{alerts, api, errors, inventory};
+
+/// the fewest characters we accept in a password
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// what a user is allowed to do. each role can do everything the ones before it can
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// keeps track of their own paints
+    User,
+    /// also edits the catalog
+    Curator,
+    /// also decides what everyone else is allowed to do
+    Admin,
+}
+
+impl Role {
+    /// the name we use for the role when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Curator => "curator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// someone with an account
+#[derive(Clone, Debug)]
+pub struct User {
+    pub id: i32,
+    pub email: String,
+    pub display_name: String,
+    /// the Argon2 hash of the user's password, in its encoded form
+    pub password_hash: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl User {
+    /// the globally unique identifier of the user
+    fn id(&self) -> ID {
+        node::global_id(Kind::User, self.id)
+    }
+
+    /// the name the user goes by
+    fn displayName(&self) -> &str {
+        &self.display_name
+    }
+
+    /// what the user is allowed to do
+    fn role(&self) -> Role {
+        self.role
+    }
+
+    /// when the user signed up
+    fn createdAt(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+/// the user making the request, along with everything only they get to see
+pub struct Viewer {
+    pub user: User,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl Viewer {
+    /// the account the viewer is signed into
+    fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// the email address the viewer signs in with
+    fn email(&self) -> &str {
+        &self.user.email
+    }
+
+    /// the paints the viewer owns, in the order they were added and a page at a time
+    fn inventory(
+        &self,
+        context: &api::Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<inventory::InventoryItemConnection> {
+        let page = Page::new(first, after, last, before);
+        let items = context.inventory.items_for_user(self.user.id, &context.products)?;
+        Ok(paginate(items, "inventoryItem", |item| item.id, page)?.into())
+    }
+
+    /// the paints the viewer wants to buy, in the order they were added and a page at a time
+    fn wishlist(
+        &self,
+        context: &api::Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<inventory::WishlistItemConnection> {
+        let page = Page::new(first, after, last, before);
+        let items = context.inventory.wishlist_for_user(self.user.id, &context.products)?;
+        Ok(paginate(items, "wishlistItem", |item| item.id, page)?.into())
+    }
+
+    /// what the viewer has to buy to get everything on their wishlist, with every price
+    /// converted into a single currency if one is given. it can also be downloaded as text
+    /// or CSV from /shopping-list
+    fn shoppingList(
+        &self,
+        context: &api::Context,
+        currency: Option<String>,
+    ) -> FieldResult<inventory::ShoppingList> {
+        let list = context.inventory.shopping_list(
+            &self.user,
+            &context.products,
+            &context.pricing,
+            &context.currency,
+        )?;
+        match currency {
+            Some(currency) => Ok(list.in_currency(&context.currency, &currency)?),
+            None => Ok(list),
+        }
+    }
+
+    /// the prices the viewer is waiting for paints to drop to, oldest first and a page at
+    /// a time
+    fn priceAlerts(
+        &self,
+        context: &api::Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<alerts::PriceAlertConnection> {
+        let page = Page::new(first, after, last, before);
+        let alerts = context.alerts.alerts_for_user(self.user.id, &context.products)?;
+        Ok(paginate(alerts, "priceAlert", |alert| alert.id, page)?.into())
+    }
+
+    /// everything the viewer has been told about, newest first and a page at a time. they
+    /// can be narrowed down to the ones the viewer hasn't read yet
+    fn notifications(
+        &self,
+        context: &api::Context,
+        unread: Option<bool>,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<alerts::NotificationConnection> {
+        let page = Page::new(first, after, last, before);
+        let mut notifications = context.alerts.notifications_for_user(self.user.id)?;
+        if unread.unwrap_or(false) {
+            notifications.retain(|notification| notification.read_at.is_none());
+        }
+        Ok(paginate(notifications, "notification", |notification| notification.id, page)?.into())
+    }
+
+    /// how many notifications the viewer hasn't read yet
+    fn unreadNotificationCount(&self, context: &api::Context) -> FieldResult<i32> {
+        let notifications = context.alerts.notifications_for_user(self.user.id)?;
+        Ok(notifications.iter().filter(|notification| notification.read_at.is_none()).count() as i32)
+    }
+}
+
+/// a freshly signed in user along with the token that proves it
+pub struct AuthPayload {
+    pub token: String,
+    pub viewer: Viewer,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl AuthPayload {
+    /// the session token to send back as `Authorization: Bearer <token>` with every request
+    fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// the user that signed in
+    fn viewer(&self) -> &Viewer {
+        &self.viewer
+    }
+}
+
+/// everything needed to create an account
+#[derive(juniper::GraphQLInputObject)]
+pub struct RegisterInput {
+    pub email: String,
+    pub password: String,
+    pub display_name: String,
+}
+
+/// the credentials of an existing account
+#[derive(juniper::GraphQLInputObject)]
+pub struct LogInInput {
+    pub email: String,
+    pub password: String,
+}
+
+impl RegisterInput {
+    /// check the input over and turn it into a user that hasn't been stored yet
+    pub(super) fn into_user(self, client: &Client) -> Result<User, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let email = normalize_email(&self.email);
+        if !email.contains('@') || email.contains(char::is_whitespace) {
+            validation.add("email", "must be an email address");
+        } else if client.repo.user_by_email(&email)?.is_some() {
+            validation.add("email", "is already registered");
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LENGTH {
+            validation.add(
+                "password",
+                &format!("must be at least {} characters long", MIN_PASSWORD_LENGTH),
+            );
+        }
+        validation.require("displayName", &self.display_name);
+        validation.finish()?;
+
+        Ok(User {
+            id: 0,
+            email,
+            display_name: self.display_name.trim().to_string(),
+            password_hash: passwords::hash(&self.password),
+            // the first admin is handed out from the command line, see `grant_admin`
+            role: Role::User,
+            created_at: Utc::now(),
+        })
+    }
+}
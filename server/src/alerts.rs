@@ -0,0 +1,419 @@
+// external crates
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::accounts::User;
+use crate::{currency, db, errors, loaders::Loader, pricing, products};
+
+// the types that make up the domain
+mod delivery;
+mod notifications;
+mod price_alerts;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::delivery::*;
+pub use self::notifications::*;
+pub use self::price_alerts::*;
+
+/// the operations a storage backend has to support in order to serve alerts and
+/// notifications
+pub trait Repository: Send + Sync {
+    /// the alerts with the matching ids, in the same order as the ids
+    fn alerts_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceAlert>>, db::Error>;
+
+    /// every alert set by each of the users (oldest first), in the same order as the user ids
+    fn alerts_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<PriceAlert>>, db::Error>;
+
+    /// every alert waiting on the paint
+    fn alerts_for_paint(&self, paint_id: i32) -> Result<Vec<PriceAlert>, db::Error>;
+
+    /// store a new alert, ignoring its id, and return it with the id it was given
+    fn insert_alert(&self, alert: &PriceAlert) -> Result<PriceAlert, db::Error>;
+
+    /// overwrite the alert with the same id, returning false if there isn't one
+    fn update_alert(&self, alert: &PriceAlert) -> Result<bool, db::Error>;
+
+    /// remove the alert with the id, returning false if there isn't one
+    fn delete_alert(&self, id: i32) -> Result<bool, db::Error>;
+
+    /// the notifications with the matching ids, in the same order as the ids
+    fn notifications_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Notification>>, db::Error>;
+
+    /// every notification for each of the users (newest first), in the same order as the
+    /// user ids
+    fn notifications_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<Notification>>, db::Error>;
+
+    /// store a new notification, ignoring its id, and return it with the id it was given
+    fn insert_notification(&self, notification: &Notification) -> Result<Notification, db::Error>;
+
+    /// note when the user first saw the notification, leaving it alone if they'd already
+    /// seen it. returns false if there isn't one
+    fn mark_notification_read(&self, id: i32, read_at: DateTime<Utc>) -> Result<bool, db::Error>;
+
+    /// count an attempt at delivering the notification, along with when it went through or
+    /// why it didn't. returns false if there isn't one
+    fn record_delivery_attempt(
+        &self,
+        id: i32,
+        delivered_at: Option<DateTime<Utc>>,
+        error: Option<&str>,
+    ) -> Result<bool, db::Error>;
+
+    /// the oldest notifications that haven't been delivered yet and haven't used up their
+    /// attempts
+    fn undelivered_notifications(&self, max_attempts: i32, limit: i32) -> Result<Vec<Notification>, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    alerts: Loader<i32, Option<PriceAlert>>,
+    alerts_by_user: Loader<i32, Vec<PriceAlert>>,
+    notifications: Loader<i32, Option<Notification>>,
+    notifications_by_user: Loader<i32, Vec<Notification>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>) -> Client {
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            alerts: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.alerts_by_id(ids)
+            }),
+            alerts_by_user: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.alerts_by_user(ids)
+            }),
+            notifications: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.notifications_by_id(ids)
+            }),
+            notifications_by_user: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.notifications_by_user(ids)
+            }),
+            repo,
+        }
+    }
+
+    /// an alert by its id, as long as it belongs to the user
+    pub fn alert(&self, owner: &User, id: i32) -> Result<Option<PriceAlert>, db::Error> {
+        Ok(self.alerts.load(id)?.filter(|alert| alert.user_id == owner.id))
+    }
+
+    /// every alert the user has set, oldest first
+    pub fn alerts_for_user(
+        &self,
+        user_id: i32,
+        products: &products::Client,
+    ) -> Result<Vec<PriceAlert>, db::Error> {
+        let alerts = self.alerts_by_user.load(user_id)?;
+
+        // the paints are usually the next thing asked for so we want them in a single batch
+        products.expect_paints(alerts.iter().map(|alert| alert.paint_id));
+
+        Ok(alerts)
+    }
+
+    /// wait for a paint to drop to a price. setting an alert for a paint that already has
+    /// one changes its threshold instead
+    pub fn set_alert(
+        &self,
+        owner: &User,
+        input: PriceAlertInput,
+        products: &products::Client,
+        pricing: &pricing::Client,
+        rates: &currency::Client,
+    ) -> Result<PriceAlert, errors::Error> {
+        let (paint_id, threshold) = input.validate(products)?;
+
+        let existing = self
+            .alerts_for_user(owner.id, products)?
+            .into_iter()
+            .find(|alert| alert.paint_id == paint_id);
+        let alert = PriceAlert {
+            id: existing.as_ref().map_or(0, |alert| alert.id),
+            user_id: owner.id,
+            paint_id,
+            threshold,
+            created_at: existing.as_ref().map_or_else(Utc::now, |alert| alert.created_at),
+            triggered_at: None,
+        };
+        // a price that's already low enough doesn't cross the threshold so the alert starts
+        // off fired and waits for the price to go back up
+        let triggered_at = match cheapest_price(&alert, pricing, rates)? {
+            Some(price) if price.cents <= alert.threshold.cents => Some(Utc::now()),
+            _ => None,
+        };
+        let alert = PriceAlert { triggered_at, ..alert };
+
+        let alert = match existing {
+            Some(_) => {
+                self.repo.update_alert(&alert)?;
+                alert
+            }
+            None => self.repo.insert_alert(&alert)?,
+        };
+
+        self.alerts.replace(alert.id, Some(alert.clone()));
+        self.alerts_by_user.clear();
+        Ok(alert)
+    }
+
+    /// stop waiting for a paint's price to drop
+    pub fn remove_alert(&self, owner: &User, id: i32) -> Result<(), errors::Error> {
+        if self.alert(owner, id)?.is_none() || !self.repo.delete_alert(id)? {
+            return Err(errors::Error::NotFound(format!("price alert {}", id)));
+        }
+
+        self.alerts.replace(id, None);
+        self.alerts_by_user.clear();
+        Ok(())
+    }
+
+    /// fire the alerts on a paint whose cheapest price just crossed their threshold, and
+    /// arm the ones whose price went back up. every alert that fires leaves a notification
+    /// in the outbox
+    pub fn check_paint(
+        &self,
+        paint_id: i32,
+        products: &products::Client,
+        pricing: &pricing::Client,
+        rates: &currency::Client,
+    ) -> Result<Vec<Notification>, db::Error> {
+        let paint = match products.paint(paint_id)? {
+            Some(paint) => paint,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut notifications = Vec::new();
+        for alert in self.repo.alerts_for_paint(paint_id)? {
+            let price = match cheapest_price(&alert, pricing, rates)? {
+                Some(price) => price,
+                // there's nothing to compare against until someone has it in stock again
+                None => continue,
+            };
+
+            let crossed = price.cents <= alert.threshold.cents;
+            let triggered_at = match (crossed, alert.triggered_at) {
+                (true, None) => {
+                    let now = Utc::now();
+                    let notification = Notification::price_drop(&alert, &paint, &price, now);
+                    notifications.push(self.notify(&notification)?);
+                    Some(now)
+                }
+                (true, fired) => fired,
+                (false, _) => None,
+            };
+            if triggered_at != alert.triggered_at {
+                let alert = PriceAlert { triggered_at, ..alert };
+                self.repo.update_alert(&alert)?;
+                self.alerts.replace(alert.id, Some(alert));
+            }
+        }
+
+        self.alerts_by_user.clear();
+        Ok(notifications)
+    }
+
+    /// a notification by its id, as long as it's for the user
+    pub fn notification(&self, owner: &User, id: i32) -> Result<Option<Notification>, db::Error> {
+        Ok(self
+            .notifications
+            .load(id)?
+            .filter(|notification| notification.user_id == owner.id))
+    }
+
+    /// everything the user has been told about, newest first
+    pub fn notifications_for_user(&self, user_id: i32) -> Result<Vec<Notification>, db::Error> {
+        self.notifications_by_user.load(user_id)
+    }
+
+    /// note that the user has seen a notification
+    pub fn mark_read(&self, owner: &User, id: i32) -> Result<Notification, errors::Error> {
+        let notification = match self.notification(owner, id)? {
+            Some(notification) => notification,
+            None => return Err(errors::Error::NotFound(format!("notification {}", id))),
+        };
+        if notification.read_at.is_some() {
+            return Ok(notification);
+        }
+
+        // someone else might have marked it in the meantime, so we go with whatever was stored
+        self.repo.mark_notification_read(id, Utc::now())?;
+        let notification = self
+            .repo
+            .notifications_by_id(&[id])?
+            .pop()
+            .flatten()
+            .ok_or_else(|| errors::Error::NotFound(format!("notification {}", id)))?;
+
+        self.notifications.replace(id, Some(notification.clone()));
+        self.notifications_by_user.clear();
+        Ok(notification)
+    }
+
+    /// write a notification to the outbox
+    fn notify(&self, notification: &Notification) -> Result<Notification, db::Error> {
+        let notification = self.repo.insert_notification(notification)?;
+
+        self.notifications.replace(notification.id, Some(notification.clone()));
+        self.notifications_by_user.clear();
+        Ok(notification)
+    }
+}
+
+/// the cheapest price the alert's paint is in stock at, in the alert's currency. prices we
+/// can't convert are left out of it
+fn cheapest_price(
+    alert: &PriceAlert,
+    pricing: &pricing::Client,
+    rates: &currency::Client,
+) -> Result<Option<currency::Money>, db::Error> {
+    let mut cheapest: Option<currency::Money> = None;
+    for observation in pricing.current_prices(alert.paint_id, rates)? {
+        if !observation.in_stock {
+            continue;
+        }
+        let price = match rates.convert(&observation.price, Some(&alert.threshold.currency)) {
+            Ok(price) => price,
+            Err(errors::Error::Storage(err)) => return Err(err),
+            Err(_) => continue,
+        };
+        cheapest = match cheapest {
+            Some(cheapest) if cheapest.cents <= price.cents => Some(cheapest),
+            _ => Some(price),
+        };
+    }
+
+    Ok(cheapest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::Role;
+    use crate::currency::Money;
+    use crate::storage;
+    use chrono::Duration;
+
+    /// a backend with a user waiting on Abaddon Black to drop to 5.00 USD, and the id of
+    /// the alert they set
+    fn waiting() -> (storage::Backend, i32) {
+        let backend = storage::Backend::memory();
+        let user = backend
+            .accounts()
+            .insert_user(&User {
+                id: 0,
+                email: "painter@example.com".to_string(),
+                display_name: "Painter".to_string(),
+                password_hash: String::new(),
+                role: Role::User,
+                created_at: Utc::now(),
+            })
+            .unwrap();
+        backend
+            .pricing()
+            .insert_retailer(&pricing::Retailer {
+                id: 0,
+                name: "Hobby Shop".to_string(),
+                url: None,
+            })
+            .unwrap();
+        let alert = backend
+            .alerts()
+            .insert_alert(&PriceAlert {
+                id: 0,
+                user_id: user.id,
+                paint_id: 1,
+                threshold: Money::new(500, "USD"),
+                created_at: Utc::now(),
+                triggered_at: None,
+            })
+            .unwrap();
+
+        (backend, alert.id)
+    }
+
+    /// record a new price for Abaddon Black and check it the way a fresh request would,
+    /// returning the notifications that were written
+    fn observe(backend: &storage::Backend, step: i64, cents: i64) -> Vec<Notification> {
+        backend
+            .pricing()
+            .insert_observation(&pricing::PriceObservation {
+                id: 0,
+                retailer_id: 1,
+                paint_id: 1,
+                price: Money::new(cents, "USD"),
+                in_stock: true,
+                observed_at: Utc::now() + Duration::seconds(step),
+            })
+            .unwrap();
+
+        Client::new(backend.alerts())
+            .check_paint(
+                1,
+                &products::Client::new(
+                    backend.products(),
+                    products::ColorIndex::new(),
+                    products::SearchIndex::new(),
+                ),
+                &pricing::Client::new(backend.pricing()),
+                &currency::Client::new(backend.currency()),
+            )
+            .unwrap()
+    }
+
+    /// whether the alert has fired and is waiting for the price to go back up
+    fn fired(backend: &storage::Backend, alert_id: i32) -> bool {
+        backend.alerts().alerts_by_id(&[alert_id]).unwrap()[0]
+            .as_ref()
+            .unwrap()
+            .triggered_at
+            .is_some()
+    }
+
+    #[test]
+    fn alerts_stay_armed_above_the_threshold() {
+        let (backend, alert_id) = waiting();
+
+        assert!(observe(&backend, 1, 650).is_empty());
+        assert!(observe(&backend, 2, 501).is_empty());
+        assert!(!fired(&backend, alert_id));
+    }
+
+    #[test]
+    fn alerts_fire_once_when_the_price_crosses() {
+        let (backend, alert_id) = waiting();
+        observe(&backend, 1, 650);
+
+        let notifications = observe(&backend, 2, 500);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].paint_id, Some(1));
+        assert_eq!(notifications[0].title, "Abaddon Black is down to 5.00 USD");
+        assert!(fired(&backend, alert_id));
+
+        // staying under the threshold isn't news
+        assert!(observe(&backend, 3, 450).is_empty());
+        assert!(fired(&backend, alert_id));
+        assert_eq!(
+            backend.alerts().undelivered_notifications(MAX_ATTEMPTS, 10).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn alerts_rearm_when_the_price_goes_back_up() {
+        let (backend, alert_id) = waiting();
+        observe(&backend, 1, 450);
+        assert!(fired(&backend, alert_id));
+
+        assert!(observe(&backend, 2, 700).is_empty());
+        assert!(!fired(&backend, alert_id));
+
+        assert_eq!(observe(&backend, 3, 480).len(), 1);
+        assert!(fired(&backend, alert_id));
+    }
+}
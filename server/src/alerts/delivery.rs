@@ -0,0 +1,300 @@
+// external crates
+use chrono::Utc;
+use lettre::smtp::authentication::Credentials;
+use lettre::smtp::client::net::DEFAULT_TLS_PROTOCOLS;
+use lettre::smtp::{ClientSecurity, SmtpClient, SUBMISSIONS_PORT};
+use lettre::{ClientTlsParameters, EmailAddress, Envelope, SendableEmail, Transport};
+use native_tls::TlsConnector;
+
+use super::{Notification, Repository};
+use crate::{accounts, db};
+
+/// how many times we try to deliver a notification before giving up on it
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// a way of sending notifications on to the people they're for. the outbox hands every
+/// notification it has to a delivery until it goes through or runs out of attempts
+pub trait Delivery: Send + Sync {
+    /// send the notification to the email address, or say why it couldn't be sent
+    fn deliver(&self, to: &str, notification: &Notification) -> Result<(), String>;
+}
+
+/// delivers notifications as email through an SMTP server
+pub struct Smtp {
+    host: String,
+    port: u16,
+    /// whether to talk to the server over TLS. a local server (or a mock one) won't have it
+    tls: bool,
+    credentials: Option<(String, String)>,
+    /// the address the emails come from
+    from: String,
+}
+
+impl Smtp {
+    /// a delivery to the server at the host and port that doesn't sign in or use TLS
+    pub fn new(host: &str, port: u16, from: &str) -> Smtp {
+        Smtp {
+            host: host.to_string(),
+            port,
+            tls: false,
+            credentials: None,
+            from: from.to_string(),
+        }
+    }
+
+    /// talk to the server over TLS, checking its certificate against the host. the
+    /// submissions port (465) expects TLS from the start while any other port has to
+    /// upgrade to it with STARTTLS
+    pub fn tls(self, tls: bool) -> Smtp {
+        Smtp { tls, ..self }
+    }
+
+    /// sign into the server before sending anything
+    pub fn credentials(self, username: &str, password: &str) -> Smtp {
+        Smtp {
+            credentials: Some((username.to_string(), password.to_string())),
+            ..self
+        }
+    }
+
+    /// a client for the server that talks TLS if we were asked to
+    fn client(&self) -> Result<SmtpClient, String> {
+        let address = (self.host.as_str(), self.port);
+        if !self.tls {
+            return SmtpClient::new(address, ClientSecurity::None).map_err(|err| err.to_string());
+        }
+
+        let mut connector = TlsConnector::builder();
+        connector.min_protocol_version(Some(DEFAULT_TLS_PROTOCOLS[0]));
+        let connector = connector.build().map_err(|err| err.to_string())?;
+        let parameters = ClientTlsParameters::new(self.host.clone(), connector);
+        let security = if self.port == SUBMISSIONS_PORT {
+            ClientSecurity::Wrapper(parameters)
+        } else {
+            ClientSecurity::Required(parameters)
+        };
+
+        SmtpClient::new(address, security).map_err(|err| err.to_string())
+    }
+
+    /// the email for a notification, headers and all
+    fn message(&self, to: &str, notification: &Notification) -> String {
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <notification-{}@{}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+            strip_controls(&self.from),
+            strip_controls(to),
+            encode_header(&notification.title),
+            Utc::now().to_rfc2822(),
+            notification.id,
+            self.from.rsplit('@').next().unwrap_or("localhost"),
+            notification.body.replace('\n', "\r\n"),
+        )
+    }
+}
+
+impl Delivery for Smtp {
+    fn deliver(&self, to: &str, notification: &Notification) -> Result<(), String> {
+        let envelope = Envelope::new(
+            Some(EmailAddress::new(self.from.clone()).map_err(|err| err.to_string())?),
+            vec![EmailAddress::new(to.to_string()).map_err(|err| err.to_string())?],
+        )
+        .map_err(|err| err.to_string())?;
+        let email = SendableEmail::new(
+            envelope,
+            format!("notification-{}", notification.id),
+            self.message(to, notification).into_bytes(),
+        );
+
+        let mut client = self.client()?;
+        if let Some((username, password)) = &self.credentials {
+            client = client.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let mut transport = client.transport();
+        let result = transport.send(email).map(|_| ()).map_err(|err| err.to_string());
+        transport.close();
+
+        result
+    }
+}
+
+/// try to deliver every notification in the outbox that hasn't gone out yet, returning how
+/// many went through. failures are recorded on the notification so it's tried again later
+pub fn deliver_pending(
+    repo: &dyn Repository,
+    accounts: &dyn accounts::Repository,
+    delivery: &dyn Delivery,
+) -> Result<usize, db::Error> {
+    let pending = repo.undelivered_notifications(MAX_ATTEMPTS, 100)?;
+    let user_ids: Vec<i32> = pending.iter().map(|notification| notification.user_id).collect();
+    let users = accounts.users_by_id(&user_ids)?;
+
+    let mut delivered = 0;
+    for (notification, user) in pending.into_iter().zip(users) {
+        let result = match user {
+            Some(user) => delivery.deliver(&user.email, &notification),
+            None => Err("the user no longer exists".to_string()),
+        };
+
+        match result {
+            Ok(()) => {
+                delivered += 1;
+                repo.record_delivery_attempt(notification.id, Some(Utc::now()), None)?;
+            }
+            Err(err) => {
+                repo.record_delivery_attempt(notification.id, None, Some(&err))?;
+            }
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// a header value that's safe to put in an email, encoded as RFC 2047 if it isn't plain ascii
+fn encode_header(value: &str) -> String {
+    let value = strip_controls(value);
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?utf-8?B?{}?=", base64::encode(&value))
+    }
+}
+
+/// the value with every control character (line breaks included) turned into a space, so
+/// nothing that ends up in a header can start a header of its own
+fn strip_controls(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::{Role, User};
+    use crate::storage;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// an SMTP server that takes a single email and hands back everything it was sent: the
+    /// commands followed by the message
+    fn mock_server() -> (u16, thread::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut commands = Vec::new();
+            let mut message = String::new();
+
+            writer.write_all(b"220 mock ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let reply: &[u8] = if command == "DATA" {
+                    b"354 go ahead\r\n"
+                } else if command == "QUIT" {
+                    b"221 bye\r\n"
+                } else {
+                    b"250 ok\r\n"
+                };
+                writer.write_all(reply).unwrap();
+                commands.push(command.clone());
+
+                if command == "DATA" {
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        message.push_str(&line);
+                    }
+                    writer.write_all(b"250 queued\r\n").unwrap();
+                } else if command == "QUIT" {
+                    break;
+                }
+            }
+
+            (commands, message)
+        });
+
+        (port, server)
+    }
+
+    /// a notification waiting in the outbox for the user
+    fn pending(user_id: i32, title: &str) -> Notification {
+        Notification {
+            id: 0,
+            user_id,
+            paint_id: Some(1),
+            title: title.to_string(),
+            body: "It's cheap.\nGo and get it.".to_string(),
+            created_at: Utc::now(),
+            read_at: None,
+            delivered_at: None,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn delivers_the_outbox_over_smtp() {
+        let backend = storage::Backend::memory();
+        let user = backend
+            .accounts()
+            .insert_user(&User {
+                id: 0,
+                email: "painter@example.com".to_string(),
+                display_name: "Painter".to_string(),
+                password_hash: String::new(),
+                role: Role::User,
+                created_at: Utc::now(),
+            })
+            .unwrap();
+        let repo = backend.alerts();
+        // a paint name with a line break in it shouldn't be able to add headers
+        let sent = repo
+            .insert_notification(&pending(user.id, "Abaddon Black\r\nBcc: someone@example.com"))
+            .unwrap();
+        let orphaned = repo.insert_notification(&pending(user.id + 1, "Nuln Oil")).unwrap();
+
+        let (port, server) = mock_server();
+        let smtp = Smtp::new("127.0.0.1", port, "paints@example.com");
+        let delivered = deliver_pending(&*repo, &*backend.accounts(), &smtp).unwrap();
+        let (commands, message) = server.join().unwrap();
+
+        assert_eq!(delivered, 1);
+        assert!(commands[0].starts_with("EHLO "));
+        assert!(commands.contains(&"MAIL FROM:<paints@example.com>".to_string()));
+        assert!(commands.contains(&"RCPT TO:<painter@example.com>".to_string()));
+        assert!(message.contains("To: painter@example.com\r\n"));
+        assert!(message.contains("Subject: Abaddon Black  Bcc: someone@example.com\r\n"));
+        assert!(!message.contains("\r\nBcc:"));
+        assert!(message.contains("\r\n\r\nIt's cheap.\r\nGo and get it.\r\n"));
+
+        let notifications = repo.notifications_by_id(&[sent.id, orphaned.id]).unwrap();
+        let sent = notifications[0].as_ref().unwrap();
+        assert!(sent.delivered_at.is_some());
+        assert_eq!(sent.attempts, 1);
+        assert_eq!(sent.last_error, None);
+        let orphaned = notifications[1].as_ref().unwrap();
+        assert!(orphaned.delivered_at.is_none());
+        assert_eq!(orphaned.attempts, 1);
+        assert_eq!(orphaned.last_error.as_deref(), Some("the user no longer exists"));
+
+        // only the one that failed is tried again
+        let pending = repo.undelivered_notifications(MAX_ATTEMPTS, 10).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, orphaned.id);
+    }
+}
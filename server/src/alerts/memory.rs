@@ -0,0 +1,151 @@
+// external crates
+use chrono::{DateTime, Utc};
+
+use super::{Notification, PriceAlert, Repository};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn alerts_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceAlert>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.price_alerts.iter().find(|alert| alert.id == *id).cloned())
+            .collect())
+    }
+
+    fn alerts_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<PriceAlert>>, db::Error> {
+        let tables = self.read();
+
+        // alerts are kept in the order they were set
+        Ok(user_ids
+            .iter()
+            .map(|user_id| {
+                tables
+                    .price_alerts
+                    .iter()
+                    .filter(|alert| alert.user_id == *user_id)
+                    .cloned()
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn alerts_for_paint(&self, paint_id: i32) -> Result<Vec<PriceAlert>, db::Error> {
+        Ok(self
+            .read()
+            .price_alerts
+            .iter()
+            .filter(|alert| alert.paint_id == paint_id)
+            .cloned()
+            .collect())
+    }
+
+    fn insert_alert(&self, alert: &PriceAlert) -> Result<PriceAlert, db::Error> {
+        let mut tables = self.write();
+
+        let alert = PriceAlert {
+            id: memory::next_id(tables.price_alerts.iter().map(|alert| alert.id)),
+            ..alert.clone()
+        };
+        tables.price_alerts.push(alert.clone());
+
+        Ok(alert)
+    }
+
+    fn update_alert(&self, alert: &PriceAlert) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().price_alerts, alert, |alert| alert.id))
+    }
+
+    fn delete_alert(&self, id: i32) -> Result<bool, db::Error> {
+        Ok(memory::remove(&mut self.write().price_alerts, id, |alert| alert.id))
+    }
+
+    fn notifications_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Notification>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| {
+                tables
+                    .notifications
+                    .iter()
+                    .find(|notification| notification.id == *id)
+                    .cloned()
+            })
+            .collect())
+    }
+
+    fn notifications_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<Notification>>, db::Error> {
+        let tables = self.read();
+
+        // notifications are kept in the order they were written, so newest first is backwards
+        Ok(user_ids
+            .iter()
+            .map(|user_id| {
+                tables
+                    .notifications
+                    .iter()
+                    .rev()
+                    .filter(|notification| notification.user_id == *user_id)
+                    .cloned()
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn insert_notification(&self, notification: &Notification) -> Result<Notification, db::Error> {
+        let mut tables = self.write();
+
+        let notification = Notification {
+            id: memory::next_id(tables.notifications.iter().map(|notification| notification.id)),
+            ..notification.clone()
+        };
+        tables.notifications.push(notification.clone());
+
+        Ok(notification)
+    }
+
+    fn mark_notification_read(&self, id: i32, read_at: DateTime<Utc>) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        match tables.notifications.iter_mut().find(|notification| notification.id == id) {
+            Some(notification) => {
+                notification.read_at = notification.read_at.or(Some(read_at));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn record_delivery_attempt(
+        &self,
+        id: i32,
+        delivered_at: Option<DateTime<Utc>>,
+        error: Option<&str>,
+    ) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        match tables.notifications.iter_mut().find(|notification| notification.id == id) {
+            Some(notification) => {
+                notification.delivered_at = delivered_at;
+                notification.attempts += 1;
+                notification.last_error = error.map(str::to_string);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn undelivered_notifications(&self, max_attempts: i32, limit: i32) -> Result<Vec<Notification>, db::Error> {
+        Ok(self
+            .read()
+            .notifications
+            .iter()
+            .filter(|notification| notification.delivered_at.is_none())
+            .filter(|notification| notification.attempts < max_attempts)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
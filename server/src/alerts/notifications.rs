@@ -0,0 +1,99 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use super::PriceAlert;
+use crate::currency::Money;
+use crate::node::{self, Kind, Node};
+use crate::{api, products};
+
+/// something we have to tell a user about. notifications are kept for the user to read and
+/// are also sent on to them by whatever delivery the server is configured with
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub id: i32,
+    pub user_id: i32,
+    /// the paint the notification is about, if it's about one that's still in the catalog
+    pub paint_id: Option<i32>,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// how many times we've tried to deliver the notification
+    pub attempts: i32,
+    /// why the last delivery failed
+    pub last_error: Option<String>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl Notification {
+    /// the globally unique identifier of the notification
+    fn id(&self) -> ID {
+        node::global_id(Kind::Notification, self.id)
+    }
+
+    /// a one line summary
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// the whole message
+    fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// the paint the notification is about
+    fn paint(&self, context: &api::Context) -> FieldResult<Option<products::Paint>> {
+        match self.paint_id {
+            Some(id) => Ok(context.products.paint(id)?),
+            None => Ok(None),
+        }
+    }
+
+    /// whether the user has seen the notification
+    fn read(&self) -> bool {
+        self.read_at.is_some()
+    }
+
+    /// when the notification was written
+    fn createdAt(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// when the notification was sent on to the user, if it has been
+    fn deliveredAt(&self) -> Option<DateTime<Utc>> {
+        self.delivered_at
+    }
+}
+
+connection!(NotificationConnection, NotificationEdge, Notification);
+
+impl Notification {
+    /// the notification for an alert whose paint dropped to the price
+    pub fn price_drop(
+        alert: &PriceAlert,
+        paint: &products::Paint,
+        price: &Money,
+        now: DateTime<Utc>,
+    ) -> Notification {
+        Notification {
+            id: 0,
+            user_id: alert.user_id,
+            paint_id: Some(paint.id),
+            title: format!("{} is down to {}", paint.name, price),
+            body: format!(
+                "{} can be bought for {} right now, which is at or below the {} you were waiting for.",
+                paint.name, price, alert.threshold
+            ),
+            created_at: now,
+            read_at: None,
+            delivered_at: None,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use crate::currency::{is_currency_code, Money};
+use crate::node::{self, Kind, Node};
+use crate::{api, errors, products};
+
+/// a price someone is waiting for a paint to drop to
+#[derive(Clone, Debug)]
+pub struct PriceAlert {
+    pub id: i32,
+    pub user_id: i32,
+    pub paint_id: i32,
+    /// the price of a single pot that's low enough
+    pub threshold: Money,
+    pub created_at: DateTime<Utc>,
+    /// when the price last dropped to the threshold, or nothing if it's above it
+    pub triggered_at: Option<DateTime<Utc>>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl PriceAlert {
+    /// the globally unique identifier of the alert
+    fn id(&self) -> ID {
+        node::global_id(Kind::PriceAlert, self.id)
+    }
+
+    /// the paint being watched
+    fn paint(&self, context: &api::Context) -> FieldResult<products::Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+
+    /// the price of a single pot that's low enough, converted into another currency if
+    /// one is given
+    fn threshold(&self, context: &api::Context, currency: Option<String>) -> FieldResult<Money> {
+        Ok(context.currency.convert(&self.threshold, currency.as_deref())?)
+    }
+
+    /// whether the alert will fire the next time the price drops to the threshold. it
+    /// isn't while the price is already at or below it
+    fn armed(&self) -> bool {
+        self.triggered_at.is_none()
+    }
+
+    /// when the price last dropped to the threshold
+    fn triggeredAt(&self) -> Option<DateTime<Utc>> {
+        self.triggered_at
+    }
+
+    /// when the alert was first set
+    fn createdAt(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+connection!(PriceAlertConnection, PriceAlertEdge, PriceAlert);
+
+/// a price to wait for a paint to drop to
+#[derive(juniper::GraphQLInputObject)]
+pub struct PriceAlertInput {
+    /// the id of the paint to watch
+    pub paint: ID,
+    /// the price of a single pot that's low enough, in the currency's unit (ie, 3.75)
+    pub price: f64,
+    /// the ISO 4217 code of the currency (ie, USD or EUR)
+    pub currency: String,
+}
+
+impl PriceAlertInput {
+    /// check the input over, returning the id of the paint and the threshold
+    pub(super) fn validate(self, products: &products::Client) -> Result<(i32, Money), errors::Error> {
+        let mut validation = errors::Validation::new();
+        let paint = products.find_paint(&self.paint)?;
+        if paint.is_none() {
+            validation.add("paint", "does not exist");
+        }
+        if !self.price.is_finite() || self.price <= 0.0 {
+            validation.add("price", "must be more than zero");
+        }
+        let currency = self.currency.trim().to_uppercase();
+        if !is_currency_code(&currency) {
+            validation.add("currency", "must be a three letter currency code");
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the paint were missing
+        let threshold = Money::new((self.price * 100.0).round() as i64, &currency);
+        Ok((paint.unwrap().id, threshold))
+    }
+}
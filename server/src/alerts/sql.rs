@@ -0,0 +1,215 @@
+// external crates
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Row};
+
+use super::{Notification, PriceAlert, Repository};
+use crate::currency::Money;
+use crate::db;
+
+/// the columns we need to select in order to build a PriceAlert
+const ALERT_COLUMNS: &str =
+    "id, user_id, paint_id, threshold_cents, currency, created_at, triggered_at";
+
+/// the columns we need to select in order to build a Notification
+const NOTIFICATION_COLUMNS: &str =
+    "id, user_id, paint_id, title, body, created_at, read_at, delivered_at, attempts, last_error";
+
+impl Repository for db::Pool {
+    fn alerts_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceAlert>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM price_alerts WHERE id IN ({{keys}})", ALERT_COLUMNS),
+            ids,
+            alert_from_row,
+            |alert| alert.id,
+        )
+    }
+
+    fn alerts_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<PriceAlert>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM price_alerts WHERE user_id IN ({{keys}}) ORDER BY id",
+                ALERT_COLUMNS
+            ),
+            user_ids,
+            alert_from_row,
+            |alert| alert.user_id,
+        )
+    }
+
+    fn alerts_for_paint(&self, paint_id: i32) -> Result<Vec<PriceAlert>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM price_alerts WHERE paint_id = ? ORDER BY id",
+            ALERT_COLUMNS
+        ))?;
+
+        let alerts = statement
+            .query_map(params![paint_id], alert_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(alerts)
+    }
+
+    fn insert_alert(&self, alert: &PriceAlert) -> Result<PriceAlert, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO price_alerts
+                 (user_id, paint_id, threshold_cents, currency, created_at, triggered_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                alert.user_id,
+                alert.paint_id,
+                alert.threshold.cents,
+                alert.threshold.currency,
+                alert.created_at,
+                alert.triggered_at,
+            ],
+        )?;
+
+        Ok(PriceAlert {
+            id: conn.last_insert_rowid() as i32,
+            ..alert.clone()
+        })
+    }
+
+    fn update_alert(&self, alert: &PriceAlert) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE price_alerts SET threshold_cents = ?, currency = ?, triggered_at = ? WHERE id = ?",
+            params![
+                alert.threshold.cents,
+                alert.threshold.currency,
+                alert.triggered_at,
+                alert.id,
+            ],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_alert(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM price_alerts WHERE id = ?", [id])? > 0)
+    }
+
+    fn notifications_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Notification>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM notifications WHERE id IN ({{keys}})", NOTIFICATION_COLUMNS),
+            ids,
+            notification_from_row,
+            |notification| notification.id,
+        )
+    }
+
+    fn notifications_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<Notification>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM notifications WHERE user_id IN ({{keys}})
+                 ORDER BY created_at DESC, id DESC",
+                NOTIFICATION_COLUMNS
+            ),
+            user_ids,
+            notification_from_row,
+            |notification| notification.user_id,
+        )
+    }
+
+    fn insert_notification(&self, notification: &Notification) -> Result<Notification, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO notifications
+                 (user_id, paint_id, title, body, created_at, read_at, delivered_at, attempts,
+                  last_error)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                notification.user_id,
+                notification.paint_id,
+                notification.title,
+                notification.body,
+                notification.created_at,
+                notification.read_at,
+                notification.delivered_at,
+                notification.attempts,
+                notification.last_error,
+            ],
+        )?;
+
+        Ok(Notification {
+            id: conn.last_insert_rowid() as i32,
+            ..notification.clone()
+        })
+    }
+
+    fn mark_notification_read(&self, id: i32, read_at: DateTime<Utc>) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE notifications SET read_at = COALESCE(read_at, ?) WHERE id = ?",
+            params![read_at, id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn record_delivery_attempt(
+        &self,
+        id: i32,
+        delivered_at: Option<DateTime<Utc>>,
+        error: Option<&str>,
+    ) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE notifications
+             SET delivered_at = ?, attempts = attempts + 1, last_error = ?
+             WHERE id = ?",
+            params![delivered_at, error, id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn undelivered_notifications(&self, max_attempts: i32, limit: i32) -> Result<Vec<Notification>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM notifications
+             WHERE delivered_at IS NULL AND attempts < ?
+             ORDER BY created_at, id
+             LIMIT ?",
+            NOTIFICATION_COLUMNS
+        ))?;
+
+        let notifications = statement
+            .query_map(params![max_attempts, limit], notification_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(notifications)
+    }
+}
+
+/// build an alert out of a row containing ALERT_COLUMNS
+fn alert_from_row(row: &Row) -> rusqlite::Result<PriceAlert> {
+    Ok(PriceAlert {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        paint_id: row.get(2)?,
+        threshold: Money::new(row.get(3)?, &row.get::<_, String>(4)?),
+        created_at: row.get(5)?,
+        triggered_at: row.get(6)?,
+    })
+}
+
+/// build a notification out of a row containing NOTIFICATION_COLUMNS
+fn notification_from_row(row: &Row) -> rusqlite::Result<Notification> {
+    Ok(Notification {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        paint_id: row.get(2)?,
+        title: row.get(3)?,
+        body: row.get(4)?,
+        created_at: row.get(5)?,
+        read_at: row.get(6)?,
+        delivered_at: row.get(7)?,
+        attempts: row.get(8)?,
+        last_error: row.get(9)?,
+    })
+}
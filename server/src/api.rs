@@ -1,7 +1,10 @@
 // external crates
-use juniper::{EmptyMutation, RootNode};
+use juniper::{FieldResult, RootNode, ID};
 
-use super::products;
+use super::node::{self, Kind, Node};
+use super::pagination::{paginate, Page};
+use super::accounts::{self, Role};
+use super::{alerts, currency, errors, inventory, pricing, products, recipes, storage};
 
 /// the root query type
 pub struct Query;
@@ -15,33 +18,821 @@ impl Query {
         option_env!("CARGO_PKG_VERSION").unwrap_or("unknown")
     }
 
-    /// the list of product videos that we know of
-    fn productVideos(context: &Context) -> Vec<&products::Product> {
-        context.products.all_videos()
+    /// the user making the request, or nothing if they aren't signed in
+    fn viewer(context: &Context) -> FieldResult<Option<accounts::Viewer>> {
+        Ok(context.accounts.viewer()?.map(|user| accounts::Viewer { user }))
     }
+
+    /// the API keys that have been handed out, oldest first and a page at a time. only
+    /// admins can see them
+    fn apiKeys(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<accounts::ApiKeyConnection> {
+        authorize(context, Role::Admin)?;
+        let page = Page::new(first, after, last, before);
+        Ok(paginate(context.accounts.all_api_keys()?, "apiKey", |key| key.id, page)?.into())
+    }
+
+    /// how many requests an API key made on each of the last few days (30 by default).
+    /// leaving out the key looks up the one the request was made with, while admins can
+    /// look up any key
+    fn apiKeyUsage(
+        context: &Context,
+        id: Option<ID>,
+        days: Option<i32>,
+    ) -> FieldResult<accounts::ApiKeyUsage> {
+        let api_key = match id {
+            Some(id) => {
+                authorize(context, Role::Admin)?;
+                let local = record_id(&id, Kind::ApiKey)?;
+                context
+                    .accounts
+                    .api_key(local)?
+                    .ok_or_else(|| errors::Error::NotFound(format!("api key {}", &*id)))?
+            }
+            None => context.accounts.request_api_key().cloned().ok_or_else(|| {
+                errors::Error::Unauthenticated("the request wasn't made with an API key".to_string())
+            })?,
+        };
+        Ok(context.accounts.api_key_usage(api_key, days.unwrap_or(30))?)
+    }
+
+    /// look up any record by its global id
+    fn node(context: &Context, id: ID) -> FieldResult<Option<Node>> {
+        Ok(Node::find(context, &id)?)
+    }
+
+    /// look up any number of records by their global ids, in the same order as the ids
+    fn nodes(context: &Context, ids: Vec<ID>) -> FieldResult<Vec<Option<Node>>> {
+        Ok(ids
+            .iter()
+            .map(|id| Node::find(context, id))
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// the paints that we know of, a page at a time. they come sorted by brand, line and
+    /// name unless an order is given
+    fn paints(
+        context: &Context,
+        filter: Option<products::PaintFilter>,
+        order_by: Option<products::PaintOrderBy>,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<products::PaintConnection> {
+        let page = Page::new(first, after, last, before);
+        let paints = context.products.find_paints(filter, order_by)?;
+        Ok(paginate(paints, "paint", |paint| paint.id, page)?.into())
+    }
+
+    /// the paints, brands and videos whose names match the text, best match first. the
+    /// search shrugs off accents and the odd typo, and can be narrowed down to some kinds
+    /// of records
+    fn search(
+        context: &Context,
+        text: String,
+        types: Option<Vec<products::SearchType>>,
+    ) -> FieldResult<Vec<products::SearchResult>> {
+        Ok(context.products.search(&text, types)?)
+    }
+
+    /// the paints whose names (or any word in them) start with the prefix, for completing
+    /// what someone is typing into a search box
+    fn suggestPaints(
+        context: &Context,
+        prefix: String,
+        limit: Option<i32>,
+    ) -> FieldResult<Vec<products::PaintSuggestion>> {
+        Ok(context.products.suggest_paints(&prefix, limit.unwrap_or(10))?)
+    }
+
+    /// look up a single paint by its id
+    fn paint(context: &Context, id: ID) -> FieldResult<Option<products::Paint>> {
+        Ok(context.products.find_paint(&id)?)
+    }
+
+    /// the paints that look the most like a color, closest first. the search can be
+    /// narrowed down to paints from some of the brands
+    fn paintsNearColor(
+        context: &Context,
+        hex: String,
+        limit: Option<i32>,
+        brands: Option<Vec<ID>>,
+    ) -> FieldResult<Vec<products::PaintMatch>> {
+        Ok(context.products.paints_near_color(&hex, limit.unwrap_or(10), brands)?)
+    }
+
+    /// the brands that we know of, a page at a time
+    fn brands(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<products::BrandConnection> {
+        let page = Page::new(first, after, last, before);
+        Ok(paginate(context.products.all_brands()?, "brand", |brand| brand.id, page)?.into())
+    }
+
+    /// look up a single brand by its id
+    fn brand(context: &Context, id: ID) -> FieldResult<Option<products::Brand>> {
+        Ok(context.products.find_brand(&id)?)
+    }
+
+    /// the product videos that we know of, newest first and a page at a time
+    fn productVideos(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<products::ProductVideoConnection> {
+        let page = Page::new(first, after, last, before);
+        Ok(paginate(context.products.all_videos()?, "video", |video| video.id, page)?.into())
+    }
+
+    /// look up a single product video by its id
+    fn productVideo(context: &Context, id: ID) -> FieldResult<Option<products::ProductVideo>> {
+        Ok(context.products.find_video(&id)?)
+    }
+
+    /// the recipes people have written up, newest first and a page at a time. they can be
+    /// narrowed down to the ones for a miniature or faction, or that call for a paint
+    fn recipes(
+        context: &Context,
+        filter: Option<recipes::RecipeFilter>,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<recipes::RecipeConnection> {
+        let page = Page::new(first, after, last, before);
+        let recipes = context.recipes.find_recipes(filter, &context.products, &context.accounts)?;
+        Ok(paginate(recipes, "recipe", |recipe| recipe.id, page)?.into())
+    }
+
+    /// look up a single recipe by its id
+    fn recipe(context: &Context, id: ID) -> FieldResult<Option<recipes::Recipe>> {
+        Ok(context.recipes.find_recipe(&id)?)
+    }
+
+    /// the shops we keep track of prices at, by name and a page at a time
+    fn retailers(
+        context: &Context,
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> FieldResult<pricing::RetailerConnection> {
+        let page = Page::new(first, after, last, before);
+        let retailers = context.pricing.all_retailers()?;
+        Ok(paginate(retailers, "retailer", |retailer| retailer.id, page)?.into())
+    }
+
+    /// look up a single retailer by its id
+    fn retailer(context: &Context, id: ID) -> FieldResult<Option<pricing::Retailer>> {
+        Ok(context.pricing.find_retailer(&id)?)
+    }
+
+    /// the latest exchange rate for every currency prices can be converted between, by
+    /// currency code. rates are quoted against the US dollar
+    fn exchangeRates(context: &Context) -> FieldResult<Vec<currency::ExchangeRate>> {
+        Ok(context.currency.latest_rates()?)
+    }
+}
+
+/// the root mutation type
+pub struct Mutation;
+#[juniper::object(
+    Context = Context,
+)]
+impl Mutation {
+    /// create an account and sign into it
+    fn register(context: &Context, input: accounts::RegisterInput) -> FieldResult<accounts::AuthPayload> {
+        Ok(context.accounts.register(input)?)
+    }
+
+    /// sign into an existing account
+    fn logIn(context: &Context, input: accounts::LogInInput) -> FieldResult<accounts::AuthPayload> {
+        Ok(context.accounts.log_in(input)?)
+    }
+
+    /// change what a user is allowed to do. only admins can hand out roles
+    fn setUserRole(context: &Context, user: ID, role: Role) -> FieldResult<accounts::User> {
+        let admin = context.accounts.authorize(Role::Admin)?;
+        Ok(context.accounts.set_role(&admin, record_id(&user, Kind::User)?, role)?)
+    }
+
+    /// hand out an API key to a partner. only admins can issue keys
+    fn issueApiKey(
+        context: &Context,
+        input: accounts::IssueApiKeyInput,
+    ) -> FieldResult<accounts::IssuedApiKey> {
+        let admin = context.accounts.authorize(Role::Admin)?;
+        Ok(context.accounts.issue_api_key(&admin, input)?)
+    }
+
+    /// stop an API key from working for good
+    fn revokeApiKey(context: &Context, id: ID) -> FieldResult<accounts::ApiKey> {
+        authorize(context, Role::Admin)?;
+        Ok(context.accounts.revoke_api_key(record_id(&id, Kind::ApiKey)?)?)
+    }
+
+    /// record a paint the viewer owns
+    fn addToInventory(
+        context: &Context,
+        input: inventory::InventoryItemInput,
+    ) -> FieldResult<inventory::InventoryItem> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.inventory.add(&viewer, input, &context.products)?)
+    }
+
+    /// change what the viewer has recorded about one of their paints
+    fn updateInventoryItem(
+        context: &Context,
+        id: ID,
+        input: inventory::InventoryItemPatch,
+    ) -> FieldResult<inventory::InventoryItem> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.inventory.update(&viewer, record_id(&id, Kind::InventoryItem)?, input)?)
+    }
+
+    /// forget about one of the viewer's paints, returning its id
+    fn removeFromInventory(context: &Context, id: ID) -> FieldResult<ID> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        context.inventory.remove(&viewer, record_id(&id, Kind::InventoryItem)?)?;
+        Ok(id)
+    }
+
+    /// add a paint to the viewer's wishlist. a paint that's already on it is wanted that
+    /// many more times
+    fn addToWishlist(
+        context: &Context,
+        input: inventory::WishlistItemInput,
+    ) -> FieldResult<inventory::WishlistItem> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.inventory.add_to_wishlist(&viewer, input, &context.products)?)
+    }
+
+    /// take a paint off the viewer's wishlist, returning its id
+    fn removeFromWishlist(context: &Context, id: ID) -> FieldResult<ID> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        context.inventory.remove_from_wishlist(&viewer, record_id(&id, Kind::WishlistItem)?)?;
+        Ok(id)
+    }
+
+    /// tell the viewer when a paint drops to a price. setting an alert for a paint that
+    /// already has one changes its threshold instead
+    fn setPriceAlert(context: &Context, input: alerts::PriceAlertInput) -> FieldResult<alerts::PriceAlert> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.alerts.set_alert(
+            &viewer,
+            input,
+            &context.products,
+            &context.pricing,
+            &context.currency,
+        )?)
+    }
+
+    /// stop waiting for a paint's price to drop, returning the alert's id
+    fn removePriceAlert(context: &Context, id: ID) -> FieldResult<ID> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        context.alerts.remove_alert(&viewer, record_id(&id, Kind::PriceAlert)?)?;
+        Ok(id)
+    }
+
+    /// note that the viewer has seen one of their notifications
+    fn markNotificationRead(context: &Context, id: ID) -> FieldResult<alerts::Notification> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.alerts.mark_read(&viewer, record_id(&id, Kind::Notification)?)?)
+    }
+
+    /// write up a recipe under the viewer's name
+    fn createRecipe(context: &Context, input: recipes::RecipeInput) -> FieldResult<recipes::Recipe> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.recipes.create_recipe(&viewer, input, &context.products)?)
+    }
+
+    /// change a recipe, replacing its steps and tags. only its author or a curator can
+    /// change it
+    fn updateRecipe(
+        context: &Context,
+        id: ID,
+        input: recipes::RecipeInput,
+    ) -> FieldResult<recipes::Recipe> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        Ok(context.recipes.update_recipe(
+            &viewer,
+            record_id(&id, Kind::Recipe)?,
+            input,
+            &context.products,
+        )?)
+    }
+
+    /// remove a recipe, returning its id. only its author or a curator can remove it
+    fn deleteRecipe(context: &Context, id: ID) -> FieldResult<ID> {
+        let viewer = context.accounts.authorize(Role::User)?;
+        context.recipes.delete_recipe(&viewer, record_id(&id, Kind::Recipe)?)?;
+        Ok(id)
+    }
+
+    /// add a paint to the catalog
+    fn createPaint(context: &Context, input: products::PaintInput) -> FieldResult<products::Paint> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.create_paint(input)?)
+    }
+
+    /// change the details of a paint in the catalog
+    fn updatePaint(
+        context: &Context,
+        id: ID,
+        input: products::PaintPatch,
+    ) -> FieldResult<products::Paint> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.update_paint(record_id(&id, Kind::Paint)?, input)?)
+    }
+
+    /// remove a paint from the catalog, returning its id
+    fn deletePaint(context: &Context, id: ID) -> FieldResult<ID> {
+        authorize(context, Role::Curator)?;
+        context.products.delete_paint(record_id(&id, Kind::Paint)?)?;
+        Ok(id)
+    }
+
+    /// record that two paints can be used in place of each other, returning the first one
+    fn addPaintEquivalent(
+        context: &Context,
+        input: products::EquivalenceInput,
+    ) -> FieldResult<products::Paint> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.add_equivalence(input)?)
+    }
+
+    /// forget that two paints can be used in place of each other, returning the first one
+    fn removePaintEquivalent(context: &Context, paint: ID, equivalent: ID) -> FieldResult<products::Paint> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.remove_equivalence(
+            record_id(&paint, Kind::Paint)?,
+            record_id(&equivalent, Kind::Paint)?,
+        )?)
+    }
+
+    /// add a brand to the catalog
+    fn createBrand(context: &Context, input: products::BrandInput) -> FieldResult<products::Brand> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.create_brand(input)?)
+    }
+
+    /// change the details of a brand
+    fn updateBrand(
+        context: &Context,
+        id: ID,
+        input: products::BrandInput,
+    ) -> FieldResult<products::Brand> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.update_brand(record_id(&id, Kind::Brand)?, input)?)
+    }
+
+    /// remove a brand that no longer has any product lines, returning its id
+    fn deleteBrand(context: &Context, id: ID) -> FieldResult<ID> {
+        authorize(context, Role::Curator)?;
+        context.products.delete_brand(record_id(&id, Kind::Brand)?)?;
+        Ok(id)
+    }
+
+    /// add a product line to a brand
+    fn createProductLine(
+        context: &Context,
+        input: products::ProductLineInput,
+    ) -> FieldResult<products::ProductLine> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.create_line(input)?)
+    }
+
+    /// change the details of a product line
+    fn updateProductLine(
+        context: &Context,
+        id: ID,
+        input: products::ProductLinePatch,
+    ) -> FieldResult<products::ProductLine> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.update_line(record_id(&id, Kind::ProductLine)?, input)?)
+    }
+
+    /// remove a product line that no longer has any paints, returning its id
+    fn deleteProductLine(context: &Context, id: ID) -> FieldResult<ID> {
+        authorize(context, Role::Curator)?;
+        context.products.delete_line(record_id(&id, Kind::ProductLine)?)?;
+        Ok(id)
+    }
+
+    /// add a video along with the paints used in it
+    fn createProductVideo(
+        context: &Context,
+        input: products::ProductVideoInput,
+    ) -> FieldResult<products::ProductVideo> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.create_video(input)?)
+    }
+
+    /// change the details of a video, replacing the list of paints used in it
+    fn updateProductVideo(
+        context: &Context,
+        id: ID,
+        input: products::ProductVideoInput,
+    ) -> FieldResult<products::ProductVideo> {
+        authorize(context, Role::Curator)?;
+        Ok(context.products.update_video(record_id(&id, Kind::ProductVideo)?, input)?)
+    }
+
+    /// remove a video, returning its id
+    fn deleteProductVideo(context: &Context, id: ID) -> FieldResult<ID> {
+        authorize(context, Role::Curator)?;
+        context.products.delete_video(record_id(&id, Kind::ProductVideo)?)?;
+        Ok(id)
+    }
+
+    /// add a shop to keep track of prices at
+    fn createRetailer(context: &Context, input: pricing::RetailerInput) -> FieldResult<pricing::Retailer> {
+        authorize(context, Role::Curator)?;
+        Ok(context.pricing.create_retailer(input)?)
+    }
+
+    /// change the details of a retailer
+    fn updateRetailer(
+        context: &Context,
+        id: ID,
+        input: pricing::RetailerInput,
+    ) -> FieldResult<pricing::Retailer> {
+        authorize(context, Role::Curator)?;
+        Ok(context.pricing.update_retailer(record_id(&id, Kind::Retailer)?, input)?)
+    }
+
+    /// record a price seen for a paint at a retailer
+    fn recordPriceObservation(
+        context: &Context,
+        input: pricing::PriceObservationInput,
+    ) -> FieldResult<pricing::PriceObservation> {
+        authorize(context, Role::Curator)?;
+        let observation = context.pricing.record_observation(input, &context.products)?;
+
+        // let anyone waiting on the paint know if its price just dropped far enough
+        context.alerts.check_paint(
+            observation.paint_id,
+            &context.products,
+            &context.pricing,
+            &context.currency,
+        )?;
+        Ok(observation)
+    }
+
+    /// store a batch of exchange rates, replacing any for the same currency and day. none
+    /// of them are stored if any are invalid
+    fn importExchangeRates(
+        context: &Context,
+        rates: Vec<currency::ExchangeRateInput>,
+    ) -> FieldResult<Vec<currency::ExchangeRate>> {
+        authorize(context, Role::Curator)?;
+        Ok(context.currency.import(rates)?)
+    }
+}
+
+/// fail with a FORBIDDEN error unless the caller is signed in with at least the role.
+/// every mutation that changes the catalog checks for a curator before doing anything
+fn authorize(context: &Context, role: Role) -> Result<(), errors::Error> {
+    context.accounts.authorize(role).map(|_| ())
+}
+
+/// the record's own id for a global id, or a NOT_FOUND error if the id couldn't refer to
+/// the kind of record we want
+fn record_id(id: &ID, kind: Kind) -> Result<i32, errors::Error> {
+    node::local_id(kind, id)
+        .ok_or_else(|| errors::Error::NotFound(format!("{} {}", kind.as_str(), &**id)))
 }
 
 /// the context type for queries
 pub struct Context {
-    products: products::Client,
+    pub products: products::Client,
+    pub accounts: accounts::Client,
+    pub inventory: inventory::Client,
+    pub pricing: pricing::Client,
+    pub currency: currency::Client,
+    pub alerts: alerts::Client,
+    pub recipes: recipes::Client,
 }
 // Mark the Database as a valid context type for Juniper
 impl juniper::Context for Context {}
 
 impl Context {
-    pub fn new() -> Context {
+    pub fn new(
+        backend: &storage::Backend,
+        colors: &products::ColorIndex,
+        search: &products::SearchIndex,
+        sessions: &accounts::Sessions,
+        session: Option<accounts::Session>,
+        api_key: Option<accounts::ApiKey>,
+    ) -> Context {
         // creating a new context involves instantiatin each domain-specific client
         Context {
-            products: products::Client::new(),
+            products: products::Client::new(backend.products(), colors.clone(), search.clone()),
+            accounts: accounts::Client::new(backend.accounts(), sessions.clone(), session, api_key),
+            inventory: inventory::Client::new(backend.inventory()),
+            pricing: pricing::Client::new(backend.pricing()),
+            currency: currency::Client::new(backend.currency()),
+            alerts: alerts::Client::new(backend.alerts()),
+            recipes: recipes::Client::new(backend.recipes()),
         }
     }
 }
 
 /// the root schema type
-pub type Schema = RootNode<'static, Query, EmptyMutation<Context>>;
+pub type Schema = RootNode<'static, Query, Mutation>;
 
 
 /// return the root node representing our schema
 pub fn root_node() -> Schema {
-    Schema::new(Query, EmptyMutation::<Context>::new())
+    Schema::new(Query, Mutation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, memory};
+    use chrono::Utc;
+    use juniper::{graphql_value, Value, Variables};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// a products repository that keeps track of how often each of its methods is called
+    struct Counting {
+        store: memory::Store,
+        calls: Mutex<BTreeMap<&'static str, usize>>,
+    }
+
+    impl Counting {
+        fn calls(&self) -> BTreeMap<&'static str, usize> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    macro_rules! counted {
+        ($($method:ident($($arg:ident: $type:ty),*) -> $result:ty;)*) => {
+            $(
+                fn $method(&self, $($arg: $type),*) -> Result<$result, db::Error> {
+                    *self.calls.lock().unwrap().entry(stringify!($method)).or_insert(0) += 1;
+                    products::Repository::$method(&self.store, $($arg),*)
+                }
+            )*
+        };
+    }
+
+    impl products::Repository for Counting {
+        counted! {
+            find_paints(query: &products::PaintQuery) -> Vec<products::Paint>;
+            paints_by_id(ids: &[i32]) -> Vec<Option<products::Paint>>;
+            paints_by_line(line_ids: &[i32]) -> Vec<Vec<products::Paint>>;
+            insert_paint(paint: &products::Paint) -> products::Paint;
+            update_paint(paint: &products::Paint) -> bool;
+            delete_paint(id: i32) -> bool;
+            all_brands() -> Vec<products::Brand>;
+            brands_by_id(ids: &[i32]) -> Vec<Option<products::Brand>>;
+            insert_brand(brand: &products::Brand) -> products::Brand;
+            update_brand(brand: &products::Brand) -> bool;
+            delete_brand(id: i32) -> bool;
+            lines_by_id(ids: &[i32]) -> Vec<Option<products::ProductLine>>;
+            lines_by_brand(brand_ids: &[i32]) -> Vec<Vec<products::ProductLine>>;
+            insert_line(line: &products::ProductLine) -> products::ProductLine;
+            update_line(line: &products::ProductLine) -> bool;
+            delete_line(id: i32) -> bool;
+            equivalences_by_paint(paint_ids: &[i32]) -> Vec<Vec<products::Equivalence>>;
+            insert_equivalence(equivalence: &products::Equivalence) -> ();
+            delete_equivalence(paint_id: i32, equivalent_id: i32) -> bool;
+            all_videos() -> Vec<products::ProductVideo>;
+            videos_by_id(ids: &[i32]) -> Vec<Option<products::ProductVideo>>;
+            videos_by_paint(paint_ids: &[i32]) -> Vec<Vec<products::ProductVideo>>;
+            usages_by_video(video_ids: &[i32]) -> Vec<Vec<products::PaintUsage>>;
+            insert_video(video: &products::ProductVideo, usages: &[products::PaintUsage]) -> products::ProductVideo;
+            update_video(video: &products::ProductVideo, usages: &[products::PaintUsage]) -> bool;
+            delete_video(id: i32) -> bool;
+        }
+    }
+
+    /// a context for an anonymous request against a fresh in-memory backend
+    fn context(backend: &storage::Backend) -> Context {
+        Context::new(
+            backend,
+            &products::ColorIndex::new(),
+            &products::SearchIndex::new(),
+            &accounts::Sessions::new(b"secret"),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn serves_the_fixtures_from_memory() {
+        let backend = storage::Backend::memory();
+        let query = r#"{
+            paints(first: 2) {
+                totalCount
+                pageInfo { hasNextPage }
+                edges { node { name color { hex } brand { name } productLine { name } } }
+            }
+            missing: paint(id: "nope") { name }
+        }"#;
+
+        let (value, errors) =
+            juniper::execute(query, None, &root_node(), &Variables::new(), &context(&backend))
+                .expect("the query is valid");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            value,
+            graphql_value!({
+                "paints": {
+                    "totalCount": 18,
+                    "pageInfo": { "hasNextPage": true },
+                    "edges": [
+                        { "node": {
+                            "name": "Abaddon Black",
+                            "color": { "hex": "#231f20" },
+                            "brand": { "name": "Citadel" },
+                            "productLine": { "name": "Base" },
+                        } },
+                        { "node": {
+                            "name": "Leadbelcher",
+                            "color": { "hex": "#888d8f" },
+                            "brand": { "name": "Citadel" },
+                            "productLine": { "name": "Base" },
+                        } },
+                    ],
+                },
+                "missing": None,
+            })
+        );
+    }
+
+    #[test]
+    fn nested_lookups_are_batched() {
+        let backend = storage::Backend::memory();
+        let repo = Arc::new(Counting {
+            store: memory::Store::seeded(),
+            calls: Mutex::new(BTreeMap::new()),
+        });
+        let context = Context {
+            products: products::Client::new(
+                repo.clone(),
+                products::ColorIndex::new(),
+                products::SearchIndex::new(),
+            ),
+            ..context(&backend)
+        };
+
+        let query = r#"{
+            paints {
+                edges { node { name productLine { name brand { name } } brand { name } } }
+            }
+            brands {
+                edges { node { name lines { name paints { name productLine { name } } } } }
+            }
+        }"#;
+        let (_, errors) = juniper::execute(query, None, &root_node(), &Variables::new(), &context)
+            .expect("the query is valid");
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // every level of the query is a single trip to storage however many records it has
+        let expected: BTreeMap<&str, usize> = vec![
+            ("all_brands", 1),
+            ("brands_by_id", 1),
+            ("find_paints", 1),
+            ("lines_by_brand", 1),
+            ("lines_by_id", 1),
+            ("paints_by_line", 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(repo.calls(), expected);
+    }
+
+    /// a context for a request made by someone signed in as a newly registered user with the
+    /// role, along with that user
+    fn signed_in(backend: &storage::Backend, role: Role) -> (Context, accounts::User) {
+        let user = backend
+            .accounts()
+            .insert_user(&accounts::User {
+                id: 0,
+                email: format!("{}@example.com", role.as_str()),
+                display_name: role.as_str().to_string(),
+                password_hash: String::new(),
+                role,
+                created_at: Utc::now(),
+            })
+            .unwrap();
+        let sessions = accounts::Sessions::new(b"secret");
+        let context = Context::new(
+            backend,
+            &products::ColorIndex::new(),
+            &products::SearchIndex::new(),
+            &sessions,
+            sessions.verify(&sessions.issue(user.id)),
+            None,
+        );
+
+        (context, user)
+    }
+
+    /// run a query, handing back its data and the codes of any errors it ran into
+    fn run(query: &str, context: &Context) -> (Value, Vec<String>) {
+        let (value, errors) =
+            juniper::execute(query, None, &root_node(), &Variables::new(), context)
+                .expect("the query is valid");
+        let codes = errors
+            .iter()
+            .map(|err| {
+                let extensions = err.error().extensions().as_object_value();
+                let code = extensions.and_then(|fields| fields.get_field_value("code"));
+                code.and_then(Value::as_scalar_value::<String>)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        (value, codes)
+    }
+
+    #[test]
+    fn only_curators_change_the_catalog() {
+        let backend = storage::Backend::memory();
+        let paint = node::global_id(Kind::Paint, 1);
+        let create = r#"mutation { createBrand(input: { name: "Two Thin Coats" }) { name } }"#;
+        let delete = format!(r#"mutation {{ deletePaint(id: "{}") }}"#, &*paint);
+        let catalog = format!(
+            r#"{{ brands {{ totalCount }} paint(id: "{}") {{ name }} }}"#,
+            &*paint
+        );
+
+        // anyone who isn't a curator is turned away before anything changes
+        let (user, _) = signed_in(&backend, Role::User);
+        for context in &[context(&backend), user] {
+            assert_eq!(
+                run(create, context),
+                (Value::null(), vec!["FORBIDDEN".to_string()])
+            );
+            assert_eq!(
+                run(&delete, context),
+                (Value::null(), vec!["FORBIDDEN".to_string()])
+            );
+        }
+        let (value, _) = run(&catalog, &context(&backend));
+        assert_eq!(
+            value,
+            graphql_value!({ "brands": { "totalCount": 3 }, "paint": { "name": "Abaddon Black" } })
+        );
+
+        let (curator, _) = signed_in(&backend, Role::Curator);
+        assert_eq!(
+            run(create, &curator),
+            (
+                graphql_value!({ "createBrand": { "name": "Two Thin Coats" } }),
+                vec![]
+            )
+        );
+        let (value, errors) = run(&delete, &curator);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(value, graphql_value!({ "deletePaint": (&*paint) }));
+    }
+
+    #[test]
+    fn only_admins_change_roles() {
+        let backend = storage::Backend::memory();
+        let (admin, _) = signed_in(&backend, Role::Admin);
+        let (curator, _) = signed_in(&backend, Role::Curator);
+        let (user, member) = signed_in(&backend, Role::User);
+        let promote = format!(
+            r#"mutation {{ setUserRole(user: "{}", role: CURATOR) {{ role }} }}"#,
+            &*node::global_id(Kind::User, member.id)
+        );
+
+        // nobody gets to hand out roles to themselves or anyone else short of being an admin
+        for context in &[context(&backend), user, curator] {
+            assert_eq!(
+                run(&promote, context),
+                (Value::null(), vec!["FORBIDDEN".to_string()])
+            );
+        }
+        let stored = backend.accounts().users_by_id(&[member.id]).unwrap();
+        assert_eq!(stored[0].as_ref().map(|user| user.role), Some(Role::User));
+
+        assert_eq!(
+            run(&promote, &admin),
+            (
+                graphql_value!({ "setUserRole": { "role": "CURATOR" } }),
+                vec![]
+            )
+        );
+    }
 }
@@ -0,0 +1,234 @@
+// external crates
+use std::f64::consts::PI;
+
+/// the reference white for converting into CIELAB (D65, the white point of sRGB)
+const WHITE: (f64, f64, f64) = (0.950_47, 1.0, 1.088_83);
+
+/// a color in the CIELAB space, where the distance between two colors lines up far better
+/// with how different they look than it does in sRGB
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lab {
+    /// lightness, from 0 (black) to 100 (white)
+    pub l: f64,
+    /// green (negative) to red (positive)
+    pub a: f64,
+    /// blue (negative) to yellow (positive)
+    pub b: f64,
+}
+
+impl Lab {
+    /// convert an sRGB color (with 8 bits per channel) into CIELAB
+    pub fn from_srgb(red: u8, green: u8, blue: u8) -> Lab {
+        // undo the gamma curve so the channels are proportional to light intensity
+        let linear = |channel: u8| {
+            let value = f64::from(channel) / 255.0;
+            if value <= 0.040_45 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(red), linear(green), linear(blue));
+
+        // linear sRGB to CIEXYZ, scaled by the reference white
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / WHITE.0;
+        let y = (0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b) / WHITE.1;
+        let z = (0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b) / WHITE.2;
+
+        let f = |t: f64| {
+            let epsilon = 216.0 / 24389.0;
+            let kappa = 24389.0 / 27.0;
+            if t > epsilon {
+                t.cbrt()
+            } else {
+                (kappa * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    /// the hue of the color in degrees (0-360), going from red through yellow, green and
+    /// blue. colors without much chroma (ie, greys) don't really have one
+    pub fn hue(self) -> f64 {
+        hue_angle(self.a, self.b)
+    }
+
+    /// the CIEDE2000 color difference between two colors. a difference of around 1 is
+    /// the smallest most people can notice side by side
+    pub fn delta_e(self, other: Lab) -> f64 {
+        // this follows "The CIEDE2000 Color-Difference Formula: Implementation Notes,
+        // Supplementary Test Data, and Mathematical Observations" by Sharma, Wu and Dalal
+        let (l1, a1, b1) = (self.l, self.a, self.b);
+        let (l2, a2, b2) = (other.l, other.a, other.b);
+
+        let c1 = a1.hypot(b1);
+        let c2 = a2.hypot(b2);
+        let c_mean = (c1 + c2) / 2.0;
+
+        // stretch the a axis to compensate for how poorly CIELAB handles neutral colors
+        let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt());
+        let a1 = (1.0 + g) * a1;
+        let a2 = (1.0 + g) * a2;
+
+        let c1 = a1.hypot(b1);
+        let c2 = a2.hypot(b2);
+        let h1 = hue_angle(a1, b1);
+        let h2 = hue_angle(a2, b2);
+
+        let delta_l = l2 - l1;
+        let delta_c = c2 - c1;
+        let delta_h = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else {
+            h2 - h1 + 360.0
+        };
+        let delta_h = 2.0 * (c1 * c2).sqrt() * (radians(delta_h) / 2.0).sin();
+
+        let l_mean = (l1 + l2) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let t = 1.0 - 0.17 * radians(h_mean - 30.0).cos()
+            + 0.24 * radians(2.0 * h_mean).cos()
+            + 0.32 * radians(3.0 * h_mean + 6.0).cos()
+            - 0.20 * radians(4.0 * h_mean - 63.0).cos();
+
+        // how much each of the differences counts depends on where the colors sit
+        let s_l = 1.0 + (0.015 * (l_mean - 50.0).powi(2)) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+        let s_c = 1.0 + 0.045 * c_mean;
+        let s_h = 1.0 + 0.015 * c_mean * t;
+
+        // blues need the chroma and hue differences rotated against each other
+        let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+        let r_c = 2.0 * (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt();
+        let r_t = -(2.0 * radians(delta_theta)).sin() * r_c;
+
+        let l = delta_l / s_l;
+        let c = delta_c / s_c;
+        let h = delta_h / s_h;
+
+        (l * l + c * c + h * h + r_t * c * h).sqrt()
+    }
+}
+
+/// the hue of a color in degrees, between 0 and 360
+fn hue_angle(a: f64, b: f64) -> f64 {
+    if a == 0.0 && b == 0.0 {
+        return 0.0;
+    }
+
+    let degrees = b.atan2(a) * 180.0 / PI;
+    if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    }
+}
+
+fn radians(degrees: f64) -> f64 {
+    degrees * PI / 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the test pairs from Sharma, Wu and Dalal, along with the difference between them
+    const SHARMA_PAIRS: [(f64, f64, f64, f64, f64, f64, f64); 34] = [
+        (50.0000, 2.6772, -79.7751, 50.0000, 0.0000, -82.7485, 2.0425),
+        (50.0000, 3.1571, -77.2803, 50.0000, 0.0000, -82.7485, 2.8615),
+        (50.0000, 2.8361, -74.0200, 50.0000, 0.0000, -82.7485, 3.4412),
+        (50.0000, -1.3802, -84.2814, 50.0000, 0.0000, -82.7485, 1.0000),
+        (50.0000, -1.1848, -84.8006, 50.0000, 0.0000, -82.7485, 1.0000),
+        (50.0000, -0.9009, -85.5211, 50.0000, 0.0000, -82.7485, 1.0000),
+        (50.0000, 0.0000, 0.0000, 50.0000, -1.0000, 2.0000, 2.3669),
+        (50.0000, -1.0000, 2.0000, 50.0000, 0.0000, 0.0000, 2.3669),
+        (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0009, 7.1792),
+        (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0010, 7.1792),
+        (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0011, 7.2195),
+        (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0012, 7.2195),
+        (50.0000, -0.0010, 2.4900, 50.0000, 0.0009, -2.4900, 4.8045),
+        (50.0000, -0.0010, 2.4900, 50.0000, 0.0010, -2.4900, 4.8045),
+        (50.0000, -0.0010, 2.4900, 50.0000, 0.0011, -2.4900, 4.7461),
+        (50.0000, 2.5000, 0.0000, 50.0000, 0.0000, -2.5000, 4.3065),
+        (50.0000, 2.5000, 0.0000, 73.0000, 25.0000, -18.0000, 27.1492),
+        (50.0000, 2.5000, 0.0000, 61.0000, -5.0000, 29.0000, 22.8977),
+        (50.0000, 2.5000, 0.0000, 56.0000, -27.0000, -3.0000, 31.9030),
+        (50.0000, 2.5000, 0.0000, 58.0000, 24.0000, 15.0000, 19.4535),
+        (50.0000, 2.5000, 0.0000, 50.0000, 3.1736, 0.5854, 1.0000),
+        (50.0000, 2.5000, 0.0000, 50.0000, 3.2972, 0.0000, 1.0000),
+        (50.0000, 2.5000, 0.0000, 50.0000, 1.8634, 0.5757, 1.0000),
+        (50.0000, 2.5000, 0.0000, 50.0000, 3.2592, 0.3350, 1.0000),
+        (60.2574, -34.0099, 36.2677, 60.4626, -34.1751, 39.4387, 1.2644),
+        (63.0109, -31.0961, -5.8663, 62.8187, -29.7946, -4.0864, 1.2630),
+        (61.2901, 3.7196, -5.3901, 61.4292, 2.2480, -4.9620, 1.8731),
+        (35.0831, -44.1164, 3.7933, 35.0232, -40.0716, 1.5901, 1.8645),
+        (22.7233, 20.0904, -46.6940, 23.0331, 14.9730, -42.5619, 2.0373),
+        (36.4612, 47.8580, 18.3852, 36.2715, 50.5065, 21.2231, 1.4146),
+        (90.8027, -2.0831, 1.4410, 91.1528, -1.6435, 0.0447, 1.4441),
+        (90.9257, -0.5406, -0.9208, 88.6381, -0.8985, -0.7239, 1.5381),
+        (6.7747, -0.2908, -2.4247, 5.8714, -0.0985, -2.2286, 0.6377),
+        (2.0776, 0.0795, -1.1350, 0.9033, -0.0636, -0.5514, 0.9082),
+    ];
+
+    #[test]
+    fn delta_e_matches_the_reference_data() {
+        for (index, &(l1, a1, b1, l2, a2, b2, expected)) in SHARMA_PAIRS.iter().enumerate() {
+            let first = Lab { l: l1, a: a1, b: b1 };
+            let second = Lab { l: l2, a: a2, b: b2 };
+
+            // the published differences are rounded to 4 decimal places
+            for delta_e in &[first.delta_e(second), second.delta_e(first)] {
+                assert!(
+                    (delta_e - expected).abs() < 0.000_05,
+                    "pair {} came out as {} instead of {}",
+                    index + 1,
+                    delta_e,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn identical_colors_have_no_difference() {
+        let red = Lab::from_srgb(154, 17, 21);
+
+        assert_eq!(red.delta_e(red), 0.0);
+    }
+
+    #[test]
+    fn converts_from_srgb() {
+        let white = Lab::from_srgb(255, 255, 255);
+        assert!((white.l - 100.0).abs() < 0.01);
+        assert!(white.a.abs() < 0.01 && white.b.abs() < 0.01);
+
+        let black = Lab::from_srgb(0, 0, 0);
+        assert_eq!((black.l, black.a, black.b), (0.0, 0.0, 0.0));
+
+        // the reference values for pure red
+        let red = Lab::from_srgb(255, 0, 0);
+        assert!((red.l - 53.24).abs() < 0.01);
+        assert!((red.a - 80.09).abs() < 0.01);
+        assert!((red.b - 67.20).abs() < 0.01);
+        assert!((red.hue() - 40.0).abs() < 0.1);
+    }
+}
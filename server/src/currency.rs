@@ -0,0 +1,256 @@
+// external crates
+use chrono::NaiveDate;
+use std::sync::Arc;
+
+use crate::{db, errors, loaders::Loader};
+
+// the types that make up the domain
+mod money;
+mod rates;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::money::*;
+pub use self::rates::*;
+
+/// the currency every exchange rate is quoted against. it doesn't need a rate of its own
+pub const BASE: &str = "USD";
+
+/// the operations a storage backend has to support in order to serve exchange rates
+pub trait Repository: Send + Sync {
+    /// the latest rate for every currency we have one for, by currency code
+    fn latest_rates(&self) -> Result<Vec<ExchangeRate>, db::Error>;
+
+    /// the latest rate for each of the currencies, in the same order as the codes
+    fn latest_rates_by_currency(&self, currencies: &[String]) -> Result<Vec<Option<ExchangeRate>>, db::Error>;
+
+    /// store the rates, replacing any that were already stored for the same currency and day
+    fn save_rates(&self, rates: &[ExchangeRate]) -> Result<(), db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    rates: Loader<String, Option<ExchangeRate>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>) -> Client {
+        // every client gets its own loader so nothing is cached between requests
+        Client {
+            rates: Loader::new({
+                let repo = repo.clone();
+                move |currencies: &[String]| repo.latest_rates_by_currency(currencies)
+            }),
+            repo,
+        }
+    }
+
+    /// the latest rate for every currency we have one for
+    pub fn latest_rates(&self) -> Result<Vec<ExchangeRate>, db::Error> {
+        let rates = self.repo.latest_rates()?;
+        for rate in &rates {
+            self.rates.prime(rate.currency.clone(), Some(rate.clone()));
+        }
+
+        Ok(rates)
+    }
+
+    /// the latest rate for a currency, if we have one
+    pub fn rate(&self, currency: &str) -> Result<Option<ExchangeRate>, db::Error> {
+        self.rates.load(currency.to_string())
+    }
+
+    /// the money in another currency, or as it is if no currency is given
+    pub fn convert(&self, money: &Money, currency: Option<&str>) -> Result<Money, errors::Error> {
+        let currency = match currency {
+            Some(currency) => currency.trim().to_uppercase(),
+            None => return Ok(money.clone()),
+        };
+        if currency == money.currency {
+            return Ok(money.clone());
+        }
+
+        // go through the base currency, remembering the older of the two rates' dates since
+        // that's what limits how current the result is
+        let (from, from_date) = self.units_per_base(&money.currency)?;
+        let (to, to_date) = self.units_per_base(&currency)?;
+        let rate_date = match (from_date, to_date) {
+            (Some(from), Some(to)) => Some(from.min(to)),
+            (from, to) => from.or(to),
+        };
+
+        Ok(Money {
+            cents: (money.cents as f64 / from * to).round() as i64,
+            currency,
+            rate_date,
+        })
+    }
+
+    /// the money in the base currency so amounts in different currencies can be compared,
+    /// or nothing if we don't have a rate for its currency
+    pub fn in_base(&self, money: &Money) -> Result<Option<f64>, db::Error> {
+        if money.currency == BASE {
+            return Ok(Some(money.cents as f64));
+        }
+
+        Ok(self
+            .rate(&money.currency)?
+            .map(|rate| money.cents as f64 / rate.rate))
+    }
+
+    /// store a batch of rates, all or nothing, and return them as stored
+    pub fn import(&self, inputs: Vec<ExchangeRateInput>) -> Result<Vec<ExchangeRate>, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let rates: Vec<ExchangeRate> = inputs
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, input)| input.into_rate(&format!("rates.{}", index), &mut validation))
+            .collect();
+        validation.finish()?;
+
+        self.repo.save_rates(&rates)?;
+        self.rates.clear();
+        Ok(rates)
+    }
+
+    /// how many units of the currency a single unit of the base currency buys, along with
+    /// the date of the rate
+    fn units_per_base(&self, currency: &str) -> Result<(f64, Option<NaiveDate>), errors::Error> {
+        if currency == BASE {
+            return Ok((1.0, None));
+        }
+
+        match self.rate(currency)? {
+            Some(rate) => Ok((rate.rate, Some(rate.effective_on))),
+            None => Err(errors::Error::NotFound(format!("an exchange rate for {}", currency))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+
+    /// a client with rates for euros and pounds from a couple of days in October
+    fn client() -> Client {
+        let client = Client::new(storage::Backend::memory().currency());
+        client
+            .import(vec![
+                ExchangeRateInput {
+                    currency: "EUR".to_string(),
+                    rate: 0.9,
+                    effective_on: NaiveDate::from_ymd_opt(2024, 10, 1),
+                },
+                ExchangeRateInput {
+                    currency: "gbp".to_string(),
+                    rate: 0.8,
+                    effective_on: NaiveDate::from_ymd_opt(2024, 10, 5),
+                },
+            ])
+            .unwrap();
+
+        client
+    }
+
+    #[test]
+    fn converts_through_the_base_currency() {
+        let client = client();
+
+        let euros = client.convert(&Money::new(1000, "USD"), Some("eur")).unwrap();
+        assert_eq!((euros.cents, euros.currency.as_str()), (900, "EUR"));
+        assert_eq!(euros.rate_date, NaiveDate::from_ymd_opt(2024, 10, 1));
+
+        let dollars = client.convert(&Money::new(900, "EUR"), Some("USD")).unwrap();
+        assert_eq!((dollars.cents, dollars.currency.as_str()), (1000, "USD"));
+
+        // the older of the two rates is what the result is only as good as
+        let pounds = client.convert(&Money::new(900, "EUR"), Some("GBP")).unwrap();
+        assert_eq!((pounds.cents, pounds.currency.as_str()), (800, "GBP"));
+        assert_eq!(pounds.rate_date, NaiveDate::from_ymd_opt(2024, 10, 1));
+
+        // amounts are rounded to the nearest cent
+        let euros = client.convert(&Money::new(333, "USD"), Some("EUR")).unwrap();
+        assert_eq!(euros.cents, 300);
+    }
+
+    #[test]
+    fn leaves_money_alone_without_another_currency() {
+        let client = client();
+
+        let same = client.convert(&Money::new(375, "EUR"), Some("EUR")).unwrap();
+        assert_eq!((same.cents, same.rate_date), (375, None));
+        let unchanged = client.convert(&Money::new(375, "EUR"), None).unwrap();
+        assert_eq!((unchanged.cents, unchanged.currency.as_str()), (375, "EUR"));
+    }
+
+    #[test]
+    fn needs_a_rate_to_convert() {
+        let client = client();
+
+        match client.convert(&Money::new(1000, "USD"), Some("AUD")) {
+            Err(errors::Error::NotFound(message)) => assert_eq!(message, "an exchange rate for AUD"),
+            other => panic!("expected a missing rate, got {:?}", other),
+        }
+        assert_eq!(client.in_base(&Money::new(1000, "AUD")).unwrap(), None);
+        assert_eq!(client.in_base(&Money::new(900, "EUR")).unwrap(), Some(1000.0));
+        assert_eq!(client.in_base(&Money::new(900, "USD")).unwrap(), Some(900.0));
+    }
+
+    #[test]
+    fn newer_rates_win() {
+        let client = client();
+        client
+            .import(vec![ExchangeRateInput {
+                currency: "EUR".to_string(),
+                rate: 0.95,
+                effective_on: NaiveDate::from_ymd_opt(2024, 10, 7),
+            }])
+            .unwrap();
+
+        let euros = client.convert(&Money::new(1000, "USD"), Some("EUR")).unwrap();
+        assert_eq!(euros.cents, 950);
+        assert_eq!(euros.rate_date, NaiveDate::from_ymd_opt(2024, 10, 7));
+    }
+
+    #[test]
+    fn imports_are_all_or_nothing() {
+        let client = client();
+        let result = client.import(vec![
+            ExchangeRateInput {
+                currency: "AUD".to_string(),
+                rate: 1.5,
+                effective_on: None,
+            },
+            ExchangeRateInput {
+                currency: "USD".to_string(),
+                rate: 0.0,
+                effective_on: None,
+            },
+        ]);
+
+        match result {
+            Err(errors::Error::Validation(problems)) => {
+                let fields: Vec<&str> = problems.iter().map(|problem| problem.field.as_str()).collect();
+                assert_eq!(fields, vec!["rates.1.currency", "rates.1.rate"]);
+            }
+            other => panic!("expected the import to be turned down, got {:?}", other),
+        }
+        assert!(client.rate("AUD").unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_rate_files() {
+        let inputs = parse_rates("currency,rate\n# from the bank\nEUR, 0.9\n\nGBP,0.8,2024-10-05\n").unwrap();
+
+        assert_eq!(inputs.len(), 2);
+        assert_eq!((inputs[0].currency.as_str(), inputs[0].rate), ("EUR", 0.9));
+        assert_eq!(inputs[0].effective_on, None);
+        assert_eq!(inputs[1].effective_on, NaiveDate::from_ymd_opt(2024, 10, 5));
+        assert_eq!(
+            parse_rates("EUR,lots").err(),
+            Some("line 1: lots is not a number".to_string())
+        );
+    }
+}
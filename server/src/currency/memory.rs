@@ -0,0 +1,42 @@
+use super::{ExchangeRate, Repository};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn latest_rates(&self) -> Result<Vec<ExchangeRate>, db::Error> {
+        let tables = self.read();
+
+        let mut rates: Vec<ExchangeRate> = Vec::new();
+        for rate in &tables.exchange_rates {
+            match rates.iter_mut().find(|latest| latest.currency == rate.currency) {
+                Some(latest) if latest.effective_on < rate.effective_on => *latest = rate.clone(),
+                Some(_) => {}
+                None => rates.push(rate.clone()),
+            }
+        }
+        rates.sort_by(|a, b| a.currency.cmp(&b.currency));
+
+        Ok(rates)
+    }
+
+    fn latest_rates_by_currency(&self, currencies: &[String]) -> Result<Vec<Option<ExchangeRate>>, db::Error> {
+        let latest = self.latest_rates()?;
+
+        Ok(currencies
+            .iter()
+            .map(|currency| latest.iter().find(|rate| rate.currency == *currency).cloned())
+            .collect())
+    }
+
+    fn save_rates(&self, rates: &[ExchangeRate]) -> Result<(), db::Error> {
+        let mut tables = self.write();
+
+        for rate in rates {
+            tables.exchange_rates.retain(|existing| {
+                existing.currency != rate.currency || existing.effective_on != rate.effective_on
+            });
+            tables.exchange_rates.push(rate.clone());
+        }
+
+        Ok(())
+    }
+}
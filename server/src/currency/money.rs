@@ -0,0 +1,77 @@
+// external crates
+use chrono::NaiveDate;
+
+use crate::api;
+
+/// an amount of money in a single currency
+#[derive(Clone, Debug, PartialEq)]
+pub struct Money {
+    /// in hundredths of the currency's unit, so sums come out exact
+    pub cents: i64,
+    /// the ISO 4217 code of the currency (ie, USD or EUR)
+    pub currency: String,
+    /// the date of the exchange rate the amount was converted with, if it was converted
+    pub rate_date: Option<NaiveDate>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl Money {
+    /// the amount in the currency's unit (ie, 3.75)
+    fn amount(&self) -> f64 {
+        self.cents as f64 / 100.0
+    }
+
+    /// the ISO 4217 code of the currency (ie, USD or EUR)
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// the amount and currency for showing to people (ie, 3.75 USD)
+    fn formatted(&self) -> String {
+        self.to_string()
+    }
+
+    /// the date of the exchange rate the amount was converted with. this is null when the
+    /// amount is in the currency it was recorded in
+    fn rateDate(&self) -> Option<NaiveDate> {
+        self.rate_date
+    }
+}
+
+impl Money {
+    pub fn new(cents: i64, currency: &str) -> Money {
+        Money {
+            cents,
+            currency: currency.to_string(),
+            rate_date: None,
+        }
+    }
+
+    /// the amount without its currency, with two decimal places (ie, 3.75)
+    pub fn to_decimal(&self) -> String {
+        let sign = if self.cents < 0 { "-" } else { "" };
+        let cents = self.cents.abs();
+        format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+
+    /// the same amount of money, some number of times over
+    pub fn times(&self, quantity: i32) -> Money {
+        Money {
+            cents: self.cents * i64::from(quantity),
+            ..self.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.to_decimal(), self.currency)
+    }
+}
+
+/// whether the text is a currency code in the form we store them (three capital letters)
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
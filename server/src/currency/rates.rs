@@ -0,0 +1,119 @@
+// external crates
+use chrono::{NaiveDate, Utc};
+
+use super::{is_currency_code, BASE};
+use crate::{api, errors};
+
+/// how many units of a currency a US dollar bought on a given day
+#[derive(Clone, Debug)]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub rate: f64,
+    pub effective_on: NaiveDate,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ExchangeRate {
+    /// the ISO 4217 code of the currency (ie, EUR)
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// how many units of the currency a US dollar buys
+    fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// the day the rate was in effect
+    fn effectiveOn(&self) -> NaiveDate {
+        self.effective_on
+    }
+}
+
+/// the rate for a currency on a given day
+#[derive(juniper::GraphQLInputObject)]
+pub struct ExchangeRateInput {
+    /// the ISO 4217 code of the currency (ie, EUR)
+    pub currency: String,
+    /// how many units of the currency a US dollar buys
+    pub rate: f64,
+    /// the day the rate was in effect. defaults to today
+    pub effective_on: Option<NaiveDate>,
+}
+
+impl ExchangeRateInput {
+    /// check the input over and turn it into a rate, recording any problems under fields
+    /// that start with the prefix (ie, rates.2.currency)
+    pub(super) fn into_rate(self, prefix: &str, validation: &mut errors::Validation) -> Option<ExchangeRate> {
+        let mut valid = true;
+        let mut problem = |field: &str, message: &str| {
+            validation.add(&format!("{}.{}", prefix, field), message);
+            valid = false;
+        };
+
+        let currency = self.currency.trim().to_uppercase();
+        if !is_currency_code(&currency) {
+            problem("currency", "must be a three letter currency code");
+        } else if currency == BASE {
+            problem("currency", "is the currency every rate is quoted against");
+        }
+        if !self.rate.is_finite() || self.rate <= 0.0 {
+            problem("rate", "must be more than zero");
+        }
+        let today = Utc::now().naive_utc().date();
+        let effective_on = self.effective_on.unwrap_or(today);
+        if effective_on > today {
+            problem("effectiveOn", "must not be in the future");
+        }
+
+        if valid {
+            Some(ExchangeRate {
+                currency,
+                rate: self.rate,
+                effective_on,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// read rates out of a file with a line for each currency, as `currency,rate` or
+/// `currency,rate,effective_on`. blank lines, lines starting with # and a header row that
+/// starts with "currency" are skipped
+pub fn parse_rates(text: &str) -> Result<Vec<ExchangeRateInput>, String> {
+    let mut inputs = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.to_lowercase().starts_with("currency") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (currency, rate, effective_on) = match fields.as_slice() {
+            [currency, rate] => (currency, rate, None),
+            [currency, rate, effective_on] => (currency, rate, Some(effective_on)),
+            _ => return Err(format!("line {}: expected currency,rate[,effective_on]", number + 1)),
+        };
+        let rate = rate
+            .parse()
+            .map_err(|_| format!("line {}: {} is not a number", number + 1, rate))?;
+        let effective_on = match effective_on {
+            Some(date) => Some(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|_| format!("line {}: {} is not a YYYY-MM-DD date", number + 1, date))?,
+            ),
+            None => None,
+        };
+
+        inputs.push(ExchangeRateInput {
+            currency: currency.to_string(),
+            rate,
+            effective_on,
+        });
+    }
+
+    Ok(inputs)
+}
@@ -0,0 +1,59 @@
+// external crates
+use rusqlite::{params, Row, NO_PARAMS};
+
+use super::{ExchangeRate, Repository};
+use crate::db;
+
+/// the latest rate for each currency. the {filter} placeholder narrows down the currencies
+const LATEST_RATES: &str = "
+    SELECT currency, rate, effective_on FROM exchange_rates AS rates
+    WHERE {filter} AND effective_on = (
+        SELECT MAX(effective_on) FROM exchange_rates WHERE currency = rates.currency
+    )
+    ORDER BY currency";
+
+impl Repository for db::Pool {
+    fn latest_rates(&self) -> Result<Vec<ExchangeRate>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&LATEST_RATES.replace("{filter}", "1"))?;
+
+        let rates = statement
+            .query_map(NO_PARAMS, rate_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(rates)
+    }
+
+    fn latest_rates_by_currency(&self, currencies: &[String]) -> Result<Vec<Option<ExchangeRate>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &LATEST_RATES.replace("{filter}", "currency IN ({keys})"),
+            currencies,
+            rate_from_row,
+            |rate| rate.currency.clone(),
+        )
+    }
+
+    fn save_rates(&self, rates: &[ExchangeRate]) -> Result<(), db::Error> {
+        let mut conn = self.get()?;
+        let tx = conn.transaction()?;
+        for rate in rates {
+            tx.execute(
+                "INSERT OR REPLACE INTO exchange_rates (currency, effective_on, rate) VALUES (?, ?, ?)",
+                params![rate.currency, rate.effective_on, rate.rate],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// build a rate out of a row containing its currency, rate and effective date
+fn rate_from_row(row: &Row) -> rusqlite::Result<ExchangeRate> {
+    Ok(ExchangeRate {
+        currency: row.get(0)?,
+        rate: row.get(1)?,
+        effective_on: row.get(2)?,
+    })
+}
@@ -0,0 +1,197 @@
+// external crates
+use dataloader::LoadError;
+use rusqlite::types::ToSql;
+use rusqlite::{Connection, Row, NO_PARAMS};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::path::PathBuf;
+
+use crate::products::Color;
+
+/// a pool of database connections shared between every request
+pub type Pool = r2d2::Pool<ConnectionManager>;
+
+/// the migrations that build up the schema, in the order they have to be applied. a
+/// database's user_version pragma records how many of these it has already seen.
+const MIGRATIONS: &[&str] = &[
+    include_str!("../migrations/0001_create_paints.sql"),
+    include_str!("../migrations/0002_seed_paints.sql"),
+    include_str!("../migrations/0003_create_brands.sql"),
+    include_str!("../migrations/0004_create_paint_equivalents.sql"),
+    include_str!("../migrations/0005_create_product_videos.sql"),
+    include_str!("../migrations/0006_add_paint_release_dates.sql"),
+    include_str!("../migrations/0007_create_users.sql"),
+    include_str!("../migrations/0008_add_user_roles.sql"),
+    include_str!("../migrations/0009_create_api_keys.sql"),
+    include_str!("../migrations/0010_create_inventory_items.sql"),
+    include_str!("../migrations/0011_create_wishlist_items.sql"),
+    include_str!("../migrations/0012_create_retailers.sql"),
+    include_str!("../migrations/0013_create_exchange_rates.sql"),
+    include_str!("../migrations/0014_create_price_alerts.sql"),
+    include_str!("../migrations/0015_create_recipes.sql"),
+];
+
+/// open (or create) the database at the given path and bring its schema up to date
+pub fn connect(path: &str) -> Result<Pool, Error> {
+    let pool = r2d2::Pool::new(ConnectionManager {
+        path: PathBuf::from(path),
+    })?;
+
+    // apply any migrations before the pool is handed to anyone else
+    let mut conn = pool.get()?;
+    migrate(&mut conn)?;
+
+    Ok(pool)
+}
+
+/// apply every migration the database hasn't seen yet, each in its own transaction
+fn migrate(conn: &mut Connection) -> Result<(), Error> {
+    let applied: i64 = conn.query_row("PRAGMA user_version", NO_PARAMS, |row| row.get(0))?;
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        // pragmas can't be parameterized so we have to format the version in ourselves
+        tx.execute_batch(&format!("PRAGMA user_version = {}", version + 1))?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// a comma separated list of n numbered parameters, for building IN (...) clauses. the
+/// parameters are numbered so the same list can appear more than once in a query.
+pub fn placeholders(n: usize) -> String {
+    (1..=n).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ")
+}
+
+/// run a query with a `{keys}` placeholder for an IN (...) clause over the given keys and
+/// group the rows by the key they belong to, in the same order as the keys. this is the
+/// shape every batched lookup needs to hand back to its loader.
+pub fn load_grouped<K, V, F, G>(
+    conn: &Connection,
+    query: &str,
+    keys: &[K],
+    from_row: F,
+    key_of: G,
+) -> Result<Vec<Vec<V>>, Error>
+where
+    K: ToSql + Eq + Hash,
+    V: Clone,
+    F: FnMut(&Row) -> rusqlite::Result<V>,
+    G: Fn(&V) -> K,
+{
+    let mut statement = conn.prepare(&query.replace("{keys}", &placeholders(keys.len())))?;
+
+    // the rows come back in whatever order sqlite likes so we have to line them up ourselves
+    let mut groups = HashMap::new();
+    for row in statement.query_map(keys, from_row)? {
+        let value = row?;
+        groups.entry(key_of(&value)).or_insert_with(Vec::new).push(value);
+    }
+
+    Ok(keys
+        .iter()
+        .map(|key| groups.get(key).cloned().unwrap_or_default())
+        .collect())
+}
+
+/// like load_grouped but for queries where each key matches at most one row
+pub fn load_by_key<K, V, F, G>(
+    conn: &Connection,
+    query: &str,
+    keys: &[K],
+    from_row: F,
+    key_of: G,
+) -> Result<Vec<Option<V>>, Error>
+where
+    K: ToSql + Eq + Hash,
+    V: Clone,
+    F: FnMut(&Row) -> rusqlite::Result<V>,
+    G: Fn(&V) -> K,
+{
+    Ok(load_grouped(conn, query, keys, from_row, key_of)?
+        .into_iter()
+        .map(|group| group.into_iter().next())
+        .collect())
+}
+
+/// hands out connections to the sqlite file that backs the index
+pub struct ConnectionManager {
+    path: PathBuf,
+}
+
+impl r2d2::ManageConnection for ConnectionManager {
+    type Connection = Connection;
+    type Error = rusqlite::Error;
+
+    fn connect(&self) -> Result<Connection, rusqlite::Error> {
+        let conn = Connection::open(&self.path)?;
+        // sqlite leaves foreign key checks off unless you ask for them on every connection
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+        // paints are filtered and sorted by how their color looks, which sqlite can't work
+        // out from a hex string on its own
+        conn.create_scalar_function("paint_hue", 1, true, |ctx| {
+            Ok(Color::from_hex(&ctx.get::<String>(0)?).map(|color| color.to_lab().hue()))
+        })?;
+        conn.create_scalar_function("paint_lightness", 1, true, |ctx| {
+            Ok(Color::from_hex(&ctx.get::<String>(0)?).map(|color| color.to_lab().l))
+        })?;
+
+        Ok(conn)
+    }
+
+    fn is_valid(&self, conn: &mut Connection) -> Result<(), rusqlite::Error> {
+        conn.execute_batch("")
+    }
+
+    fn has_broken(&self, _: &mut Connection) -> bool {
+        false
+    }
+}
+
+/// the ways talking to the database can go wrong
+#[derive(Debug)]
+pub enum Error {
+    /// we couldn't get a connection out of the pool
+    Pool(r2d2::Error),
+    /// the database rejected what we asked of it
+    Sqlite(rusqlite::Error),
+    /// a batched lookup failed for everyone waiting on it
+    Batch(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Pool(err) => write!(f, "could not connect to the database: {}", err),
+            Error::Sqlite(err) => write!(f, "database error: {}", err),
+            Error::Batch(message) => write!(f, "batched lookup failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<r2d2::Error> for Error {
+    fn from(err: r2d2::Error) -> Error {
+        Error::Pool(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Error {
+        Error::Sqlite(err)
+    }
+}
+
+impl From<LoadError<String>> for Error {
+    fn from(err: LoadError<String>) -> Error {
+        match err {
+            LoadError::BatchFn(message) => Error::Batch(message),
+            other => Error::Batch(format!("{:?}", other)),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// external crates
+use juniper::{FieldError, Object, Value};
+
+use crate::db;
+
+/// the ways a mutation can fail that a client should be able to tell apart. each one
+/// turns into a GraphQL error with a machine readable code in its extensions.
+#[derive(Debug)]
+pub enum Error {
+    /// the input didn't hold up, along with everything that was wrong with it
+    Validation(Vec<Problem>),
+    /// the record being acted on doesn't exist
+    NotFound(String),
+    /// the caller couldn't prove who they are
+    Unauthenticated(String),
+    /// the caller isn't allowed to do what they asked
+    Forbidden(String),
+    /// something went wrong talking to storage
+    Storage(db::Error),
+}
+
+/// a single thing wrong with the input to a mutation
+#[derive(Debug)]
+pub struct Problem {
+    /// the name of the offending input field, as the client sent it
+    pub field: String,
+    pub message: String,
+}
+
+/// collects every problem with an input so the client can fix them all in one go
+#[derive(Default)]
+pub struct Validation {
+    problems: Vec<Problem>,
+}
+
+impl Validation {
+    pub fn new() -> Validation {
+        Validation::default()
+    }
+
+    /// record a problem with the given field
+    pub fn add(&mut self, field: &str, message: &str) {
+        self.problems.push(Problem {
+            field: field.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    /// record a problem with the field if the value is blank
+    pub fn require(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be blank");
+        }
+    }
+
+    /// fail with the problems we've found, if there were any
+    pub fn finish(self) -> Result<(), Error> {
+        if self.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Validation(self.problems))
+        }
+    }
+}
+
+impl From<db::Error> for Error {
+    fn from(err: db::Error) -> Error {
+        Error::Storage(err)
+    }
+}
+
+impl From<Error> for FieldError {
+    fn from(err: Error) -> FieldError {
+        let mut extensions = Object::with_capacity(2);
+
+        let message = match err {
+            Error::Validation(problems) => {
+                extensions.add_field("code", Value::scalar("VALIDATION_FAILED"));
+                extensions.add_field(
+                    "problems",
+                    Value::list(problems.into_iter().map(Problem::into_value).collect()),
+                );
+                "the input is invalid".to_string()
+            }
+            Error::NotFound(what) => {
+                extensions.add_field("code", Value::scalar("NOT_FOUND"));
+                format!("could not find {}", what)
+            }
+            Error::Unauthenticated(message) => {
+                extensions.add_field("code", Value::scalar("UNAUTHENTICATED"));
+                message
+            }
+            Error::Forbidden(message) => {
+                extensions.add_field("code", Value::scalar("FORBIDDEN"));
+                message
+            }
+            Error::Storage(err) => {
+                extensions.add_field("code", Value::scalar("INTERNAL"));
+                err.to_string()
+            }
+        };
+
+        FieldError::new(message, Value::object(extensions))
+    }
+}
+
+impl Problem {
+    fn into_value(self) -> Value {
+        let mut problem = Object::with_capacity(2);
+        problem.add_field("field", Value::scalar(self.field));
+        problem.add_field("message", Value::scalar(self.message));
+        Value::object(problem)
+    }
+}
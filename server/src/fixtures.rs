@@ -0,0 +1,177 @@
+// external crates
+use chrono::NaiveDate;
+
+use crate::products::{
+    Brand, Color, Equivalence, EquivalenceSource, Finish, Paint, PaintUsage, ProductLine,
+    ProductVideo,
+};
+
+/// the brands a freshly migrated database starts off with
+pub fn brands() -> Vec<Brand> {
+    vec!["Citadel", "Vallejo", "The Army Painter"]
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| Brand {
+            id: index as i32 + 1,
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// the product lines a freshly migrated database starts off with
+pub fn product_lines() -> Vec<ProductLine> {
+    // brand, name
+    let entries = vec![
+        (1, "Base"),
+        (1, "Shade"),
+        (1, "Contrast"),
+        (2, "Model Color"),
+        (2, "Game Color"),
+        (3, "Warpaints"),
+    ];
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (brand_id, name))| ProductLine {
+            id: index as i32 + 1,
+            brand_id,
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// the paints a freshly migrated database starts off with
+pub fn paints() -> Vec<Paint> {
+    // line, name, sku, hex, finish, volume, discontinued
+    let entries = vec![
+        (1, "Abaddon Black", None, "#231f20", Finish::Matte, 12.0, false),
+        (1, "Mephiston Red", None, "#9a1115", Finish::Matte, 12.0, false),
+        (1, "Wraithbone", None, "#dbd1b2", Finish::Matte, 12.0, false),
+        (1, "Retributor Armour", None, "#b98e35", Finish::Metallic, 12.0, false),
+        (1, "Leadbelcher", None, "#888d8f", Finish::Metallic, 12.0, false),
+        (2, "Nuln Oil", None, "#14100e", Finish::Wash, 18.0, false),
+        (2, "Agrax Earthshade", None, "#5a4a30", Finish::Wash, 18.0, false),
+        (3, "Blood Angels Red", None, "#b7121b", Finish::Contrast, 18.0, false),
+        (3, "Black Templar", None, "#1f1f21", Finish::Contrast, 18.0, false),
+        (4, "Black", Some("70.950"), "#171717", Finish::Matte, 17.0, false),
+        (4, "White", Some("70.951"), "#f7f7f5", Finish::Matte, 17.0, false),
+        (4, "Flat Red", Some("70.957"), "#a51a1e", Finish::Matte, 17.0, false),
+        (5, "Dead White", Some("72.001"), "#fcfcfc", Finish::Matte, 17.0, false),
+        (5, "Bloody Red", Some("72.010"), "#c21e27", Finish::Matte, 17.0, false),
+        (5, "Black", Some("72.051"), "#0b0b0b", Finish::Matte, 17.0, false),
+        (6, "Matt Black", None, "#0d0d0d", Finish::Matte, 18.0, false),
+        (6, "Matt White", None, "#f4f4f4", Finish::Matte, 18.0, false),
+        (6, "Pure Red", None, "#c3161b", Finish::Matte, 18.0, false),
+    ];
+
+    // ids line up with the order the seed migration inserts rows in
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (line_id, name, sku, hex, finish, volume, discontinued))| Paint {
+            id: index as i32 + 1,
+            line_id,
+            name: name.to_string(),
+            sku: sku.map(|sku: &str| sku.to_string()),
+            color: Color::from_hex(hex).expect("fixture colors must be valid hex"),
+            finish,
+            volume_ml: volume,
+            discontinued,
+            // the release dates migration only knows about the citadel lines
+            released_on: match line_id {
+                1 | 2 => NaiveDate::from_ymd_opt(2012, 6, 2),
+                3 => NaiveDate::from_ymd_opt(2019, 6, 8),
+                _ => None,
+            },
+        })
+        .collect()
+}
+
+/// the paint equivalences a freshly migrated database starts off with
+pub fn paint_equivalents() -> Vec<Equivalence> {
+    // paint, equivalent, source
+    let entries = vec![
+        (1, 15, EquivalenceSource::Official),
+        (1, 16, EquivalenceSource::Community),
+        (2, 14, EquivalenceSource::Official),
+        (2, 18, EquivalenceSource::Community),
+    ];
+
+    entries
+        .into_iter()
+        .map(|(paint_id, equivalent_id, source)| Equivalence {
+            paint_id,
+            equivalent_id,
+            source,
+        })
+        .collect()
+}
+
+/// the product videos a freshly migrated database starts off with
+pub fn product_videos() -> Vec<ProductVideo> {
+    // title, channel, url, duration, published on
+    let entries = vec![
+        (
+            "Blood Angels Armour in Three Steps",
+            "Brushwork Basics",
+            "https://videos.example.com/blood-angels-armour",
+            754,
+            (2019, 3, 14),
+        ),
+        (
+            "Black Armour That Still Reads as Black",
+            "Tabletop Standard",
+            "https://videos.example.com/black-armour",
+            1260,
+            (2019, 5, 2),
+        ),
+        (
+            "Speed Painting Skeletons",
+            "Tabletop Standard",
+            "https://videos.example.com/speed-skeletons",
+            540,
+            (2019, 6, 20),
+        ),
+    ];
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (title, channel, url, duration, (year, month, day)))| ProductVideo {
+            id: index as i32 + 1,
+            title: title.to_string(),
+            channel: channel.to_string(),
+            url: url.to_string(),
+            duration_seconds: duration,
+            published_on: NaiveDate::from_ymd_opt(year, month, day)
+                .expect("fixture dates must be valid"),
+        })
+        .collect()
+}
+
+/// the paints used in the product videos a freshly migrated database starts off with
+pub fn paint_usages() -> Vec<PaintUsage> {
+    // video, paint, start, note
+    let entries = vec![
+        (1, 2, 45, Some("basecoat over a black primer")),
+        (1, 8, 210, Some("glazed over the basecoat")),
+        (1, 6, 420, Some("recess shade")),
+        (2, 10, 30, Some("basecoat")),
+        (2, 11, 300, Some("edge highlights")),
+        (2, 13, 900, Some("final spot highlights")),
+        (3, 3, 20, None),
+        (3, 7, 120, Some("all over shade")),
+        (3, 17, 400, Some("drybrush")),
+    ];
+
+    entries
+        .into_iter()
+        .map(|(video_id, paint_id, start_seconds, note)| PaintUsage {
+            video_id,
+            paint_id,
+            start_seconds,
+            note: note.map(str::to_string),
+        })
+        .collect()
+}
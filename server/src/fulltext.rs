@@ -0,0 +1,263 @@
+// external crates
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// fold text into the form we index and search by: lowercase, without accents and with
+/// anything that isn't a letter or a digit turned into a space
+pub fn normalize(text: &str) -> String {
+    // splitting accented letters into the letter and the accent lets us drop the accent
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect()
+}
+
+/// the normalized words in a piece of text
+pub fn words(text: &str) -> Vec<String> {
+    normalize(text).split_whitespace().map(str::to_string).collect()
+}
+
+/// an inverted index from words to the documents they show up in, each document carrying
+/// a value. words are matched loosely so a search still finds what it's after with a typo
+/// or two, or with only the start of a word.
+pub struct TextIndex<T> {
+    documents: Vec<T>,
+    /// every distinct word in the documents
+    terms: Vec<String>,
+    term_ids: HashMap<String, usize>,
+    /// the documents each term shows up in along with the weight of the field it's in
+    postings: Vec<Vec<(usize, f64)>>,
+    /// the terms each run of three characters shows up in, to narrow down the terms that
+    /// could be a misspelling of a word without comparing against every one
+    trigrams: HashMap<String, Vec<usize>>,
+}
+
+impl<T> TextIndex<T> {
+    /// build an index out of documents made up of fields, each with a weight for how much
+    /// a match in it counts for
+    pub fn build(documents: Vec<(T, Vec<(String, f64)>)>) -> TextIndex<T> {
+        let mut index = TextIndex {
+            documents: Vec::with_capacity(documents.len()),
+            terms: Vec::new(),
+            term_ids: HashMap::new(),
+            postings: Vec::new(),
+            trigrams: HashMap::new(),
+        };
+
+        for (document, (value, fields)) in documents.into_iter().enumerate() {
+            index.documents.push(value);
+            for (text, weight) in fields {
+                for word in words(&text) {
+                    let term = index.term(word);
+                    let postings = &mut index.postings[term];
+
+                    // a word that shows up in more than one field counts for the best of them
+                    match postings.iter_mut().find(|(found, _)| *found == document) {
+                        Some(posting) => posting.1 = posting.1.max(weight),
+                        None => postings.push((document, weight)),
+                    }
+                }
+            }
+        }
+
+        index
+    }
+
+    /// the up to `limit` documents that best match the query and whose values pass the
+    /// filter, best first, along with how well they matched (from 0 to 1)
+    pub fn search<F>(&self, query: &str, limit: usize, accept: F) -> Vec<(&T, f64)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let words = words(query);
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        // every word in the query counts for its best match in each document
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for word in &words {
+            let mut best: HashMap<usize, f64> = HashMap::new();
+            for (term, similarity) in self.similar_terms(word) {
+                for (document, weight) in &self.postings[term] {
+                    let score = best.entry(*document).or_insert(0.0);
+                    *score = score.max(similarity * weight);
+                }
+            }
+            for (document, score) in best {
+                *scores.entry(document).or_insert(0.0) += score;
+            }
+        }
+
+        let mut matches: Vec<(usize, f64)> = scores
+            .into_iter()
+            .filter(|(document, _)| accept(&self.documents[*document]))
+            .map(|(document, score)| (document, score / words.len() as f64))
+            .collect();
+        // ties go to whichever document was indexed first so the order is stable
+        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+        matches.truncate(limit);
+
+        matches
+            .into_iter()
+            .map(|(document, score)| (&self.documents[document], score))
+            .collect()
+    }
+
+    /// the id of a term, adding it to the index if it's new
+    fn term(&mut self, word: String) -> usize {
+        if let Some(term) = self.term_ids.get(&word) {
+            return *term;
+        }
+
+        let term = self.terms.len();
+        for trigram in trigrams(&word) {
+            self.trigrams.entry(trigram).or_default().push(term);
+        }
+        self.term_ids.insert(word.clone(), term);
+        self.terms.push(word);
+        self.postings.push(Vec::new());
+
+        term
+    }
+
+    /// the terms that could be what the word was meant to be, along with how alike they
+    /// are (from 0 to 1)
+    fn similar_terms(&self, word: &str) -> Vec<(usize, f64)> {
+        let length = word.chars().count();
+        let typos = typos_allowed(length);
+
+        let candidates: HashSet<usize> = trigrams(word)
+            .iter()
+            .filter_map(|trigram| self.trigrams.get(trigram))
+            .flatten()
+            .cloned()
+            .collect();
+
+        let mut similar = Vec::new();
+        for term in candidates {
+            let text = &self.terms[term];
+            let term_length = text.chars().count();
+
+            if text == word {
+                similar.push((term, 1.0));
+            } else if typos > 0 && term_length.max(length) - term_length.min(length) <= typos {
+                if let Some(distance) = distance(word, text, typos) {
+                    similar.push((term, 1.0 - distance as f64 / (length + 1) as f64));
+                }
+            }
+            // someone still typing the word gets partial credit for how much of it they have
+            if length >= MIN_PREFIX && term_length > length && text.starts_with(word) {
+                similar.push((term, PREFIX_WEIGHT * length as f64 / term_length as f64));
+            }
+        }
+
+        similar
+    }
+}
+
+/// the shortest word we'll treat as the start of a longer one
+const MIN_PREFIX: usize = 3;
+
+/// how much matching the start of a word counts for compared to matching all of it
+const PREFIX_WEIGHT: f64 = 0.8;
+
+/// how many typos we put up with in a word. short words would match far too much else
+fn typos_allowed(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// the runs of three characters in a word, padded so the start and end of the word get
+/// runs of their own
+fn trigrams(word: &str) -> Vec<String> {
+    let padded: Vec<char> = format!("  {} ", word).chars().collect();
+    padded.windows(3).map(|run| run.iter().collect()).collect()
+}
+
+/// the number of single character insertions, deletions, substitutions and swaps of
+/// neighbouring characters it takes to turn one word into the other, if it's no more
+/// than `max`
+fn distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // we only ever need the rows for the last two characters of `a` on top of the current one
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+
+        // the distance can only grow from here
+        if current.iter().all(|cell| *cell > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+
+    Some(previous[b.len()]).filter(|distance| *distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_counts_edits() {
+        assert_eq!(distance("black", "black", 2), Some(0));
+        // a substitution, an insertion and a deletion
+        assert_eq!(distance("black", "blank", 2), Some(1));
+        assert_eq!(distance("black", "blacks", 2), Some(1));
+        assert_eq!(distance("black", "back", 2), Some(1));
+        // two neighbouring characters swapped around is a single typo
+        assert_eq!(distance("black", "balck", 2), Some(1));
+        assert_eq!(distance("mephiston", "mehpitson", 2), Some(2));
+        assert_eq!(distance("", "red", 3), Some(3));
+    }
+
+    #[test]
+    fn distance_gives_up_past_the_limit() {
+        assert_eq!(distance("black", "white", 2), None);
+        assert_eq!(distance("abaddon", "abdaodn", 1), None);
+        assert_eq!(distance("red", "", 2), None);
+    }
+
+    #[test]
+    fn normalizes_accents_case_and_punctuation() {
+        assert_eq!(normalize("Crème-Brûlée"), "creme brulee");
+        assert_eq!(words("  Blood Angels' Red!"), vec!["blood", "angels", "red"]);
+        assert!(words("--").is_empty());
+    }
+
+    #[test]
+    fn search_tolerates_typos_and_prefixes() {
+        let index = TextIndex::build(vec![
+            (1, vec![("Abaddon Black".to_string(), 1.0)]),
+            (2, vec![("Mephiston Red".to_string(), 1.0)]),
+            (3, vec![("Black Templar".to_string(), 1.0)]),
+        ]);
+        let ids = |query: &str| -> Vec<i32> {
+            index.search(query, 10, |_| true).into_iter().map(|(id, _)| *id).collect()
+        };
+
+        assert_eq!(ids("abaddon black"), vec![1, 3]);
+        assert_eq!(ids("mephsiton"), vec![2]);
+        assert_eq!(ids("temp"), vec![3]);
+        assert_eq!(ids("green"), Vec::<i32>::new());
+        assert_eq!(index.search("black", 10, |id| *id != 1).len(), 1);
+    }
+}
@@ -0,0 +1,67 @@
+// external crates
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::{Outcome, State};
+
+use crate::{accounts, storage};
+
+/// who a request was made by, going by its Authorization and X-Api-Key headers. requests
+/// without either are anonymous while requests with one we can't make sense of are turned
+/// away, so a client holding onto an old token or key finds out instead of quietly being
+/// treated as anonymous.
+pub struct Credentials {
+    pub session: Option<accounts::Session>,
+    /// the key the request was made with, already counted against its quota
+    pub api_key: Option<accounts::ApiKey>,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Credentials {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Credentials, ()> {
+        Outcome::Success(Credentials {
+            session: session(request)?,
+            api_key: api_key(request)?,
+        })
+    }
+}
+
+/// the session the request was made with, if it came with a token
+fn session(request: &Request) -> request::Outcome<Option<accounts::Session>, ()> {
+    let header = match request.headers().get_one("Authorization") {
+        Some(header) => header,
+        None => return Outcome::Success(None),
+    };
+
+    // we only understand "Bearer <token>"
+    let mut parts = header.trim().splitn(2, ' ');
+    let scheme = parts.next().unwrap_or_default();
+    let token = parts.next().unwrap_or_default();
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return Outcome::Failure((Status::Unauthorized, ()));
+    }
+
+    let sessions = request.guard::<State<accounts::Sessions>>()?;
+    match sessions.verify(token) {
+        Some(session) => Outcome::Success(Some(session)),
+        None => Outcome::Failure((Status::Unauthorized, ())),
+    }
+}
+
+/// the key the request was made with, if it came with one. every request made with a key
+/// counts against its quota, and once that's used up the key is turned away until the
+/// next day
+fn api_key(request: &Request) -> request::Outcome<Option<accounts::ApiKey>, ()> {
+    let key = match request.headers().get_one("X-Api-Key") {
+        Some(key) => key,
+        None => return Outcome::Success(None),
+    };
+
+    let backend = request.guard::<State<storage::Backend>>()?;
+    match accounts::use_key(&*backend.accounts(), key) {
+        Ok(accounts::KeyUse::Accepted(api_key)) => Outcome::Success(Some(api_key)),
+        Ok(accounts::KeyUse::Invalid) => Outcome::Failure((Status::Unauthorized, ())),
+        Ok(accounts::KeyUse::OverQuota) => Outcome::Failure((Status::TooManyRequests, ())),
+        Err(_) => Outcome::Failure((Status::InternalServerError, ())),
+    }
+}
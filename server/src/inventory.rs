@@ -0,0 +1,159 @@
+// external crates
+use std::sync::Arc;
+
+use crate::{accounts::User, db, errors, loaders::Loader, products};
+
+// the types that make up the domain
+mod items;
+mod wishlist;
+// what the inventory can tell us about other records
+mod coverage;
+mod shopping;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::coverage::*;
+pub use self::items::*;
+pub use self::shopping::*;
+pub use self::wishlist::*;
+
+/// the operations a storage backend has to support in order to serve inventories
+pub trait Repository: Send + Sync {
+    /// the items with the matching ids, in the same order as the ids
+    fn items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<InventoryItem>>, db::Error>;
+
+    /// the items each of the users owns (in the order they were added), in the same order
+    /// as the user ids
+    fn items_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<InventoryItem>>, db::Error>;
+
+    /// store a new item, ignoring its id, and return it with the id it was given
+    fn insert_item(&self, item: &InventoryItem) -> Result<InventoryItem, db::Error>;
+
+    /// overwrite the item with the same id, returning false if there isn't one
+    fn update_item(&self, item: &InventoryItem) -> Result<bool, db::Error>;
+
+    /// remove the item with the given id, returning false if there isn't one
+    fn delete_item(&self, id: i32) -> Result<bool, db::Error>;
+
+    /// the wishlist items with the matching ids, in the same order as the ids
+    fn wishlist_items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<WishlistItem>>, db::Error>;
+
+    /// the paints each of the users wants (in the order they were first added), in the same
+    /// order as the user ids
+    fn wishlist_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<WishlistItem>>, db::Error>;
+
+    /// store a new wishlist item, ignoring its id, and return it with the id it was given
+    fn insert_wishlist_item(&self, item: &WishlistItem) -> Result<WishlistItem, db::Error>;
+
+    /// overwrite the wishlist item with the same id, returning false if there isn't one
+    fn update_wishlist_item(&self, item: &WishlistItem) -> Result<bool, db::Error>;
+
+    /// remove the wishlist item with the given id, returning false if there isn't one
+    fn delete_wishlist_item(&self, id: i32) -> Result<bool, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    items: Loader<i32, Option<InventoryItem>>,
+    items_by_user: Loader<i32, Vec<InventoryItem>>,
+    wishlist_items: Loader<i32, Option<WishlistItem>>,
+    wishlist_by_user: Loader<i32, Vec<WishlistItem>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>) -> Client {
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            items: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.items_by_id(ids)
+            }),
+            items_by_user: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.items_by_user(ids)
+            }),
+            wishlist_items: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.wishlist_items_by_id(ids)
+            }),
+            wishlist_by_user: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.wishlist_by_user(ids)
+            }),
+            repo,
+        }
+    }
+
+    /// an item by its id, as long as it belongs to the user. nobody gets to see what
+    /// anyone else owns
+    pub fn item(&self, owner: &User, id: i32) -> Result<Option<InventoryItem>, db::Error> {
+        Ok(self.items.load(id)?.filter(|item| item.user_id == owner.id))
+    }
+
+    /// everything the user owns, in the order they added it
+    pub fn items_for_user(
+        &self,
+        user_id: i32,
+        products: &products::Client,
+    ) -> Result<Vec<InventoryItem>, db::Error> {
+        let items = self.items_by_user.load(user_id)?;
+
+        // the paints are usually the next thing asked for so we want them in a single batch
+        products.expect_paints(items.iter().map(|item| item.paint_id));
+
+        Ok(items)
+    }
+
+    /// record a paint the user owns
+    pub fn add(
+        &self,
+        owner: &User,
+        input: InventoryItemInput,
+        products: &products::Client,
+    ) -> Result<InventoryItem, errors::Error> {
+        let item = input.into_item(0, owner, products)?;
+        let item = self.repo.insert_item(&item)?;
+
+        self.items.replace(item.id, Some(item.clone()));
+        self.items_by_user.clear();
+        Ok(item)
+    }
+
+    /// change what the user has recorded about one of their paints
+    pub fn update(
+        &self,
+        owner: &User,
+        id: i32,
+        patch: InventoryItemPatch,
+    ) -> Result<InventoryItem, errors::Error> {
+        let item = match self.item(owner, id)? {
+            Some(item) => patch.apply(item)?,
+            None => return Err(not_found(id)),
+        };
+        if !self.repo.update_item(&item)? {
+            return Err(not_found(id));
+        }
+
+        // make sure the rest of the request sees the new version
+        self.items.replace(id, Some(item.clone()));
+        self.items_by_user.clear();
+        Ok(item)
+    }
+
+    /// forget about one of the user's paints
+    pub fn remove(&self, owner: &User, id: i32) -> Result<(), errors::Error> {
+        if self.item(owner, id)?.is_none() || !self.repo.delete_item(id)? {
+            return Err(not_found(id));
+        }
+
+        self.items.replace(id, None);
+        self.items_by_user.clear();
+        Ok(())
+    }
+}
+
+/// the error for an item that doesn't exist (or belongs to someone else)
+fn not_found(id: i32) -> errors::Error {
+    errors::Error::NotFound(format!("inventory item {}", id))
+}
@@ -0,0 +1,120 @@
+use super::{Client, Condition};
+use crate::accounts::User;
+use crate::products::{Paint, PaintMatch};
+use crate::{api, db, products};
+
+/// how much of a list of paints (ie, the ones used in a video) someone already owns
+pub struct InventoryCoverage {
+    /// the paints in the list that are in the inventory, in the order of the list
+    pub owned: Vec<Paint>,
+    /// the paints in the list that aren't, in the order of the list
+    pub missing: Vec<MissingPaint>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl InventoryCoverage {
+    /// whether every paint in the list is already owned
+    fn complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// the paints in the list that are already owned
+    fn owned(&self) -> &[Paint] {
+        &self.owned
+    }
+
+    /// the paints in the list that would have to be bought (or stood in for)
+    fn missing(&self) -> &[MissingPaint] {
+        &self.missing
+    }
+}
+
+/// a paint that isn't in the inventory along with the closest one that is
+pub struct MissingPaint {
+    pub paint: Paint,
+    pub substitute: Option<PaintMatch>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl MissingPaint {
+    fn paint(&self) -> &Paint {
+        &self.paint
+    }
+
+    /// the owned paint that looks the most like the missing one, going by delta-E. it's
+    /// up to the painter to decide whether it's close enough
+    fn substitute(&self) -> Option<&PaintMatch> {
+        self.substitute.as_ref()
+    }
+}
+
+impl Client {
+    /// how much of the list of paints the user owns. paints that show up more than once
+    /// are only counted once, and dried out pots don't count as owned
+    pub fn coverage<I>(
+        &self,
+        owner: &User,
+        paint_ids: I,
+        products: &products::Client,
+    ) -> Result<InventoryCoverage, db::Error>
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        let mut wanted: Vec<i32> = Vec::new();
+        for id in paint_ids {
+            if !wanted.contains(&id) {
+                wanted.push(id);
+            }
+        }
+
+        let mut owned_ids: Vec<i32> = self
+            .items_for_user(owner.id, products)?
+            .into_iter()
+            .filter(|item| item.condition != Condition::DriedOut)
+            .map(|item| item.paint_id)
+            .collect();
+        owned_ids.sort();
+        owned_ids.dedup();
+
+        // load every paint involved in a single batch
+        products.expect_paints(wanted.iter().chain(owned_ids.iter()).cloned());
+        let mut inventory = Vec::new();
+        for id in &owned_ids {
+            inventory.extend(products.paint(*id)?);
+        }
+
+        let mut coverage = InventoryCoverage {
+            owned: Vec::new(),
+            missing: Vec::new(),
+        };
+        for id in wanted {
+            // the paint might have been removed from the catalog since it was listed
+            let paint = match products.paint(id)? {
+                Some(paint) => paint,
+                None => continue,
+            };
+            if owned_ids.binary_search(&id).is_ok() {
+                coverage.owned.push(paint);
+                continue;
+            }
+
+            // an inventory is small enough that comparing against every paint in it is
+            // quicker than building an index for it
+            let substitute = inventory
+                .iter()
+                .map(|owned| (owned, paint.color.distance(owned.color)))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(owned, delta_e)| PaintMatch {
+                    paint: owned.clone(),
+                    delta_e,
+                });
+            coverage.missing.push(MissingPaint { paint, substitute });
+        }
+
+        Ok(coverage)
+    }
+}
@@ -0,0 +1,244 @@
+// external crates
+use chrono::{DateTime, NaiveDate, Utc};
+use juniper::{FieldResult, ID};
+
+use crate::accounts::User;
+use crate::node::{self, Kind, Node};
+use crate::{api, errors, products};
+
+/// how much use is left in a pot
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    New,
+    Half,
+    DriedOut,
+}
+
+impl Condition {
+    /// the name we use for the condition when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Condition::New => "new",
+            Condition::Half => "half",
+            Condition::DriedOut => "dried_out",
+        }
+    }
+}
+
+/// a paint someone owns
+#[derive(Clone, Debug)]
+pub struct InventoryItem {
+    pub id: i32,
+    pub user_id: i32,
+    pub paint_id: i32,
+    /// how many pots of the paint there are in this condition
+    pub quantity: i32,
+    pub condition: Condition,
+    pub purchased_on: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl InventoryItem {
+    /// the globally unique identifier of the item
+    fn id(&self) -> ID {
+        node::global_id(Kind::InventoryItem, self.id)
+    }
+
+    /// the paint that's owned
+    fn paint(&self, context: &api::Context) -> FieldResult<products::Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+
+    /// how many pots of the paint there are in this condition
+    fn quantity(&self) -> i32 {
+        self.quantity
+    }
+
+    /// how much use is left in the pots
+    fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    /// the day the paint was bought, if the owner remembers
+    fn purchasedOn(&self) -> Option<NaiveDate> {
+        self.purchased_on
+    }
+
+    fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// when the paint was added to the inventory
+    fn addedAt(&self) -> DateTime<Utc> {
+        self.added_at
+    }
+}
+
+connection!(InventoryItemConnection, InventoryItemEdge, InventoryItem);
+
+/// everything needed to record a paint someone owns
+#[derive(juniper::GraphQLInputObject)]
+pub struct InventoryItemInput {
+    /// the id of the paint
+    pub paint: ID,
+    /// defaults to a single pot
+    pub quantity: Option<i32>,
+    /// defaults to new
+    pub condition: Option<Condition>,
+    pub purchased_on: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+impl InventoryItemInput {
+    /// check the input over and turn it into an item for the owner that hasn't been stored yet
+    pub(super) fn into_item(
+        self,
+        id: i32,
+        owner: &User,
+        products: &products::Client,
+    ) -> Result<InventoryItem, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let paint = products.find_paint(&self.paint)?;
+        if paint.is_none() {
+            validation.add("paint", "does not exist");
+        }
+        let quantity = self.quantity.unwrap_or(1);
+        if quantity < 1 {
+            validation.add("quantity", "must be at least 1");
+        }
+        validation.finish()?;
+
+        Ok(InventoryItem {
+            id,
+            user_id: owner.id,
+            // the validation would have failed if the paint were missing
+            paint_id: paint.unwrap().id,
+            quantity,
+            condition: self.condition.unwrap_or(Condition::New),
+            purchased_on: self.purchased_on,
+            // blank notes aren't worth keeping
+            notes: self
+                .notes
+                .map(|notes| notes.trim().to_string())
+                .filter(|notes| !notes.is_empty()),
+            added_at: Utc::now(),
+        })
+    }
+}
+
+/// the changes to make to an item. fields that are left out are unchanged
+#[derive(juniper::GraphQLInputObject)]
+pub struct InventoryItemPatch {
+    pub quantity: Option<i32>,
+    pub condition: Option<Condition>,
+    pub purchased_on: Option<NaiveDate>,
+    /// remove the purchase date the item has, for when it turns out to be wrong
+    pub clear_purchased_on: Option<bool>,
+    /// blank notes clear them
+    pub notes: Option<String>,
+}
+
+impl InventoryItemPatch {
+    /// apply the changes to the item, validating the result
+    pub(super) fn apply(self, item: InventoryItem) -> Result<InventoryItem, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let quantity = self.quantity.unwrap_or(item.quantity);
+        if quantity < 1 {
+            validation.add("quantity", "must be at least 1");
+        }
+        let clear_purchased_on = self.clear_purchased_on.unwrap_or(false);
+        if clear_purchased_on && self.purchased_on.is_some() {
+            validation.add("clearPurchasedOn", "cannot be combined with purchasedOn");
+        }
+        validation.finish()?;
+
+        Ok(InventoryItem {
+            quantity,
+            condition: self.condition.unwrap_or(item.condition),
+            purchased_on: if clear_purchased_on {
+                None
+            } else {
+                self.purchased_on.or(item.purchased_on)
+            },
+            notes: match self.notes {
+                Some(notes) => Some(notes.trim().to_string()).filter(|notes| !notes.is_empty()),
+                None => item.notes,
+            },
+            ..item
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a patch that leaves everything as it is
+    fn unchanged() -> InventoryItemPatch {
+        InventoryItemPatch {
+            quantity: None,
+            condition: None,
+            purchased_on: None,
+            clear_purchased_on: None,
+            notes: None,
+        }
+    }
+
+    fn item(purchased_on: Option<NaiveDate>) -> InventoryItem {
+        InventoryItem {
+            id: 1,
+            user_id: 1,
+            paint_id: 1,
+            quantity: 2,
+            condition: Condition::Half,
+            purchased_on,
+            notes: Some("the good pot".to_string()),
+            added_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn patches_set_keep_and_clear_the_purchase_date() {
+        let purchased_on = NaiveDate::from_ymd_opt(2019, 6, 8).unwrap();
+
+        let kept = unchanged().apply(item(Some(purchased_on))).unwrap();
+        assert_eq!(kept.purchased_on, Some(purchased_on));
+        assert_eq!(kept.quantity, 2);
+
+        let set = InventoryItemPatch {
+            purchased_on: Some(purchased_on),
+            ..unchanged()
+        };
+        let updated = set.apply(item(None)).unwrap();
+        assert_eq!(updated.purchased_on, Some(purchased_on));
+
+        let clear = InventoryItemPatch {
+            clear_purchased_on: Some(true),
+            ..unchanged()
+        };
+        let cleared = clear.apply(item(Some(purchased_on))).unwrap();
+        assert_eq!(cleared.purchased_on, None);
+        assert_eq!(cleared.notes.as_deref(), Some("the good pot"));
+    }
+
+    #[test]
+    fn patches_cannot_set_and_clear_the_purchase_date() {
+        let both = InventoryItemPatch {
+            purchased_on: NaiveDate::from_ymd_opt(2019, 6, 8),
+            clear_purchased_on: Some(true),
+            ..unchanged()
+        };
+
+        match both.apply(item(None)) {
+            Err(errors::Error::Validation(problems)) => {
+                assert_eq!(problems[0].field, "clearPurchasedOn");
+            }
+            _ => panic!("the patch should have been turned down"),
+        }
+    }
+}
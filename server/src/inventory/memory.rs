@@ -0,0 +1,95 @@
+use super::{InventoryItem, Repository, WishlistItem};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<InventoryItem>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.inventory_items.iter().find(|item| item.id == *id).cloned())
+            .collect())
+    }
+
+    fn items_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<InventoryItem>>, db::Error> {
+        let tables = self.read();
+
+        // items are kept in the order they were added
+        Ok(user_ids
+            .iter()
+            .map(|user_id| {
+                tables
+                    .inventory_items
+                    .iter()
+                    .filter(|item| item.user_id == *user_id)
+                    .cloned()
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn insert_item(&self, item: &InventoryItem) -> Result<InventoryItem, db::Error> {
+        let mut tables = self.write();
+
+        let item = InventoryItem {
+            id: memory::next_id(tables.inventory_items.iter().map(|item| item.id)),
+            ..item.clone()
+        };
+        tables.inventory_items.push(item.clone());
+
+        Ok(item)
+    }
+
+    fn update_item(&self, item: &InventoryItem) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().inventory_items, item, |item| item.id))
+    }
+
+    fn delete_item(&self, id: i32) -> Result<bool, db::Error> {
+        Ok(memory::remove(&mut self.write().inventory_items, id, |item| item.id))
+    }
+
+    fn wishlist_items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<WishlistItem>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.wishlist_items.iter().find(|item| item.id == *id).cloned())
+            .collect())
+    }
+
+    fn wishlist_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<WishlistItem>>, db::Error> {
+        let tables = self.read();
+
+        Ok(user_ids
+            .iter()
+            .map(|user_id| {
+                tables
+                    .wishlist_items
+                    .iter()
+                    .filter(|item| item.user_id == *user_id)
+                    .cloned()
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn insert_wishlist_item(&self, item: &WishlistItem) -> Result<WishlistItem, db::Error> {
+        let mut tables = self.write();
+
+        let item = WishlistItem {
+            id: memory::next_id(tables.wishlist_items.iter().map(|item| item.id)),
+            ..item.clone()
+        };
+        tables.wishlist_items.push(item.clone());
+
+        Ok(item)
+    }
+
+    fn update_wishlist_item(&self, item: &WishlistItem) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().wishlist_items, item, |item| item.id))
+    }
+
+    fn delete_wishlist_item(&self, id: i32) -> Result<bool, db::Error> {
+        Ok(memory::remove(&mut self.write().wishlist_items, id, |item| item.id))
+    }
+}
@@ -0,0 +1,326 @@
+// external crates
+use juniper::FieldResult;
+
+use super::Client;
+use crate::accounts::User;
+use crate::currency::{self, Money};
+use crate::pricing::{PriceObservation, Retailer};
+use crate::products::Paint;
+use crate::{api, db, errors, pricing, products};
+
+/// everything someone wants to buy, worked out from their wishlist
+pub struct ShoppingList {
+    /// in the order the paints were added to the wishlist
+    pub items: Vec<ShoppingListItem>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ShoppingList {
+    /// the paints to buy
+    fn items(&self) -> &[ShoppingListItem] {
+        &self.items
+    }
+
+    /// how many pots there are to buy all together
+    fn totalQuantity(&self) -> i32 {
+        self.items.iter().map(|item| item.quantity).sum()
+    }
+
+    /// the paints to buy split up by the retailer that has them in stock the cheapest
+    fn retailers(&self) -> Vec<ShoppingListRetailer> {
+        self.by_retailer()
+    }
+
+    /// the paints no retailer has in stock right now
+    fn unavailable(&self) -> Vec<ShoppingListItem> {
+        self.unavailable()
+    }
+}
+
+/// a paint to buy
+#[derive(Clone)]
+pub struct ShoppingListItem {
+    pub paint: Paint,
+    /// the name of the brand that makes the paint, to look for on the shelf
+    pub brand: String,
+    pub quantity: i32,
+    /// where to buy the paint and what it costs there, if anyone has it in stock
+    pub source: Option<(Retailer, PriceObservation)>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ShoppingListItem {
+    fn paint(&self) -> &Paint {
+        &self.paint
+    }
+
+    /// how many pots to buy
+    fn quantity(&self) -> i32 {
+        self.quantity
+    }
+
+    /// the retailer with the cheapest price for the paint that has it in stock
+    fn retailer(&self) -> Option<&Retailer> {
+        self.source.as_ref().map(|(retailer, _)| retailer)
+    }
+
+    /// the price of a single pot at the retailer, converted into another currency if one
+    /// is given
+    fn unitPrice(&self, context: &api::Context, currency: Option<String>) -> FieldResult<Option<Money>> {
+        let price = self.source.as_ref().map(|(_, observation)| {
+            context.currency.convert(&observation.price, currency.as_deref())
+        });
+        Ok(price.transpose()?)
+    }
+
+    /// the price of every pot to buy at the retailer, converted into another currency if
+    /// one is given
+    fn total(&self, context: &api::Context, currency: Option<String>) -> FieldResult<Option<Money>> {
+        let total = self
+            .total()
+            .map(|total| context.currency.convert(&total, currency.as_deref()));
+        Ok(total.transpose()?)
+    }
+}
+
+/// the paints to buy from a single retailer
+pub struct ShoppingListRetailer {
+    pub retailer: Retailer,
+    pub items: Vec<ShoppingListItem>,
+    pub total: Money,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl ShoppingListRetailer {
+    fn retailer(&self) -> &Retailer {
+        &self.retailer
+    }
+
+    /// the paints to buy there
+    fn items(&self) -> &[ShoppingListItem] {
+        &self.items
+    }
+
+    /// what the paints come to all together, converted into another currency if one is
+    /// given
+    fn total(&self, context: &api::Context, currency: Option<String>) -> FieldResult<Money> {
+        Ok(context.currency.convert(&self.total, currency.as_deref())?)
+    }
+}
+
+impl ShoppingListItem {
+    /// the price of every pot to buy, if anyone has the paint in stock
+    pub fn total(&self) -> Option<Money> {
+        self.source
+            .as_ref()
+            .map(|(_, observation)| observation.price.times(self.quantity))
+    }
+}
+
+impl ShoppingList {
+    /// the items that can be bought, grouped by retailer and by currency, so every group
+    /// has a total that can actually be paid. groups come in the order their first paint
+    /// was added to the wishlist
+    pub fn by_retailer(&self) -> Vec<ShoppingListRetailer> {
+        let mut groups: Vec<ShoppingListRetailer> = Vec::new();
+        for item in &self.items {
+            let (retailer, total) = match (&item.source, item.total()) {
+                (Some((retailer, _)), Some(total)) => (retailer, total),
+                _ => continue,
+            };
+            let group = groups.iter_mut().find(|group| {
+                group.retailer.id == retailer.id && group.total.currency == total.currency
+            });
+            match group {
+                Some(group) => {
+                    group.items.push(item.clone());
+                    group.total.cents += total.cents;
+                }
+                None => groups.push(ShoppingListRetailer {
+                    retailer: retailer.clone(),
+                    items: vec![item.clone()],
+                    total,
+                }),
+            }
+        }
+
+        groups
+    }
+
+    /// the list with every price converted into a single currency, so each retailer's
+    /// paints add up to one total
+    pub fn in_currency(self, rates: &currency::Client, currency: &str) -> Result<ShoppingList, errors::Error> {
+        let mut items = Vec::new();
+        for item in self.items {
+            let source = match item.source {
+                Some((retailer, observation)) => {
+                    let price = rates.convert(&observation.price, Some(currency))?;
+                    Some((retailer, PriceObservation { price, ..observation }))
+                }
+                None => None,
+            };
+            items.push(ShoppingListItem { source, ..item });
+        }
+
+        Ok(ShoppingList { items })
+    }
+
+    /// the items no retailer has in stock right now
+    pub fn unavailable(&self) -> Vec<ShoppingListItem> {
+        self.items.iter().filter(|item| item.source.is_none()).cloned().collect()
+    }
+
+    /// the list as plain text, a section per retailer and a line per paint
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for group in self.by_retailer() {
+            text.push_str(&format!("{}\n", group.retailer.name));
+            for item in &group.items {
+                text.push_str(&text_line(item));
+            }
+            text.push_str(&format!("total: {}\n\n", group.total));
+        }
+
+        let unavailable = self.unavailable();
+        if !unavailable.is_empty() {
+            text.push_str("not in stock anywhere\n");
+            for item in &unavailable {
+                text.push_str(&text_line(item));
+            }
+        }
+
+        text
+    }
+
+    /// the list as CSV with a header row, for pulling into a spreadsheet. the price columns
+    /// are left blank for paints no retailer has in stock
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("brand,paint,sku,quantity,retailer,unit_price,total,currency\n");
+        for item in &self.items {
+            let (retailer, unit_price, total, currency) = match (&item.source, item.total()) {
+                (Some((retailer, observation)), Some(total)) => (
+                    retailer.name.clone(),
+                    observation.price.to_decimal(),
+                    total.to_decimal(),
+                    total.currency,
+                ),
+                _ => Default::default(),
+            };
+            let row = [
+                item.brand.clone(),
+                item.paint.name.clone(),
+                item.paint.sku.clone().unwrap_or_default(),
+                item.quantity.to_string(),
+                retailer,
+                unit_price,
+                total,
+                currency,
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+impl Client {
+    /// what the user has to buy to get everything on their wishlist, along with the cheapest
+    /// place to buy each paint
+    pub fn shopping_list(
+        &self,
+        owner: &User,
+        products: &products::Client,
+        pricing: &pricing::Client,
+        rates: &currency::Client,
+    ) -> Result<ShoppingList, db::Error> {
+        let wishlist = self.wishlist_for_user(owner.id, products)?;
+        pricing.expect_paints(wishlist.iter().map(|item| item.paint_id));
+
+        let mut items = Vec::new();
+        for wanted in wishlist {
+            // the paint might have been removed from the catalog since it was wanted
+            let paint = match products.paint(wanted.paint_id)? {
+                Some(paint) => paint,
+                None => continue,
+            };
+            let brand = match products.line(paint.line_id)? {
+                Some(line) => products.brand(line.brand_id)?.map(|brand| brand.name),
+                None => None,
+            };
+            let source = match pricing.cheapest_in_stock(paint.id, rates)? {
+                Some(observation) => pricing
+                    .retailer(observation.retailer_id)?
+                    .map(|retailer| (retailer, observation)),
+                None => None,
+            };
+
+            items.push(ShoppingListItem {
+                paint,
+                brand: brand.unwrap_or_default(),
+                quantity: wanted.quantity,
+                source,
+            });
+        }
+
+        Ok(ShoppingList { items })
+    }
+}
+
+/// a line of the plain text list for a single paint
+fn text_line(item: &ShoppingListItem) -> String {
+    let mut line = format!("{} x {} {}", item.quantity, item.brand, item.paint.name);
+    if let Some(sku) = &item.paint.sku {
+        line.push_str(&format!(" ({})", sku));
+    }
+    if let (Some((_, observation)), Some(total)) = (&item.source, item.total()) {
+        line.push_str(&format!(" at {} = {}", observation.price, total));
+    }
+    line.push('\n');
+
+    line
+}
+
+/// a field quoted for CSV if it has to be. spreadsheets run anything that looks like a
+/// formula so those fields are prefixed with a quote to keep them as plain text
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(&['=', '+', '-', '@'][..]) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("Abaddon Black"), "Abaddon Black");
+        assert_eq!(csv_field("Black, Matte"), "\"Black, Matte\"");
+        assert_eq!(csv_field("6\" Brush"), "\"6\"\" Brush\"");
+    }
+
+    #[test]
+    fn csv_fields_are_not_formulas() {
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+        assert_eq!(csv_field("+44 20"), "'+44 20");
+        assert_eq!(csv_field("-2+3"), "'-2+3");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("=HYPERLINK(\"x\",\"y\")"), "\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\"");
+    }
+}
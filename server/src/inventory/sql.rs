@@ -0,0 +1,169 @@
+// external crates
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Row};
+
+use super::{Condition, InventoryItem, Repository, WishlistItem};
+use crate::db;
+
+/// the columns we need to select in order to build an InventoryItem
+const ITEM_COLUMNS: &str =
+    "id, user_id, paint_id, quantity, condition, purchased_on, notes, added_at";
+
+impl Repository for db::Pool {
+    fn items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<InventoryItem>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM inventory_items WHERE id IN ({{keys}})", ITEM_COLUMNS),
+            ids,
+            item_from_row,
+            |item| item.id,
+        )
+    }
+
+    fn items_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<InventoryItem>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM inventory_items WHERE user_id IN ({{keys}}) ORDER BY id",
+                ITEM_COLUMNS
+            ),
+            user_ids,
+            item_from_row,
+            |item| item.user_id,
+        )
+    }
+
+    fn insert_item(&self, item: &InventoryItem) -> Result<InventoryItem, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO inventory_items
+                 (user_id, paint_id, quantity, condition, purchased_on, notes, added_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                item.user_id,
+                item.paint_id,
+                item.quantity,
+                item.condition,
+                item.purchased_on,
+                item.notes,
+                item.added_at,
+            ],
+        )?;
+
+        Ok(InventoryItem {
+            id: conn.last_insert_rowid() as i32,
+            ..item.clone()
+        })
+    }
+
+    fn update_item(&self, item: &InventoryItem) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE inventory_items
+             SET quantity = ?, condition = ?, purchased_on = ?, notes = ?
+             WHERE id = ?",
+            params![item.quantity, item.condition, item.purchased_on, item.notes, item.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_item(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM inventory_items WHERE id = ?", [id])? > 0)
+    }
+
+    fn wishlist_items_by_id(&self, ids: &[i32]) -> Result<Vec<Option<WishlistItem>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM wishlist_items WHERE id IN ({{keys}})", WISHLIST_COLUMNS),
+            ids,
+            wishlist_item_from_row,
+            |item| item.id,
+        )
+    }
+
+    fn wishlist_by_user(&self, user_ids: &[i32]) -> Result<Vec<Vec<WishlistItem>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM wishlist_items WHERE user_id IN ({{keys}}) ORDER BY id",
+                WISHLIST_COLUMNS
+            ),
+            user_ids,
+            wishlist_item_from_row,
+            |item| item.user_id,
+        )
+    }
+
+    fn insert_wishlist_item(&self, item: &WishlistItem) -> Result<WishlistItem, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO wishlist_items (user_id, paint_id, quantity, added_at) VALUES (?, ?, ?, ?)",
+            params![item.user_id, item.paint_id, item.quantity, item.added_at],
+        )?;
+
+        Ok(WishlistItem {
+            id: conn.last_insert_rowid() as i32,
+            ..item.clone()
+        })
+    }
+
+    fn update_wishlist_item(&self, item: &WishlistItem) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE wishlist_items SET quantity = ? WHERE id = ?",
+            params![item.quantity, item.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_wishlist_item(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM wishlist_items WHERE id = ?", [id])? > 0)
+    }
+}
+
+/// the columns we need to select in order to build a WishlistItem
+const WISHLIST_COLUMNS: &str = "id, user_id, paint_id, quantity, added_at";
+
+/// build an item out of a row containing ITEM_COLUMNS
+fn item_from_row(row: &Row) -> rusqlite::Result<InventoryItem> {
+    Ok(InventoryItem {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        paint_id: row.get(2)?,
+        quantity: row.get(3)?,
+        condition: row.get(4)?,
+        purchased_on: row.get(5)?,
+        notes: row.get(6)?,
+        added_at: row.get(7)?,
+    })
+}
+
+/// build a wishlist item out of a row containing WISHLIST_COLUMNS
+fn wishlist_item_from_row(row: &Row) -> rusqlite::Result<WishlistItem> {
+    Ok(WishlistItem {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        paint_id: row.get(2)?,
+        quantity: row.get(3)?,
+        added_at: row.get(4)?,
+    })
+}
+
+impl ToSql for Condition {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Condition {
+    fn column_result(value: ValueRef) -> FromSqlResult<Condition> {
+        match value.as_str()? {
+            "new" => Ok(Condition::New),
+            "half" => Ok(Condition::Half),
+            "dried_out" => Ok(Condition::DriedOut),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
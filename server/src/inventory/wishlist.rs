@@ -0,0 +1,136 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use super::Client;
+use crate::accounts::User;
+use crate::node::{self, Kind, Node};
+use crate::{api, db, errors, products};
+
+/// a paint someone wants to buy
+#[derive(Clone, Debug)]
+pub struct WishlistItem {
+    pub id: i32,
+    pub user_id: i32,
+    pub paint_id: i32,
+    /// how many pots they want
+    pub quantity: i32,
+    pub added_at: DateTime<Utc>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl WishlistItem {
+    /// the globally unique identifier of the item
+    fn id(&self) -> ID {
+        node::global_id(Kind::WishlistItem, self.id)
+    }
+
+    /// the paint that's wanted
+    fn paint(&self, context: &api::Context) -> FieldResult<products::Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+
+    /// how many pots are wanted
+    fn quantity(&self) -> i32 {
+        self.quantity
+    }
+
+    /// when the paint was first added to the wishlist
+    fn addedAt(&self) -> DateTime<Utc> {
+        self.added_at
+    }
+}
+
+connection!(WishlistItemConnection, WishlistItemEdge, WishlistItem);
+
+/// a paint to add to the wishlist
+#[derive(juniper::GraphQLInputObject)]
+pub struct WishlistItemInput {
+    /// the id of the paint
+    pub paint: ID,
+    /// how many more pots are wanted. defaults to one
+    pub quantity: Option<i32>,
+}
+
+impl Client {
+    /// a wishlist item by its id, as long as it belongs to the user
+    pub fn wishlist_item(&self, owner: &User, id: i32) -> Result<Option<WishlistItem>, db::Error> {
+        Ok(self.wishlist_items.load(id)?.filter(|item| item.user_id == owner.id))
+    }
+
+    /// everything the user wants to buy, in the order they first added it
+    pub fn wishlist_for_user(
+        &self,
+        user_id: i32,
+        products: &products::Client,
+    ) -> Result<Vec<WishlistItem>, db::Error> {
+        let items = self.wishlist_by_user.load(user_id)?;
+
+        // the paints are usually the next thing asked for so we want them in a single batch
+        products.expect_paints(items.iter().map(|item| item.paint_id));
+
+        Ok(items)
+    }
+
+    /// add a paint to the user's wishlist. a paint that's already on it is wanted that many
+    /// more times
+    pub fn add_to_wishlist(
+        &self,
+        owner: &User,
+        input: WishlistItemInput,
+        products: &products::Client,
+    ) -> Result<WishlistItem, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let paint = products.find_paint(&input.paint)?;
+        if paint.is_none() {
+            validation.add("paint", "does not exist");
+        }
+        let quantity = input.quantity.unwrap_or(1);
+        if quantity < 1 {
+            validation.add("quantity", "must be at least 1");
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the paint were missing
+        let paint_id = paint.unwrap().id;
+        let existing = self
+            .wishlist_for_user(owner.id, products)?
+            .into_iter()
+            .find(|item| item.paint_id == paint_id);
+        let item = match existing {
+            Some(item) => {
+                let item = WishlistItem {
+                    quantity: item.quantity + quantity,
+                    ..item
+                };
+                self.repo.update_wishlist_item(&item)?;
+                item
+            }
+            None => self.repo.insert_wishlist_item(&WishlistItem {
+                id: 0,
+                user_id: owner.id,
+                paint_id,
+                quantity,
+                added_at: Utc::now(),
+            })?,
+        };
+
+        self.wishlist_items.replace(item.id, Some(item.clone()));
+        self.wishlist_by_user.clear();
+        Ok(item)
+    }
+
+    /// take a paint off the user's wishlist
+    pub fn remove_from_wishlist(&self, owner: &User, id: i32) -> Result<(), errors::Error> {
+        if self.wishlist_item(owner, id)?.is_none() || !self.repo.delete_wishlist_item(id)? {
+            return Err(errors::Error::NotFound(format!("wishlist item {}", id)));
+        }
+
+        self.wishlist_items.replace(id, None);
+        self.wishlist_by_user.clear();
+        Ok(())
+    }
+}
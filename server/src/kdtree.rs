@@ -0,0 +1,191 @@
+// external crates
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// a point in three dimensions (ie, a color in CIELAB)
+pub type Point = [f64; 3];
+
+/// a k-d tree over points in three dimensions, each carrying a value. the tree is laid out
+/// in a single vector: every subtree is a slice whose middle element splits the rest
+/// along one axis, cycling through the axes as we go down.
+pub struct KdTree<T> {
+    entries: Vec<(Point, T)>,
+}
+
+impl<T> KdTree<T> {
+    /// build a tree out of the given entries
+    pub fn build(mut entries: Vec<(Point, T)>) -> KdTree<T> {
+        arrange(&mut entries, 0);
+        KdTree { entries }
+    }
+
+    /// the up to `limit` entries closest to the target (by straight line distance) whose
+    /// values pass the filter, closest first, along with their distance
+    pub fn nearest<F>(&self, target: Point, limit: usize, accept: F) -> Vec<(&T, f64)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        let mut best = BinaryHeap::with_capacity(limit + 1);
+        self.search(0, self.entries.len(), 0, &target, limit, &accept, &mut best);
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|candidate| (&self.entries[candidate.index].1, candidate.distance.sqrt()))
+            .collect()
+    }
+
+    /// every entry no further than `radius` from the target (by straight line distance)
+    /// whose value passes the filter, in no particular order, along with its distance
+    pub fn within<F>(&self, target: Point, radius: f64, accept: F) -> Vec<(&T, f64)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut found = Vec::new();
+        let end = self.entries.len();
+        self.gather(0, end, 0, &target, radius * radius, &accept, &mut found);
+
+        found
+            .into_iter()
+            .map(|(value, distance)| (value, distance.sqrt()))
+            .collect()
+    }
+
+    /// look for matches in the subtree held in entries[start..end]. best is a max-heap
+    /// so the worst of the matches we're holding onto is always on top
+    #[allow(clippy::too_many_arguments)]
+    fn search<F>(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &Point,
+        limit: usize,
+        accept: &F,
+        best: &mut BinaryHeap<Candidate>,
+    ) where
+        F: Fn(&T) -> bool,
+    {
+        if start >= end {
+            return;
+        }
+
+        let middle = start + (end - start) / 2;
+        let (point, value) = &self.entries[middle];
+        if accept(value) {
+            best.push(Candidate {
+                distance: squared_distance(point, target),
+                index: middle,
+            });
+            if best.len() > limit {
+                best.pop();
+            }
+        }
+
+        // look on the target's side of the split first since that's where the closest
+        // matches are most likely to be
+        let axis = depth % 3;
+        let offset = target[axis] - point[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, middle), (middle + 1, end))
+        } else {
+            ((middle + 1, end), (start, middle))
+        };
+        self.search(near.0, near.1, depth + 1, target, limit, accept, best);
+
+        // the other side can only help if it's closer than the worst match we have
+        let worst = best.peek().map_or(f64::INFINITY, |candidate| candidate.distance);
+        if best.len() < limit || offset * offset < worst {
+            self.search(far.0, far.1, depth + 1, target, limit, accept, best);
+        }
+    }
+
+    /// collect the entries in the subtree held in entries[start..end] that are within the
+    /// (squared) radius, along with their squared distance
+    #[allow(clippy::too_many_arguments)]
+    fn gather<'a, F>(
+        &'a self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &Point,
+        radius: f64,
+        accept: &F,
+        found: &mut Vec<(&'a T, f64)>,
+    ) where
+        F: Fn(&T) -> bool,
+    {
+        if start >= end {
+            return;
+        }
+
+        let middle = start + (end - start) / 2;
+        let (point, value) = &self.entries[middle];
+        let distance = squared_distance(point, target);
+        if distance <= radius && accept(value) {
+            found.push((value, distance));
+        }
+
+        // a side of the split can only hold matches if the radius reaches across to it
+        let axis = depth % 3;
+        let offset = target[axis] - point[axis];
+        if offset < 0.0 || offset * offset <= radius {
+            self.gather(start, middle, depth + 1, target, radius, accept, found);
+        }
+        if offset >= 0.0 || offset * offset <= radius {
+            self.gather(middle + 1, end, depth + 1, target, radius, accept, found);
+        }
+    }
+}
+
+/// put the entries in tree order by splitting around the median of each axis in turn
+fn arrange<T>(entries: &mut [(Point, T)], depth: usize) {
+    if entries.len() <= 1 {
+        return;
+    }
+
+    let axis = depth % 3;
+    entries.sort_unstable_by(|a, b| a.0[axis].partial_cmp(&b.0[axis]).unwrap_or(Ordering::Equal));
+
+    let middle = entries.len() / 2;
+    let (before, rest) = entries.split_at_mut(middle);
+    arrange(before, depth + 1);
+    arrange(&mut rest[1..], depth + 1);
+}
+
+fn squared_distance(a: &Point, b: &Point) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+/// an entry we've found along with its squared distance from the target, ordered by the
+/// distance so they can sit in a heap
+struct Candidate {
+    distance: f64,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+            .then(self.index.cmp(&other.index))
+    }
+}
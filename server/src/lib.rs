@@ -0,0 +1,24 @@
+// the domains and everything they're built on, shared by the server and the benchmarks
+pub mod playground;
+// the connection macro has to be declared before the modules that use it
+#[macro_use]
+pub mod pagination;
+pub mod accounts;
+pub mod alerts;
+pub mod api;
+pub mod colors;
+pub mod currency;
+pub mod db;
+pub mod errors;
+pub mod fixtures;
+pub mod fulltext;
+pub mod inventory;
+pub mod kdtree;
+pub mod loaders;
+pub mod memory;
+pub mod node;
+pub mod pricing;
+pub mod products;
+pub mod recipes;
+pub mod storage;
+pub mod trie;
@@ -0,0 +1,226 @@
+// external crates
+use dataloader::{cached, BatchFn, BatchFuture};
+use futures::{future, Future};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::db;
+
+/// a per-request cache that batches up lookups of values by their key. on top of what
+/// the dataloader crate gives us, a loader can be told which keys are likely to be asked
+/// for next (ie, the product lines of every paint in a list) so that the first lookup
+/// fetches all of them at once instead of one trip to storage per sibling.
+pub struct Loader<K, V>
+where
+    K: Clone + Ord,
+    V: Clone,
+{
+    // errors are shared between everyone waiting on a batch so they have to be cloneable
+    inner: cached::Loader<K, V, String, BTreeMap<K, cached::LoadFuture<V, String>>>,
+    ahead: Arc<Mutex<Ahead<K, V>>>,
+}
+
+/// the keys we expect to be asked for and the values fetched for them ahead of time. the
+/// dataloader batches whatever has been asked for by the time its thread gets around to
+/// it, so the keys we know about up front are fetched together here instead
+struct Ahead<K, V> {
+    expected: Vec<K>,
+    fetched: BTreeMap<K, V>,
+}
+
+impl<K, V> Loader<K, V>
+where
+    K: Clone + Ord + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    /// build a loader that hands each batch of keys to the given function, which must
+    /// return exactly one value per key in the same order as the keys
+    pub fn new<F>(load: F) -> Loader<K, V>
+    where
+        F: Fn(&[K]) -> Result<Vec<V>, db::Error> + Send + 'static,
+    {
+        let ahead = Arc::new(Mutex::new(Ahead {
+            expected: Vec::new(),
+            fetched: BTreeMap::new(),
+        }));
+
+        Loader {
+            inner: dataloader::Loader::new(Batch {
+                load,
+                ahead: ahead.clone(),
+            })
+            .cached(),
+            ahead,
+        }
+    }
+
+    /// the value for a single key
+    pub fn load(&self, key: K) -> Result<V, db::Error> {
+        Ok(self.inner.load(key).wait()?)
+    }
+
+    /// remember keys that we expect to be asked for so they can join the next batch
+    pub fn expect<I: IntoIterator<Item = K>>(&self, keys: I) {
+        lock(&self.ahead).expected.extend(keys);
+    }
+
+    /// fill the cache with a value we already have on hand
+    pub fn prime(&self, key: K, value: V) {
+        self.inner.prime(key, value);
+    }
+
+    /// replace whatever is in the cache for the key (ie, after a mutation)
+    pub fn replace(&self, key: K, value: V) {
+        lock(&self.ahead).fetched.remove(&key);
+        self.inner.remove(&key);
+        self.inner.prime(key, value);
+    }
+
+    /// drop every cached value so the next lookups go back to storage
+    pub fn clear(&self) {
+        lock(&self.ahead).fetched.clear();
+        self.inner.clear();
+    }
+}
+
+/// adapts a plain function over a batch of keys into something the dataloader can call
+struct Batch<K, V, F> {
+    load: F,
+    ahead: Arc<Mutex<Ahead<K, V>>>,
+}
+
+impl<K, V, F> Batch<K, V, F>
+where
+    K: Clone + Ord,
+    V: Clone,
+    F: Fn(&[K]) -> Result<Vec<V>, db::Error>,
+{
+    /// the values for the keys, fetching any we don't have yet along with every key we
+    /// were expecting to be asked for
+    fn values(&self, keys: &[K]) -> Result<Vec<V>, String> {
+        let mut ahead = lock(&self.ahead);
+        let Ahead { expected, fetched } = &mut *ahead;
+
+        let mut missing: Vec<K> = keys
+            .iter()
+            .filter(|&key| !fetched.contains_key(key))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            missing.append(expected);
+            missing.sort();
+            missing.dedup();
+            missing.retain(|key| !fetched.contains_key(key));
+
+            let values = (self.load)(&missing).map_err(|err| err.to_string())?;
+            if values.len() != missing.len() {
+                return Err(format!(
+                    "asked for {} values but got {}",
+                    missing.len(),
+                    values.len()
+                ));
+            }
+            fetched.extend(missing.into_iter().zip(values));
+        }
+
+        // the dataloader caches what we hand back so there's no need to hold onto it too
+        Ok(keys.iter().filter_map(|key| fetched.remove(key)).collect())
+    }
+}
+
+impl<K, V, F> BatchFn<K, V> for Batch<K, V, F>
+where
+    K: Clone + Ord,
+    V: Clone + 'static,
+    F: Fn(&[K]) -> Result<Vec<V>, db::Error>,
+{
+    type Error = String;
+
+    fn load(&self, keys: &[K]) -> BatchFuture<V, String> {
+        Box::new(future::result(self.values(keys)))
+    }
+}
+
+fn lock<K, V>(ahead: &Mutex<Ahead<K, V>>) -> MutexGuard<'_, Ahead<K, V>> {
+    // a panic while holding the lock can't leave anything half-written so it's safe to carry on
+    ahead.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every batch of keys a loader was handed, in order
+    type Batches = Arc<Mutex<Vec<Vec<i32>>>>;
+
+    /// a loader that doubles its keys and records every batch it's handed
+    fn doubling() -> (Loader<i32, i32>, Batches) {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let loader = Loader::new({
+            let batches = batches.clone();
+            move |keys: &[i32]| {
+                batches.lock().unwrap().push(keys.to_vec());
+                Ok(keys.iter().map(|key| key * 2).collect())
+            }
+        });
+
+        (loader, batches)
+    }
+
+    #[test]
+    fn caches_values() {
+        let (loader, batches) = doubling();
+
+        assert_eq!(loader.load(1).unwrap(), 2);
+        assert_eq!(loader.load(1).unwrap(), 2);
+        assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+    }
+
+    #[test]
+    fn expected_keys_join_the_next_batch() {
+        let (loader, batches) = doubling();
+
+        loader.expect(vec![3, 1, 2, 3]);
+        assert_eq!(loader.load(2).unwrap(), 4);
+        assert_eq!(loader.load(1).unwrap(), 2);
+        assert_eq!(loader.load(3).unwrap(), 6);
+        assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn primed_keys_are_not_fetched() {
+        let (loader, batches) = doubling();
+
+        loader.prime(1, 10);
+        assert_eq!(loader.load(1).unwrap(), 10);
+        assert_eq!(loader.load(2).unwrap(), 4);
+        assert_eq!(*batches.lock().unwrap(), vec![vec![2]]);
+    }
+
+    #[test]
+    fn replace_and_clear() {
+        let (loader, batches) = doubling();
+
+        loader.prime(1, 10);
+        loader.prime(1, 20);
+        assert_eq!(loader.load(1).unwrap(), 10);
+        loader.replace(1, 30);
+        assert_eq!(loader.load(1).unwrap(), 30);
+
+        // values fetched ahead of time are forgotten along with the rest
+        loader.expect(vec![2]);
+        loader.clear();
+        assert_eq!(loader.load(1).unwrap(), 2);
+        assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+        loader.clear();
+        assert_eq!(loader.load(2).unwrap(), 4);
+        assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![2]]);
+    }
+
+    #[test]
+    fn short_batches_fail() {
+        let loader: Loader<i32, i32> = Loader::new(|_: &[i32]| Ok(Vec::new()));
+
+        assert!(loader.load(1).is_err());
+    }
+}
@@ -1,13 +1,16 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 
 // externals
+use rocket::http::{ContentType, Status};
 use rocket::response::content;
 use rocket::State;
+use std::convert::TryFrom;
+
+// the domains live in the library so the benchmarks can get at them too
+use paint_server::{accounts, alerts, api, currency, db, errors, playground, products, storage};
 
 // local module declarations
-mod playground;
-mod api;
-mod products;
+mod guards;
 
 #[rocket::get("/")]
 fn playground() -> content::Html<&'static str> {
@@ -18,17 +21,231 @@ fn playground() -> content::Html<&'static str> {
 fn api(
     request: juniper_rocket::GraphQLRequest,
     schema: State<api::Schema>,
+    backend: State<storage::Backend>,
+    colors: State<products::ColorIndex>,
+    search: State<products::SearchIndex>,
+    sessions: State<accounts::Sessions>,
+    credentials: guards::Credentials,
 ) -> juniper_rocket::GraphQLResponse {
     // we want to create a new collection of dataloaders on every request
-    let context = api::Context::new();
+    let context = api::Context::new(
+        &backend,
+        &colors,
+        &search,
+        &sessions,
+        credentials.session,
+        credentials.api_key,
+    );
 
     // resolve the request given the schema and current context
     request.execute(&schema, &context)
 }
 
+/// the viewer's shopping list as a download, either as plain text (the default) or as CSV.
+/// the prices can be converted into a single currency too
+#[rocket::get("/shopping-list?<format>&<currency>")]
+fn shopping_list(
+    format: Option<String>,
+    currency: Option<String>,
+    backend: State<storage::Backend>,
+    colors: State<products::ColorIndex>,
+    search: State<products::SearchIndex>,
+    sessions: State<accounts::Sessions>,
+    credentials: guards::Credentials,
+) -> Result<content::Content<String>, Status> {
+    let context = api::Context::new(
+        &backend,
+        &colors,
+        &search,
+        &sessions,
+        credentials.session,
+        credentials.api_key,
+    );
+    let viewer = match context.accounts.viewer() {
+        Ok(Some(viewer)) => viewer,
+        Ok(None) => return Err(Status::Unauthorized),
+        Err(_) => return Err(Status::InternalServerError),
+    };
+    let list = context
+        .inventory
+        .shopping_list(&viewer, &context.products, &context.pricing, &context.currency)
+        .map_err(|_| Status::InternalServerError)?;
+    let list = match currency {
+        // the only way converting can fail is not having a rate for the currency
+        Some(currency) => list
+            .in_currency(&context.currency, &currency)
+            .map_err(|_| Status::BadRequest)?,
+        None => list,
+    };
+
+    match format.as_deref().unwrap_or("text") {
+        "text" => Ok(content::Content(ContentType::Plain, list.to_text())),
+        "csv" => Ok(content::Content(ContentType::CSV, list.to_csv())),
+        _ => Err(Status::BadRequest),
+    }
+}
+
+/// build the storage backend described by the config
+fn backend(config: &rocket::Config) -> storage::Backend {
+    match config.get_str("storage").unwrap_or("sqlite") {
+        "memory" => storage::Backend::memory(),
+        "sqlite" => {
+            // the database lives wherever the config points us (or next to the server by default)
+            let database = config.get_str("database").unwrap_or("paints.db");
+            storage::Backend::Sqlite(db::connect(database).expect("could not open the database"))
+        }
+        other => panic!("unknown storage backend: {}", other),
+    }
+}
+
+/// the fewest bytes a configured session secret can have. anything shorter is too easy to
+/// guess, and an empty one would let anyone sign their own tokens
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// the keys session tokens are signed with, going by the config
+fn sessions(config: &rocket::Config) -> Result<accounts::Sessions, String> {
+    match config.get_str("session_secret") {
+        Ok(secret) if secret.len() < MIN_SECRET_LENGTH => Err(format!(
+            "session_secret must be at least {} bytes long",
+            MIN_SECRET_LENGTH
+        )),
+        Ok(secret) => Ok(accounts::Sessions::new(secret.as_bytes())),
+        Err(_) => {
+            eprintln!("no session_secret is configured so everyone is signed out on restart");
+            Ok(accounts::Sessions::ephemeral())
+        }
+    }
+}
+
+/// how long the outbox waits between rounds of sending notifications
+const OUTBOX_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// the way notifications are sent on to users, going by the config
+fn delivery(config: &rocket::Config) -> Result<Option<alerts::Smtp>, String> {
+    let host = match config.get_str("smtp_host") {
+        Ok(host) => host,
+        Err(_) => {
+            eprintln!("no smtp_host is configured so notifications are only shown in the app");
+            return Ok(None);
+        }
+    };
+    // a port out of range would otherwise wrap around to some other port entirely
+    let port = config.get_int("smtp_port").unwrap_or(25);
+    let port = u16::try_from(port)
+        .map_err(|_| format!("smtp_port must be between 0 and 65535, not {}", port))?;
+    let from = config.get_str("smtp_from").unwrap_or("paints@localhost");
+
+    let smtp = alerts::Smtp::new(host, port, from).tls(config.get_bool("smtp_tls").unwrap_or(false));
+    match (config.get_str("smtp_username"), config.get_str("smtp_password")) {
+        (Ok(username), Ok(password)) => Ok(Some(smtp.credentials(username, password))),
+        _ => Ok(Some(smtp)),
+    }
+}
+
+/// send the notifications in the outbox on every so often, for as long as the server runs
+fn spawn_outbox(backend: &storage::Backend, delivery: Box<dyn alerts::Delivery>) {
+    let repo = backend.alerts();
+    let accounts = backend.accounts();
+
+    std::thread::spawn(move || loop {
+        if let Err(err) = alerts::deliver_pending(&*repo, &*accounts, &*delivery) {
+            eprintln!("could not deliver notifications: {}", err);
+        }
+        std::thread::sleep(OUTBOX_INTERVAL);
+    });
+}
+
+/// load the exchange rates in a file into the database, returning how many there were
+fn import_rates(backend: &storage::Backend, path: &str) -> Result<usize, String> {
+    // the memory backend is thrown away as soon as we exit
+    if let storage::Backend::Memory(_) = backend {
+        return Err("exchange rates can only be imported into a sqlite database".to_string());
+    }
+
+    let text =
+        std::fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path, err))?;
+    let inputs = currency::parse_rates(&text)?;
+    match currency::Client::new(backend.currency()).import(inputs) {
+        Ok(rates) => Ok(rates.len()),
+        Err(errors::Error::Validation(problems)) => Err(problems
+            .iter()
+            .map(|problem| format!("{} {}", problem.field, problem.message))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Err(errors::Error::Storage(err)) => Err(err.to_string()),
+        // importing doesn't look anything up or check who's asking
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+/// make the user registered with the email address an admin, returning their address
+fn grant_admin(backend: &storage::Backend, email: &str) -> Result<String, String> {
+    // the memory backend is thrown away as soon as we exit
+    if let storage::Backend::Memory(_) = backend {
+        return Err("admins can only be granted in a sqlite database".to_string());
+    }
+
+    match accounts::grant_admin(&*backend.accounts(), email) {
+        Ok(user) => Ok(user.email),
+        Err(errors::Error::NotFound(_)) => Err(format!("nobody has registered as {}", email)),
+        Err(errors::Error::Storage(err)) => Err(err.to_string()),
+        // granting doesn't check who's asking or validate anything
+        Err(err) => Err(format!("{:?}", err)),
+    }
+}
+
+/// the value if there is one, otherwise explain what went wrong and stop
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    })
+}
+
 fn main() {
-    rocket::ignite()
+    let rocket = rocket::ignite();
+    let backend = backend(rocket.config());
+
+    // `paint-server import-rates rates.csv` loads exchange rates and
+    // `paint-server grant-admin someone@example.com` makes someone an admin instead of serving
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, command, argument] = args.as_slice() {
+        let outcome = match command.as_str() {
+            "import-rates" => Some(
+                import_rates(&backend, argument)
+                    .map(|count| format!("imported {} exchange rates", count)),
+            ),
+            "grant-admin" => Some(
+                grant_admin(&backend, argument).map(|email| format!("{} is now an admin", email)),
+            ),
+            _ => None,
+        };
+        match outcome {
+            Some(Ok(message)) => {
+                println!("{}", message);
+                return;
+            }
+            Some(Err(message)) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+            None => {}
+        }
+    }
+
+    let sessions = or_exit(sessions(rocket.config()));
+    if let Some(delivery) = or_exit(delivery(rocket.config())) {
+        spawn_outbox(&backend, Box::new(delivery));
+    }
+
+    rocket
         .manage(api::root_node())
-        .mount("/", rocket::routes![playground, api])
+        .manage(backend)
+        .manage(sessions)
+        // the color index is built on the first lookup and shared by every request after
+        .manage(products::ColorIndex::new())
+        // as is the search index
+        .manage(products::SearchIndex::new())
+        .mount("/", rocket::routes![playground, api, shopping_list])
         .launch();
 }
@@ -0,0 +1,96 @@
+// external crates
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{accounts, alerts, currency, fixtures, inventory, pricing, products, recipes};
+
+/// an in-memory stand-in for the database. every domain implements its repository
+/// for the store alongside the database version so the two can be swapped freely.
+pub struct Store {
+    tables: RwLock<Tables>,
+}
+
+/// the records held by the store, one collection per table in the database
+pub struct Tables {
+    pub paints: Vec<products::Paint>,
+    pub brands: Vec<products::Brand>,
+    pub product_lines: Vec<products::ProductLine>,
+    pub paint_equivalents: Vec<products::Equivalence>,
+    pub product_videos: Vec<products::ProductVideo>,
+    pub paint_usages: Vec<products::PaintUsage>,
+    pub users: Vec<accounts::User>,
+    pub api_keys: Vec<accounts::ApiKey>,
+    pub api_key_usage: Vec<accounts::ApiKeyDailyUsage>,
+    pub inventory_items: Vec<inventory::InventoryItem>,
+    pub wishlist_items: Vec<inventory::WishlistItem>,
+    pub retailers: Vec<pricing::Retailer>,
+    pub price_observations: Vec<pricing::PriceObservation>,
+    pub exchange_rates: Vec<currency::ExchangeRate>,
+    pub price_alerts: Vec<alerts::PriceAlert>,
+    pub notifications: Vec<alerts::Notification>,
+    pub recipes: Vec<recipes::Recipe>,
+    /// each step holds its own paints rather than keeping them in a table of their own
+    pub recipe_steps: Vec<recipes::RecipeStep>,
+    pub recipe_tags: Vec<recipes::RecipeTag>,
+}
+
+impl Store {
+    /// a store that starts off with the same records as a freshly migrated database
+    pub fn seeded() -> Store {
+        Store {
+            tables: RwLock::new(Tables {
+                paints: fixtures::paints(),
+                brands: fixtures::brands(),
+                product_lines: fixtures::product_lines(),
+                paint_equivalents: fixtures::paint_equivalents(),
+                product_videos: fixtures::product_videos(),
+                paint_usages: fixtures::paint_usages(),
+                // nobody has signed up yet
+                users: Vec::new(),
+                api_keys: Vec::new(),
+                api_key_usage: Vec::new(),
+                inventory_items: Vec::new(),
+                wishlist_items: Vec::new(),
+                retailers: Vec::new(),
+                price_observations: Vec::new(),
+                exchange_rates: Vec::new(),
+                price_alerts: Vec::new(),
+                notifications: Vec::new(),
+                recipes: Vec::new(),
+                recipe_steps: Vec::new(),
+                recipe_tags: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        // a panic while holding the lock can't leave the tables half-written so it's safe to carry on
+        self.tables.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// the id sqlite would hand the next row of a table with the given ids
+pub fn next_id<I: Iterator<Item = i32>>(ids: I) -> i32 {
+    ids.max().unwrap_or(0) + 1
+}
+
+/// overwrite the record with the same id, returning false if there isn't one
+pub fn replace<T: Clone, F: Fn(&T) -> i32>(records: &mut [T], record: &T, id_of: F) -> bool {
+    match records.iter_mut().find(|existing| id_of(existing) == id_of(record)) {
+        Some(existing) => {
+            *existing = record.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+/// remove the record with the given id, returning false if there isn't one
+pub fn remove<T, F: Fn(&T) -> i32>(records: &mut Vec<T>, id: i32, id_of: F) -> bool {
+    let before = records.len();
+    records.retain(|record| id_of(record) != id);
+    records.len() < before
+}
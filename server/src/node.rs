@@ -0,0 +1,180 @@
+// external crates
+use juniper::ID;
+
+use crate::{accounts, alerts, api, db, inventory, pricing, products, recipes};
+
+/// the kinds of records that can be looked up by their global id
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Paint,
+    Brand,
+    ProductLine,
+    ProductVideo,
+    User,
+    ApiKey,
+    InventoryItem,
+    WishlistItem,
+    Retailer,
+    PriceObservation,
+    PriceAlert,
+    Notification,
+    Recipe,
+}
+
+impl Kind {
+    /// the name of the GraphQL type for the kind of record, which prefixes its global ids
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Paint => "Paint",
+            Kind::Brand => "Brand",
+            Kind::ProductLine => "ProductLine",
+            Kind::ProductVideo => "ProductVideo",
+            Kind::User => "User",
+            Kind::ApiKey => "ApiKey",
+            Kind::InventoryItem => "InventoryItem",
+            Kind::WishlistItem => "WishlistItem",
+            Kind::Retailer => "Retailer",
+            Kind::PriceObservation => "PriceObservation",
+            Kind::PriceAlert => "PriceAlert",
+            Kind::Notification => "Notification",
+            Kind::Recipe => "Recipe",
+        }
+    }
+
+    fn from_str(name: &str) -> Option<Kind> {
+        match name {
+            "Paint" => Some(Kind::Paint),
+            "Brand" => Some(Kind::Brand),
+            "ProductLine" => Some(Kind::ProductLine),
+            "ProductVideo" => Some(Kind::ProductVideo),
+            "User" => Some(Kind::User),
+            "ApiKey" => Some(Kind::ApiKey),
+            "InventoryItem" => Some(Kind::InventoryItem),
+            "WishlistItem" => Some(Kind::WishlistItem),
+            "Retailer" => Some(Kind::Retailer),
+            "PriceObservation" => Some(Kind::PriceObservation),
+            "PriceAlert" => Some(Kind::PriceAlert),
+            "Notification" => Some(Kind::Notification),
+            "Recipe" => Some(Kind::Recipe),
+            _ => None,
+        }
+    }
+}
+
+/// the id clients use for a record. it's unique across every kind of record so a client
+/// cache can use it as the key. clients are meant to treat it as opaque
+pub fn global_id(kind: Kind, id: i32) -> ID {
+    ID::new(base64::encode(&format!("{}:{}", kind.as_str(), id)))
+}
+
+/// the kind of record a global id points at along with the record's own id
+pub fn decode(id: &ID) -> Option<(Kind, i32)> {
+    let decoded = String::from_utf8(base64::decode(&**id).ok()?).ok()?;
+    let mut parts = decoded.splitn(2, ':');
+    let kind = Kind::from_str(parts.next()?)?;
+
+    Some((kind, parts.next()?.parse().ok()?))
+}
+
+/// the record's own id if the global id points at the given kind of record
+pub fn local_id(kind: Kind, id: &ID) -> Option<i32> {
+    match decode(id) {
+        Some((found, id)) if found == kind => Some(id),
+        _ => None,
+    }
+}
+
+/// any record that can be looked up by its global id
+pub enum Node {
+    Paint(products::Paint),
+    Brand(products::Brand),
+    ProductLine(products::ProductLine),
+    ProductVideo(products::ProductVideo),
+    User(accounts::User),
+    ApiKey(accounts::ApiKey),
+    InventoryItem(inventory::InventoryItem),
+    WishlistItem(inventory::WishlistItem),
+    Retailer(pricing::Retailer),
+    PriceObservation(pricing::PriceObservation),
+    PriceAlert(alerts::PriceAlert),
+    Notification(alerts::Notification),
+    Recipe(recipes::Recipe),
+}
+
+impl Node {
+    /// the record a global id points at, if there is one
+    pub fn find(context: &api::Context, id: &ID) -> Result<Option<Node>, db::Error> {
+        let (kind, id) = match decode(id) {
+            Some(decoded) => decoded,
+            None => return Ok(None),
+        };
+
+        let products = &context.products;
+        Ok(match kind {
+            Kind::Paint => products.paint(id)?.map(Node::Paint),
+            Kind::Brand => products.brand(id)?.map(Node::Brand),
+            Kind::ProductLine => products.line(id)?.map(Node::ProductLine),
+            Kind::ProductVideo => products.video(id)?.map(Node::ProductVideo),
+            Kind::User => context.accounts.user(id)?.map(Node::User),
+            Kind::ApiKey => context.accounts.api_key(id)?.map(Node::ApiKey),
+            // only the owner of an item gets to see it
+            Kind::InventoryItem => match context.accounts.viewer()? {
+                Some(viewer) => context.inventory.item(&viewer, id)?.map(Node::InventoryItem),
+                None => None,
+            },
+            Kind::WishlistItem => match context.accounts.viewer()? {
+                Some(viewer) => context.inventory.wishlist_item(&viewer, id)?.map(Node::WishlistItem),
+                None => None,
+            },
+            Kind::Retailer => context.pricing.retailer(id)?.map(Node::Retailer),
+            Kind::PriceObservation => context.pricing.observation(id)?.map(Node::PriceObservation),
+            Kind::PriceAlert => match context.accounts.viewer()? {
+                Some(viewer) => context.alerts.alert(&viewer, id)?.map(Node::PriceAlert),
+                None => None,
+            },
+            Kind::Notification => match context.accounts.viewer()? {
+                Some(viewer) => context.alerts.notification(&viewer, id)?.map(Node::Notification),
+                None => None,
+            },
+            Kind::Recipe => context.recipes.recipe(id)?.map(Node::Recipe),
+        })
+    }
+}
+
+juniper::graphql_interface!(Node: api::Context |&self| {
+    description: "a record that can be looked up by its global id"
+
+    field id() -> ID as "the globally unique identifier of the record" {
+        match *self {
+            Node::Paint(ref paint) => global_id(Kind::Paint, paint.id),
+            Node::Brand(ref brand) => global_id(Kind::Brand, brand.id),
+            Node::ProductLine(ref line) => global_id(Kind::ProductLine, line.id),
+            Node::ProductVideo(ref video) => global_id(Kind::ProductVideo, video.id),
+            Node::User(ref user) => global_id(Kind::User, user.id),
+            Node::ApiKey(ref api_key) => global_id(Kind::ApiKey, api_key.id),
+            Node::InventoryItem(ref item) => global_id(Kind::InventoryItem, item.id),
+            Node::WishlistItem(ref item) => global_id(Kind::WishlistItem, item.id),
+            Node::Retailer(ref retailer) => global_id(Kind::Retailer, retailer.id),
+            Node::PriceObservation(ref observation) => global_id(Kind::PriceObservation, observation.id),
+            Node::PriceAlert(ref alert) => global_id(Kind::PriceAlert, alert.id),
+            Node::Notification(ref notification) => global_id(Kind::Notification, notification.id),
+            Node::Recipe(ref recipe) => global_id(Kind::Recipe, recipe.id),
+        }
+    }
+
+    instance_resolvers: |_| {
+        &products::Paint => match *self { Node::Paint(ref paint) => Some(paint), _ => None },
+        &products::Brand => match *self { Node::Brand(ref brand) => Some(brand), _ => None },
+        &products::ProductLine => match *self { Node::ProductLine(ref line) => Some(line), _ => None },
+        &products::ProductVideo => match *self { Node::ProductVideo(ref video) => Some(video), _ => None },
+        &accounts::User => match *self { Node::User(ref user) => Some(user), _ => None },
+        &accounts::ApiKey => match *self { Node::ApiKey(ref api_key) => Some(api_key), _ => None },
+        &inventory::InventoryItem => match *self { Node::InventoryItem(ref item) => Some(item), _ => None },
+        &inventory::WishlistItem => match *self { Node::WishlistItem(ref item) => Some(item), _ => None },
+        &pricing::Retailer => match *self { Node::Retailer(ref retailer) => Some(retailer), _ => None },
+        &pricing::PriceObservation => match *self { Node::PriceObservation(ref observation) => Some(observation), _ => None },
+        &alerts::PriceAlert => match *self { Node::PriceAlert(ref alert) => Some(alert), _ => None },
+        &alerts::Notification => match *self { Node::Notification(ref notification) => Some(notification), _ => None },
+        &recipes::Recipe => match *self { Node::Recipe(ref recipe) => Some(recipe), _ => None },
+    }
+});
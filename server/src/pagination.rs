@@ -0,0 +1,376 @@
+use crate::{api, errors};
+
+/// how many records a page holds when the client doesn't say
+const DEFAULT_PAGE_SIZE: i32 = 25;
+/// the most records a client can ask for in a single page
+const MAX_PAGE_SIZE: i32 = 100;
+
+/// the arguments every connection field takes, following the relay spec
+pub struct Page {
+    pub first: Option<i32>,
+    pub after: Option<String>,
+    pub last: Option<i32>,
+    pub before: Option<String>,
+}
+
+impl Page {
+    pub fn new(
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> Page {
+        Page {
+            first,
+            after,
+            last,
+            before,
+        }
+    }
+}
+
+/// a slice of a list of records along with what's needed to fetch the pages around it
+pub struct Connection<T> {
+    pub edges: Vec<Edge<T>>,
+    pub page_info: PageInfo,
+    pub total_count: i32,
+}
+
+/// a record in a connection and the cursor that points at it
+pub struct Edge<T> {
+    pub node: T,
+    pub cursor: String,
+}
+
+/// where a page sits in the full list
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    pub start_cursor: Option<String>,
+    pub end_cursor: Option<String>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl PageInfo {
+    /// whether there are more records after this page
+    fn hasNextPage(&self) -> bool {
+        self.has_next_page
+    }
+
+    /// whether there are more records before this page
+    fn hasPreviousPage(&self) -> bool {
+        self.has_previous_page
+    }
+
+    /// the cursor of the first record in the page
+    fn startCursor(&self) -> Option<&str> {
+        self.start_cursor.as_deref()
+    }
+
+    /// the cursor of the last record in the page
+    fn endCursor(&self) -> Option<&str> {
+        self.end_cursor.as_deref()
+    }
+}
+
+/// cut the page out of the full list of records. cursors point at a record rather than a
+/// position so they keep working as records are added and removed around them. a cursor
+/// whose record has since been removed (or filtered out) picks up from where the record used
+/// to be instead
+pub fn paginate<T, F>(
+    records: Vec<T>,
+    kind: &str,
+    id_of: F,
+    page: Page,
+) -> Result<Connection<T>, errors::Error>
+where
+    F: Fn(&T) -> i32,
+{
+    let mut validation = errors::Validation::new();
+    for (field, size) in &[("first", page.first), ("last", page.last)] {
+        if let Some(size) = size {
+            if !(0..=MAX_PAGE_SIZE).contains(size) {
+                validation.add(field, &format!("must be between 0 and {}", MAX_PAGE_SIZE));
+            }
+        }
+    }
+
+    // where the list gets cut for a cursor, given how far past its record the cut goes
+    let cut = |cursor: &str, past: usize| {
+        let (id, position) = decode_cursor(kind, cursor)?;
+        match records.iter().position(|record| id_of(record) == id) {
+            Some(found) => Some(found + past),
+            // everything after the missing record has moved up into its place
+            None => Some(position.min(records.len())),
+        }
+    };
+    let after = page.after.as_ref().map(|cursor| cut(cursor, 1));
+    let before = page.before.as_ref().map(|cursor| cut(cursor, 0));
+    for (field, cut) in &[("after", after), ("before", before)] {
+        if let Some(None) = cut {
+            validation.add(field, "is not a valid cursor");
+        }
+    }
+    validation.finish()?;
+
+    // narrow down to the records between the cursors and then take from either end
+    let mut start = after.flatten().unwrap_or(0);
+    let mut end = before.flatten().unwrap_or(records.len()).max(start);
+    match (page.first, page.last) {
+        (None, None) => end = end.min(start + DEFAULT_PAGE_SIZE as usize),
+        (first, last) => {
+            if let Some(first) = first {
+                end = end.min(start + first as usize);
+            }
+            if let Some(last) = last {
+                start = start.max(end.saturating_sub(last as usize));
+            }
+        }
+    }
+
+    let total_count = records.len() as i32;
+    let has_previous_page = start > 0;
+    let has_next_page = end < records.len();
+    let edges: Vec<Edge<T>> = records
+        .into_iter()
+        .enumerate()
+        .skip(start)
+        .take(end - start)
+        .map(|(position, node)| Edge {
+            cursor: encode_cursor(kind, id_of(&node), position),
+            node,
+        })
+        .collect();
+
+    Ok(Connection {
+        page_info: PageInfo {
+            has_next_page,
+            has_previous_page,
+            start_cursor: edges.first().map(|edge| edge.cursor.clone()),
+            end_cursor: edges.last().map(|edge| edge.cursor.clone()),
+        },
+        edges,
+        total_count,
+    })
+}
+
+/// the cursor for a record along with where it sat in the list. clients are meant to treat
+/// it as opaque
+fn encode_cursor(kind: &str, id: i32, position: usize) -> String {
+    base64::encode(&format!("{}:{}:{}", kind, id, position))
+}
+
+/// the id of the record a cursor points at and where it sat in the list, if it points at
+/// the right kind of record
+fn decode_cursor(kind: &str, cursor: &str) -> Option<(i32, usize)> {
+    let decoded = String::from_utf8(base64::decode(cursor).ok()?).ok()?;
+    let mut parts = decoded.splitn(3, ':');
+    if parts.next()? != kind {
+        return None;
+    }
+    let id = parts.next()?.parse().ok()?;
+    let position = parts.next()?.parse().ok()?;
+
+    Some((id, position))
+}
+
+/// declare the connection and edge types for a kind of record. juniper can't expose
+/// generic types so every kind of record gets its own pair
+macro_rules! connection {
+    ($connection:ident, $edge:ident, $node:ty) => {
+        pub struct $connection {
+            edges: Vec<$edge>,
+            page_info: $crate::pagination::PageInfo,
+            total_count: i32,
+        }
+
+        pub struct $edge {
+            node: $node,
+            cursor: String,
+        }
+
+        impl From<$crate::pagination::Connection<$node>> for $connection {
+            fn from(connection: $crate::pagination::Connection<$node>) -> $connection {
+                $connection {
+                    edges: connection
+                        .edges
+                        .into_iter()
+                        .map(|edge| $edge {
+                            node: edge.node,
+                            cursor: edge.cursor,
+                        })
+                        .collect(),
+                    page_info: connection.page_info,
+                    total_count: connection.total_count,
+                }
+            }
+        }
+
+        #[juniper::object(
+            Context = $crate::api::Context,
+        )]
+        impl $connection {
+            /// the records in the page along with their cursors
+            fn edges(&self) -> &[$edge] {
+                &self.edges
+            }
+
+            /// where the page sits in the full list
+            fn pageInfo(&self) -> &$crate::pagination::PageInfo {
+                &self.page_info
+            }
+
+            /// how many records there are across every page
+            fn totalCount(&self) -> i32 {
+                self.total_count
+            }
+        }
+
+        #[juniper::object(
+            Context = $crate::api::Context,
+        )]
+        impl $edge {
+            /// the record itself
+            fn node(&self) -> &$node {
+                &self.node
+            }
+
+            /// an opaque pointer at the record to pass as after or before to fetch the
+            /// records around it
+            fn cursor(&self) -> &str {
+                &self.cursor
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the ids in a page
+    fn ids(connection: &Connection<i32>) -> Vec<i32> {
+        connection.edges.iter().map(|edge| edge.node).collect()
+    }
+
+    /// cut a page out of the records, which are their own ids
+    fn page(
+        records: &[i32],
+        first: Option<i32>,
+        after: Option<&str>,
+        last: Option<i32>,
+        before: Option<&str>,
+    ) -> Result<Connection<i32>, errors::Error> {
+        let page = Page::new(first, after.map(str::to_string), last, before.map(str::to_string));
+        paginate(records.to_vec(), "paint", |id| *id, page)
+    }
+
+    /// the problems a page was turned down for, as (field, message) pairs
+    fn problems(result: Result<Connection<i32>, errors::Error>) -> Vec<(String, String)> {
+        match result {
+            Err(errors::Error::Validation(problems)) => problems
+                .into_iter()
+                .map(|problem| (problem.field, problem.message))
+                .collect(),
+            _ => panic!("the page should have been turned down"),
+        }
+    }
+
+    #[test]
+    fn pages_forwards() {
+        let records: Vec<i32> = (1..=10).collect();
+
+        let first = page(&records, Some(4), None, None, None).unwrap();
+        assert_eq!(ids(&first), vec![1, 2, 3, 4]);
+        assert!(first.page_info.has_next_page);
+        assert!(!first.page_info.has_previous_page);
+        assert_eq!(first.total_count, 10);
+
+        let after = first.page_info.end_cursor.unwrap();
+        let second = page(&records, Some(4), Some(&after), None, None).unwrap();
+        assert_eq!(ids(&second), vec![5, 6, 7, 8]);
+
+        let after = second.page_info.end_cursor.unwrap();
+        let third = page(&records, Some(4), Some(&after), None, None).unwrap();
+        assert_eq!(ids(&third), vec![9, 10]);
+        assert!(!third.page_info.has_next_page);
+        assert!(third.page_info.has_previous_page);
+    }
+
+    #[test]
+    fn pages_backwards() {
+        let records: Vec<i32> = (1..=10).collect();
+
+        let last = page(&records, None, None, Some(3), None).unwrap();
+        assert_eq!(ids(&last), vec![8, 9, 10]);
+        assert!(last.page_info.has_previous_page);
+
+        let before = last.page_info.start_cursor.unwrap();
+        let previous = page(&records, None, None, Some(3), Some(&before)).unwrap();
+        assert_eq!(ids(&previous), vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn cursors_follow_their_record() {
+        let records: Vec<i32> = (1..=10).collect();
+        let after = page(&records, Some(3), None, None, None).unwrap().page_info.end_cursor.unwrap();
+
+        // a record added in front of the cursor doesn't show up twice
+        let mut grown = records.clone();
+        grown.insert(0, 11);
+        assert_eq!(ids(&page(&grown, Some(3), Some(&after), None, None).unwrap()), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn cursors_outlive_their_record() {
+        let records: Vec<i32> = (1..=10).collect();
+        let first = page(&records, Some(3), None, None, None).unwrap();
+        let after = first.page_info.end_cursor.unwrap();
+        let before = first.page_info.start_cursor.unwrap();
+
+        // the record the cursor points at is removed before the next page is asked for
+        let shrunk: Vec<i32> = records.iter().cloned().filter(|id| *id != 3).collect();
+        let next = page(&shrunk, Some(3), Some(&after), None, None).unwrap();
+        assert_eq!(ids(&next), vec![4, 5, 6]);
+
+        let shrunk: Vec<i32> = records.iter().cloned().filter(|id| *id != 1).collect();
+        let previous = page(&shrunk, None, None, Some(3), Some(&before)).unwrap();
+        assert!(ids(&previous).is_empty());
+        assert!(previous.page_info.has_next_page);
+    }
+
+    #[test]
+    fn bad_cursors_are_turned_down() {
+        let records: Vec<i32> = (1..=10).collect();
+
+        // a cursor for another kind of record
+        let brand = encode_cursor("brand", 3, 2);
+        assert_eq!(
+            problems(page(&records, None, Some(&brand), None, None)),
+            vec![("after".to_string(), "is not a valid cursor".to_string())]
+        );
+
+        // a cursor that's missing where its record sat
+        let partial = base64::encode("paint:7");
+        assert_eq!(
+            problems(page(&records, None, None, None, Some(&partial))),
+            vec![("before".to_string(), "is not a valid cursor".to_string())]
+        );
+    }
+
+    #[test]
+    fn page_sizes_are_limited() {
+        let records: Vec<i32> = (1..=200).collect();
+
+        assert_eq!(page(&records, None, None, None, None).unwrap().edges.len(), 25);
+        assert_eq!(
+            problems(page(&records, Some(101), None, Some(-1), None)),
+            vec![
+                ("first".to_string(), "must be between 0 and 100".to_string()),
+                ("last".to_string(), "must be between 0 and 100".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// external crates
+use juniper::ID;
+use std::sync::Arc;
+
+use crate::node::{self, Kind};
+use crate::{db, errors, loaders::Loader, products};
+
+// the types that make up the domain
+mod observations;
+mod retailers;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::observations::*;
+pub use self::retailers::*;
+
+/// the operations a storage backend has to support in order to serve prices
+pub trait Repository: Send + Sync {
+    /// every retailer we know of, by name
+    fn all_retailers(&self) -> Result<Vec<Retailer>, db::Error>;
+
+    /// the retailers with the matching ids, in the same order as the ids
+    fn retailers_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Retailer>>, db::Error>;
+
+    /// store a new retailer, ignoring its id, and return it with the id it was given
+    fn insert_retailer(&self, retailer: &Retailer) -> Result<Retailer, db::Error>;
+
+    /// overwrite the retailer with the same id, returning false if there isn't one
+    fn update_retailer(&self, retailer: &Retailer) -> Result<bool, db::Error>;
+
+    /// the observations with the matching ids, in the same order as the ids
+    fn observations_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceObservation>>, db::Error>;
+
+    /// every price seen for each of the paints (oldest first), in the same order as the
+    /// paint ids
+    fn observations_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<PriceObservation>>, db::Error>;
+
+    /// store a new observation, ignoring its id, and return it with the id it was given
+    fn insert_observation(&self, observation: &PriceObservation) -> Result<PriceObservation, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    retailers: Loader<i32, Option<Retailer>>,
+    observations: Loader<i32, Option<PriceObservation>>,
+    observations_by_paint: Loader<i32, Vec<PriceObservation>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>) -> Client {
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            retailers: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.retailers_by_id(ids)
+            }),
+            observations: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.observations_by_id(ids)
+            }),
+            observations_by_paint: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.observations_by_paint(ids)
+            }),
+            repo,
+        }
+    }
+
+    pub fn all_retailers(&self) -> Result<Vec<Retailer>, db::Error> {
+        let retailers = self.repo.all_retailers()?;
+        for retailer in &retailers {
+            self.retailers.prime(retailer.id, Some(retailer.clone()));
+        }
+
+        Ok(retailers)
+    }
+
+    pub fn retailer(&self, id: i32) -> Result<Option<Retailer>, db::Error> {
+        self.retailers.load(id)
+    }
+
+    /// the retailer an id sent by the client refers to, if there is one
+    pub fn find_retailer(&self, id: &ID) -> Result<Option<Retailer>, db::Error> {
+        match node::local_id(Kind::Retailer, id) {
+            Some(id) => self.retailer(id),
+            None => Ok(None),
+        }
+    }
+
+    pub fn create_retailer(&self, input: RetailerInput) -> Result<Retailer, errors::Error> {
+        let retailer = input.into_retailer(0, self)?;
+        let retailer = self.repo.insert_retailer(&retailer)?;
+
+        self.retailers.replace(retailer.id, Some(retailer.clone()));
+        Ok(retailer)
+    }
+
+    pub fn update_retailer(&self, id: i32, input: RetailerInput) -> Result<Retailer, errors::Error> {
+        if self.retailer(id)?.is_none() {
+            return Err(errors::Error::NotFound(format!("retailer {}", id)));
+        }
+        let retailer = input.into_retailer(id, self)?;
+        if !self.repo.update_retailer(&retailer)? {
+            return Err(errors::Error::NotFound(format!("retailer {}", id)));
+        }
+
+        self.retailers.replace(id, Some(retailer.clone()));
+        Ok(retailer)
+    }
+
+    pub fn observation(&self, id: i32) -> Result<Option<PriceObservation>, db::Error> {
+        self.observations.load(id)
+    }
+
+    /// every price seen for the paint, oldest first
+    pub fn observations_for_paint(&self, paint_id: i32) -> Result<Vec<PriceObservation>, db::Error> {
+        let observations = self.observations_by_paint.load(paint_id)?;
+
+        // the retailers are usually the next thing asked for so we want them in a single batch
+        self.retailers
+            .expect(observations.iter().map(|observation| observation.retailer_id));
+
+        Ok(observations)
+    }
+
+    /// remember paints whose prices are about to be asked for so they're loaded in a
+    /// single batch
+    pub fn expect_paints<I: IntoIterator<Item = i32>>(&self, paint_ids: I) {
+        self.observations_by_paint.expect(paint_ids);
+    }
+
+    /// record a price seen for a paint at a retailer
+    pub fn record_observation(
+        &self,
+        input: PriceObservationInput,
+        products: &products::Client,
+    ) -> Result<PriceObservation, errors::Error> {
+        let observation = input.into_observation(self, products)?;
+        let observation = self.repo.insert_observation(&observation)?;
+
+        self.observations.replace(observation.id, Some(observation.clone()));
+        self.observations_by_paint.clear();
+        Ok(observation)
+    }
+}
@@ -0,0 +1,83 @@
+use super::{PriceObservation, Repository, Retailer};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn all_retailers(&self) -> Result<Vec<Retailer>, db::Error> {
+        let mut retailers = self.read().retailers.clone();
+        retailers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(retailers)
+    }
+
+    fn retailers_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Retailer>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.retailers.iter().find(|retailer| retailer.id == *id).cloned())
+            .collect())
+    }
+
+    fn insert_retailer(&self, retailer: &Retailer) -> Result<Retailer, db::Error> {
+        let mut tables = self.write();
+
+        let retailer = Retailer {
+            id: memory::next_id(tables.retailers.iter().map(|retailer| retailer.id)),
+            ..retailer.clone()
+        };
+        tables.retailers.push(retailer.clone());
+
+        Ok(retailer)
+    }
+
+    fn update_retailer(&self, retailer: &Retailer) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().retailers, retailer, |retailer| retailer.id))
+    }
+
+    fn observations_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceObservation>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| {
+                tables
+                    .price_observations
+                    .iter()
+                    .find(|observation| observation.id == *id)
+                    .cloned()
+            })
+            .collect())
+    }
+
+    fn observations_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<PriceObservation>>, db::Error> {
+        let tables = self.read();
+
+        Ok(paint_ids
+            .iter()
+            .map(|paint_id| {
+                let mut observations: Vec<PriceObservation> = tables
+                    .price_observations
+                    .iter()
+                    .filter(|observation| observation.paint_id == *paint_id)
+                    .cloned()
+                    .collect();
+                // the sort is stable so observations from the same moment stay in the order
+                // they were recorded
+                observations.sort_by_key(|observation| observation.observed_at);
+                observations
+            })
+            .collect())
+    }
+
+    fn insert_observation(&self, observation: &PriceObservation) -> Result<PriceObservation, db::Error> {
+        let mut tables = self.write();
+
+        let observation = PriceObservation {
+            id: memory::next_id(tables.price_observations.iter().map(|observation| observation.id)),
+            ..observation.clone()
+        };
+        tables.price_observations.push(observation.clone());
+
+        Ok(observation)
+    }
+}
@@ -0,0 +1,239 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use super::Client;
+use crate::currency::{self, is_currency_code, Money};
+use crate::node::{self, Kind, Node};
+use crate::{api, db, errors, products};
+
+/// a price seen for a paint at a retailer at some point in time
+#[derive(Clone, Debug)]
+pub struct PriceObservation {
+    pub id: i32,
+    pub retailer_id: i32,
+    pub paint_id: i32,
+    /// the price of a single pot
+    pub price: Money,
+    pub in_stock: bool,
+    pub observed_at: DateTime<Utc>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl PriceObservation {
+    /// the globally unique identifier of the observation
+    fn id(&self) -> ID {
+        node::global_id(Kind::PriceObservation, self.id)
+    }
+
+    /// the shop the price was seen at
+    fn retailer(&self, context: &api::Context) -> FieldResult<super::Retailer> {
+        Ok(context.pricing.retailer(self.retailer_id)?.ok_or("missing retailer")?)
+    }
+
+    /// the paint the price is for
+    fn paint(&self, context: &api::Context) -> FieldResult<products::Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+
+    /// the price of a single pot, converted into another currency if one is given
+    fn price(&self, context: &api::Context, currency: Option<String>) -> FieldResult<Money> {
+        Ok(context.currency.convert(&self.price, currency.as_deref())?)
+    }
+
+    /// whether the shop had the paint to sell when the price was seen
+    fn inStock(&self) -> bool {
+        self.in_stock
+    }
+
+    /// when the price was seen
+    fn observedAt(&self) -> DateTime<Utc> {
+        self.observed_at
+    }
+}
+
+/// a price seen for a paint at a retailer
+#[derive(juniper::GraphQLInputObject)]
+pub struct PriceObservationInput {
+    /// the id of the retailer the price was seen at
+    pub retailer: ID,
+    /// the id of the paint the price is for
+    pub paint: ID,
+    /// the price of a single pot in the currency's unit (ie, 3.75)
+    pub price: f64,
+    /// the ISO 4217 code of the currency (ie, USD or EUR)
+    pub currency: String,
+    pub in_stock: bool,
+    /// when the price was seen. defaults to now
+    pub observed_at: Option<DateTime<Utc>>,
+}
+
+impl PriceObservationInput {
+    /// check the input over and turn it into an observation that hasn't been stored yet
+    pub(super) fn into_observation(
+        self,
+        client: &Client,
+        products: &products::Client,
+    ) -> Result<PriceObservation, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let retailer = client.find_retailer(&self.retailer)?;
+        if retailer.is_none() {
+            validation.add("retailer", "does not exist");
+        }
+        let paint = products.find_paint(&self.paint)?;
+        if paint.is_none() {
+            validation.add("paint", "does not exist");
+        }
+        if !self.price.is_finite() || self.price < 0.0 {
+            validation.add("price", "must not be negative");
+        }
+        let currency = self.currency.trim().to_uppercase();
+        if !is_currency_code(&currency) {
+            validation.add("currency", "must be a three letter currency code");
+        }
+        let now = Utc::now();
+        let observed_at = self.observed_at.unwrap_or(now);
+        if observed_at > now {
+            validation.add("observedAt", "must not be in the future");
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the retailer or paint were missing
+        Ok(PriceObservation {
+            id: 0,
+            retailer_id: retailer.unwrap().id,
+            paint_id: paint.unwrap().id,
+            price: Money::new((self.price * 100.0).round() as i64, &currency),
+            in_stock: self.in_stock,
+            observed_at,
+        })
+    }
+}
+
+impl Client {
+    /// the latest price at every retailer that has been seen selling the paint. prices
+    /// where the paint is in stock come first, cheapest first. prices in different
+    /// currencies are compared with the latest exchange rates. prices in a currency we
+    /// don't have a rate for can't be compared so they come after the rest
+    pub fn current_prices(
+        &self,
+        paint_id: i32,
+        rates: &currency::Client,
+    ) -> Result<Vec<PriceObservation>, db::Error> {
+        // the observations are oldest first so later ones replace earlier ones
+        let mut latest: Vec<PriceObservation> = Vec::new();
+        for observation in self.observations_for_paint(paint_id)? {
+            match latest.iter_mut().find(|seen| seen.retailer_id == observation.retailer_id) {
+                Some(seen) => *seen = observation,
+                None => latest.push(observation),
+            }
+        }
+
+        let mut ranked = Vec::new();
+        for observation in latest {
+            let cost = rates.in_base(&observation.price)?;
+            ranked.push((cost, observation));
+        }
+        ranked.sort_by(|(a_cost, a), (b_cost, b)| {
+            b.in_stock
+                .cmp(&a.in_stock)
+                .then(a_cost.is_none().cmp(&b_cost.is_none()))
+                .then(a_cost.partial_cmp(b_cost).unwrap_or(std::cmp::Ordering::Equal))
+        });
+
+        Ok(ranked.into_iter().map(|(_, observation)| observation).collect())
+    }
+
+    /// the cheapest price the paint can be bought for right now, if any retailer has it
+    /// in stock
+    pub fn cheapest_in_stock(
+        &self,
+        paint_id: i32,
+        rates: &currency::Client,
+    ) -> Result<Option<PriceObservation>, db::Error> {
+        Ok(self
+            .current_prices(paint_id, rates)?
+            .into_iter()
+            .find(|observation| observation.in_stock))
+    }
+
+    /// every price seen for the paint since the given time, oldest first, optionally at a
+    /// single retailer
+    pub fn price_history(
+        &self,
+        paint_id: i32,
+        retailer_id: Option<i32>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<PriceObservation>, db::Error> {
+        let mut observations = self.observations_for_paint(paint_id)?;
+        if let Some(retailer_id) = retailer_id {
+            observations.retain(|observation| observation.retailer_id == retailer_id);
+        }
+        if let Some(since) = since {
+            observations.retain(|observation| observation.observed_at >= since);
+        }
+
+        Ok(observations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::ExchangeRate;
+    use crate::pricing::Retailer;
+    use crate::storage;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn prices_we_cannot_convert_come_last() {
+        let backend = storage::Backend::memory();
+        backend
+            .currency()
+            .save_rates(&[ExchangeRate {
+                currency: "EUR".to_string(),
+                rate: 0.9,
+                effective_on: NaiveDate::from_ymd_opt(2019, 6, 1).unwrap(),
+            }])
+            .unwrap();
+        // retailer, price, in stock
+        let prices = vec![
+            (1, Money::new(900, "USD"), true),
+            (2, Money::new(800, "EUR"), true),
+            // a yen is worth far less than a cent but without a rate we can't tell
+            (3, Money::new(100, "JPY"), true),
+            (4, Money::new(500, "USD"), false),
+        ];
+        for (retailer_id, price, in_stock) in prices {
+            backend
+                .pricing()
+                .insert_retailer(&Retailer {
+                    id: 0,
+                    name: format!("Shop {}", retailer_id),
+                    url: None,
+                })
+                .unwrap();
+            backend
+                .pricing()
+                .insert_observation(&PriceObservation {
+                    id: 0,
+                    retailer_id,
+                    paint_id: 1,
+                    price,
+                    in_stock,
+                    observed_at: Utc::now(),
+                })
+                .unwrap();
+        }
+
+        let ranked = Client::new(backend.pricing())
+            .current_prices(1, &currency::Client::new(backend.currency()))
+            .unwrap();
+        // the euro price is the cheapest once it's converted
+        let retailers: Vec<i32> = ranked.iter().map(|price| price.retailer_id).collect();
+        assert_eq!(retailers, vec![2, 1, 3, 4]);
+    }
+}
@@ -0,0 +1,75 @@
+// external crates
+use juniper::ID;
+
+use super::Client;
+use crate::node::{self, Kind, Node};
+use crate::{api, errors};
+
+/// a shop that sells paint
+#[derive(Clone, Debug)]
+pub struct Retailer {
+    pub id: i32,
+    pub name: String,
+    pub url: Option<String>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl Retailer {
+    /// the globally unique identifier of the retailer
+    fn id(&self) -> ID {
+        node::global_id(Kind::Retailer, self.id)
+    }
+
+    /// the name the shop trades under
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// where the shop can be found online
+    fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+}
+
+connection!(RetailerConnection, RetailerEdge, Retailer);
+
+/// the details of a retailer
+#[derive(juniper::GraphQLInputObject)]
+pub struct RetailerInput {
+    pub name: String,
+    pub url: Option<String>,
+}
+
+impl RetailerInput {
+    /// check the input over and turn it into a retailer that hasn't been stored yet
+    pub(super) fn into_retailer(self, id: i32, client: &Client) -> Result<Retailer, errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("name", &self.name);
+
+        // two retailers with the same name would be impossible to tell apart
+        let name = self.name.trim();
+        let taken = client
+            .all_retailers()?
+            .iter()
+            .any(|retailer| retailer.id != id && retailer.name.eq_ignore_ascii_case(name));
+        if taken {
+            validation.add("name", "is already taken by another retailer");
+        }
+        let url = self.url.as_ref().map(|url| url.trim());
+        if let Some(url) = url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                validation.add("url", "must be a web address");
+            }
+        }
+        validation.finish()?;
+
+        Ok(Retailer {
+            id,
+            name: name.to_string(),
+            url: url.map(str::to_string),
+        })
+    }
+}
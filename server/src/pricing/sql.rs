@@ -0,0 +1,122 @@
+// external crates
+use rusqlite::{params, Row, NO_PARAMS};
+
+use super::{PriceObservation, Repository, Retailer};
+use crate::currency::Money;
+use crate::db;
+
+/// the columns we need to select in order to build a PriceObservation
+const OBSERVATION_COLUMNS: &str =
+    "id, retailer_id, paint_id, price_cents, currency, in_stock, observed_at";
+
+impl Repository for db::Pool {
+    fn all_retailers(&self) -> Result<Vec<Retailer>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare("SELECT id, name, url FROM retailers ORDER BY name")?;
+
+        let retailers = statement
+            .query_map(NO_PARAMS, retailer_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(retailers)
+    }
+
+    fn retailers_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Retailer>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            "SELECT id, name, url FROM retailers WHERE id IN ({keys})",
+            ids,
+            retailer_from_row,
+            |retailer| retailer.id,
+        )
+    }
+
+    fn insert_retailer(&self, retailer: &Retailer) -> Result<Retailer, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO retailers (name, url) VALUES (?, ?)",
+            params![retailer.name, retailer.url],
+        )?;
+
+        Ok(Retailer {
+            id: conn.last_insert_rowid() as i32,
+            ..retailer.clone()
+        })
+    }
+
+    fn update_retailer(&self, retailer: &Retailer) -> Result<bool, db::Error> {
+        let changed = self.get()?.execute(
+            "UPDATE retailers SET name = ?, url = ? WHERE id = ?",
+            params![retailer.name, retailer.url, retailer.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn observations_by_id(&self, ids: &[i32]) -> Result<Vec<Option<PriceObservation>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM price_observations WHERE id IN ({{keys}})", OBSERVATION_COLUMNS),
+            ids,
+            observation_from_row,
+            |observation| observation.id,
+        )
+    }
+
+    fn observations_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<PriceObservation>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM price_observations WHERE paint_id IN ({{keys}})
+                 ORDER BY observed_at, id",
+                OBSERVATION_COLUMNS
+            ),
+            paint_ids,
+            observation_from_row,
+            |observation| observation.paint_id,
+        )
+    }
+
+    fn insert_observation(&self, observation: &PriceObservation) -> Result<PriceObservation, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO price_observations
+                 (retailer_id, paint_id, price_cents, currency, in_stock, observed_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                observation.retailer_id,
+                observation.paint_id,
+                observation.price.cents,
+                observation.price.currency,
+                observation.in_stock,
+                observation.observed_at,
+            ],
+        )?;
+
+        Ok(PriceObservation {
+            id: conn.last_insert_rowid() as i32,
+            ..observation.clone()
+        })
+    }
+}
+
+/// build a retailer out of a row containing its id, name and url
+fn retailer_from_row(row: &Row) -> rusqlite::Result<Retailer> {
+    Ok(Retailer {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        url: row.get(2)?,
+    })
+}
+
+/// build an observation out of a row containing OBSERVATION_COLUMNS
+fn observation_from_row(row: &Row) -> rusqlite::Result<PriceObservation> {
+    Ok(PriceObservation {
+        id: row.get(0)?,
+        retailer_id: row.get(1)?,
+        paint_id: row.get(2)?,
+        price: Money::new(row.get(3)?, &row.get::<_, String>(4)?),
+        in_stock: row.get(5)?,
+        observed_at: row.get(6)?,
+    })
+}
@@ -1,27 +1,437 @@
-pub struct Client;
+// external crates
+use juniper::ID;
+use std::sync::Arc;
 
-use crate::api;
+use crate::node::{self, Kind};
+use crate::{db, errors, loaders::Loader};
 
-impl Client { 
-    pub fn new() -> Client { 
-        Client{}
+// the types that make up the domain
+mod brands;
+mod equivalents;
+mod filters;
+mod paints;
+mod videos;
+// the indexes that speed up lookups storage can't do well on its own
+mod index;
+mod search;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::brands::*;
+pub use self::equivalents::*;
+pub use self::filters::*;
+pub use self::index::ColorIndex;
+pub use self::paints::*;
+pub use self::search::{PaintSuggestion, SearchIndex, SearchResult, SearchType};
+pub use self::videos::*;
+
+/// the operations a storage backend has to support in order to serve products
+pub trait Repository: Send + Sync {
+    /// the paints that match the query, in the order it asks for
+    fn find_paints(&self, query: &PaintQuery) -> Result<Vec<Paint>, db::Error>;
+
+    /// the paints with the matching ids, in the same order as the ids
+    fn paints_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Paint>>, db::Error>;
+
+    /// the paints in each of the product lines, in the same order as the line ids
+    fn paints_by_line(&self, line_ids: &[i32]) -> Result<Vec<Vec<Paint>>, db::Error>;
+
+    /// store a new paint, ignoring its id, and return it with the id it was given
+    fn insert_paint(&self, paint: &Paint) -> Result<Paint, db::Error>;
+
+    /// overwrite the paint with the same id, returning false if there isn't one
+    fn update_paint(&self, paint: &Paint) -> Result<bool, db::Error>;
+
+    /// remove the paint with the given id, returning false if there isn't one
+    fn delete_paint(&self, id: i32) -> Result<bool, db::Error>;
+
+    /// every brand we know of
+    fn all_brands(&self) -> Result<Vec<Brand>, db::Error>;
+
+    /// the brands with the matching ids, in the same order as the ids
+    fn brands_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Brand>>, db::Error>;
+
+    /// store a new brand, ignoring its id, and return it with the id it was given
+    fn insert_brand(&self, brand: &Brand) -> Result<Brand, db::Error>;
+
+    /// overwrite the brand with the same id, returning false if there isn't one
+    fn update_brand(&self, brand: &Brand) -> Result<bool, db::Error>;
+
+    /// remove the brand with the given id, returning false if there isn't one
+    fn delete_brand(&self, id: i32) -> Result<bool, db::Error>;
+
+    /// the product lines with the matching ids, in the same order as the ids
+    fn lines_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductLine>>, db::Error>;
+
+    /// the product lines sold by each of the brands, in the same order as the brand ids
+    fn lines_by_brand(&self, brand_ids: &[i32]) -> Result<Vec<Vec<ProductLine>>, db::Error>;
+
+    /// store a new product line, ignoring its id, and return it with the id it was given
+    fn insert_line(&self, line: &ProductLine) -> Result<ProductLine, db::Error>;
+
+    /// overwrite the product line with the same id, returning false if there isn't one
+    fn update_line(&self, line: &ProductLine) -> Result<bool, db::Error>;
+
+    /// remove the product line with the given id, returning false if there isn't one
+    fn delete_line(&self, id: i32) -> Result<bool, db::Error>;
+
+    /// the curated equivalences of each of the paints, in the same order as the paint ids.
+    /// equivalences go both ways so each one is turned around to start from its paint
+    fn equivalences_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<Equivalence>>, db::Error>;
+
+    /// store an equivalence, replacing the source of one already stored for the same pair
+    fn insert_equivalence(&self, equivalence: &Equivalence) -> Result<(), db::Error>;
+
+    /// remove the equivalence between two paints, returning false if there isn't one
+    fn delete_equivalence(&self, paint_id: i32, equivalent_id: i32) -> Result<bool, db::Error>;
+
+    /// every video we know of, newest first
+    fn all_videos(&self) -> Result<Vec<ProductVideo>, db::Error>;
+
+    /// the videos with the matching ids, in the same order as the ids
+    fn videos_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductVideo>>, db::Error>;
+
+    /// the videos that use each of the paints (newest first), in the same order as the paint ids
+    fn videos_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<ProductVideo>>, db::Error>;
+
+    /// the paints used in each of the videos (in the order they show up), in the same order
+    /// as the video ids
+    fn usages_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<PaintUsage>>, db::Error>;
+
+    /// store a new video and the paints used in it, ignoring its id, and return it with the
+    /// id it was given
+    fn insert_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<ProductVideo, db::Error>;
+
+    /// overwrite the video with the same id along with the paints used in it, returning false
+    /// if there isn't one
+    fn update_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<bool, db::Error>;
+
+    /// remove the video with the given id, returning false if there isn't one
+    fn delete_video(&self, id: i32) -> Result<bool, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    index: ColorIndex,
+    search_index: SearchIndex,
+    catalog: Loader<(), Vec<Paint>>,
+    paints: Loader<i32, Option<Paint>>,
+    paints_by_line: Arc<Loader<i32, Vec<Paint>>>,
+    brands: Arc<Loader<i32, Option<Brand>>>,
+    lines: Loader<i32, Option<ProductLine>>,
+    lines_by_brand: Loader<i32, Vec<ProductLine>>,
+    equivalences: Loader<i32, Vec<Equivalence>>,
+    videos: Loader<i32, Option<ProductVideo>>,
+    videos_by_paint: Loader<i32, Vec<ProductVideo>>,
+    usages: Loader<i32, Vec<PaintUsage>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>, index: ColorIndex, search_index: SearchIndex) -> Client {
+        // a few loaders know which keys their siblings will be asked for next
+        let brands = Arc::new(Loader::new({
+            let repo = repo.clone();
+            move |ids: &[i32]| repo.brands_by_id(ids)
+        }));
+        let paints_by_line = Arc::new(Loader::new({
+            let repo = repo.clone();
+            move |ids: &[i32]| repo.paints_by_line(ids)
+        }));
+
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            catalog: Loader::new({
+                let repo = repo.clone();
+                move |keys: &[()]| Ok(vec![repo.find_paints(&PaintQuery::default())?; keys.len()])
+            }),
+            paints: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.paints_by_id(ids)
+            }),
+            lines: Loader::new({
+                let repo = repo.clone();
+                let brands = brands.clone();
+                move |ids: &[i32]| {
+                    let lines = repo.lines_by_id(ids)?;
+                    // the brands are usually the next thing asked for so we want them in a
+                    // single batch
+                    brands.expect(lines.iter().flatten().map(|line| line.brand_id));
+                    Ok(lines)
+                }
+            }),
+            lines_by_brand: Loader::new({
+                let repo = repo.clone();
+                let paints_by_line = paints_by_line.clone();
+                move |ids: &[i32]| {
+                    let lines = repo.lines_by_brand(ids)?;
+                    // the paints are usually the next thing asked for so we want them in a
+                    // single batch
+                    paints_by_line.expect(lines.iter().flatten().map(|line| line.id));
+                    Ok(lines)
+                }
+            }),
+            equivalences: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.equivalences_by_paint(ids)
+            }),
+            videos: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.videos_by_id(ids)
+            }),
+            videos_by_paint: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.videos_by_paint(ids)
+            }),
+            usages: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.usages_by_video(ids)
+            }),
+            brands,
+            paints_by_line,
+            repo,
+            index,
+            search_index,
+        }
     }
 
-    pub fn all_videos(&self) -> Vec<&Product> {
-        return vec!(&Product{}, &Product{})
+    pub fn all_paints(&self) -> Result<Vec<Paint>, db::Error> {
+        let paints = self.catalog.load(())?;
+        self.prime_paints(&paints);
+        Ok(paints)
     }
-}
 
-/// the root query type
-pub struct Query;
+    /// the paints that match the filter, in the order the client asked for
+    pub fn find_paints(
+        &self,
+        filter: Option<PaintFilter>,
+        order_by: Option<PaintOrderBy>,
+    ) -> Result<Vec<Paint>, errors::Error> {
+        // the whole catalog is asked for often enough that it's worth sharing
+        if filter.is_none() && order_by.is_none() {
+            return Ok(self.all_paints()?);
+        }
+
+        let query = PaintQuery::new(filter, order_by, self)?;
+        let paints = self.repo.find_paints(&query)?;
+        self.prime_paints(&paints);
+        Ok(paints)
+    }
+
+    pub fn paint(&self, id: i32) -> Result<Option<Paint>, db::Error> {
+        self.paints.load(id)
+    }
+
+    /// the paint an id sent by the client refers to, if there is one
+    pub fn find_paint(&self, id: &ID) -> Result<Option<Paint>, db::Error> {
+        match node::local_id(Kind::Paint, id) {
+            Some(id) => self.paint(id),
+            None => Ok(None),
+        }
+    }
+
+    pub fn paints_for_line(&self, line_id: i32) -> Result<Vec<Paint>, db::Error> {
+        let paints = self.paints_by_line.load(line_id)?;
+        self.prime_paints(&paints);
+        Ok(paints)
+    }
+
+    pub fn create_paint(&self, input: PaintInput) -> Result<Paint, errors::Error> {
+        let paint = input.into_paint(0, self)?;
+        let paint = self.repo.insert_paint(&paint)?;
+
+        self.paints.replace(paint.id, Some(paint.clone()));
+        self.paints_by_line.clear();
+        self.catalog.clear();
+        self.index.invalidate();
+        self.search_index.invalidate();
+        Ok(paint)
+    }
 
-pub struct Product;
+    pub fn update_paint(&self, id: i32, patch: PaintPatch) -> Result<Paint, errors::Error> {
+        let paint = match self.paint(id)? {
+            Some(paint) => patch.apply(paint, self)?,
+            None => return Err(not_found("paint", id)),
+        };
+        if !self.repo.update_paint(&paint)? {
+            return Err(not_found("paint", id));
+        }
 
-#[juniper::object(
-    Context = api::Context,
-)]
-impl Product{
-    pub fn  hello() -> String {
-        "world".to_string()
+        // make sure the rest of the request sees the new version
+        self.paints.replace(id, Some(paint.clone()));
+        self.paints_by_line.clear();
+        self.catalog.clear();
+        self.index.invalidate();
+        self.search_index.invalidate();
+        Ok(paint)
     }
+
+    pub fn delete_paint(&self, id: i32) -> Result<(), errors::Error> {
+        if !self.repo.delete_paint(id)? {
+            return Err(not_found("paint", id));
+        }
+
+        self.paints.replace(id, None);
+        self.paints_by_line.clear();
+        self.catalog.clear();
+        self.index.invalidate();
+        self.search_index.invalidate();
+        // the paint disappears from every chart and video it was in
+        self.equivalences.clear();
+        self.videos_by_paint.clear();
+        self.usages.clear();
+        Ok(())
+    }
+
+    pub fn all_brands(&self) -> Result<Vec<Brand>, db::Error> {
+        let brands = self.repo.all_brands()?;
+        for brand in &brands {
+            self.brands.prime(brand.id, Some(brand.clone()));
+        }
+
+        // the lines are usually the next thing asked for so we want them in a single batch
+        self.lines_by_brand.expect(brands.iter().map(|brand| brand.id));
+
+        Ok(brands)
+    }
+
+    pub fn brand(&self, id: i32) -> Result<Option<Brand>, db::Error> {
+        self.brands.load(id)
+    }
+
+    /// the brand an id sent by the client refers to, if there is one
+    pub fn find_brand(&self, id: &ID) -> Result<Option<Brand>, db::Error> {
+        match node::local_id(Kind::Brand, id) {
+            Some(id) => self.brand(id),
+            None => Ok(None),
+        }
+    }
+
+    pub fn create_brand(&self, input: BrandInput) -> Result<Brand, errors::Error> {
+        let brand = input.into_brand(0, self)?;
+        let brand = self.repo.insert_brand(&brand)?;
+
+        self.brands.replace(brand.id, Some(brand.clone()));
+        self.search_index.invalidate();
+        Ok(brand)
+    }
+
+    pub fn update_brand(&self, id: i32, input: BrandInput) -> Result<Brand, errors::Error> {
+        if self.brand(id)?.is_none() {
+            return Err(not_found("brand", id));
+        }
+        let brand = input.into_brand(id, self)?;
+        if !self.repo.update_brand(&brand)? {
+            return Err(not_found("brand", id));
+        }
+
+        self.brands.replace(id, Some(brand.clone()));
+        // the catalog is sorted by brand name
+        self.catalog.clear();
+        self.search_index.invalidate();
+        Ok(brand)
+    }
+
+    pub fn delete_brand(&self, id: i32) -> Result<(), errors::Error> {
+        // we'd rather make someone clean up a brand's lines than leave paints without one
+        if !self.lines_for_brand(id)?.is_empty() {
+            let mut validation = errors::Validation::new();
+            validation.add("id", "the brand still has product lines");
+            validation.finish()?;
+        }
+        if !self.repo.delete_brand(id)? {
+            return Err(not_found("brand", id));
+        }
+
+        self.brands.replace(id, None);
+        self.search_index.invalidate();
+        Ok(())
+    }
+
+    pub fn line(&self, id: i32) -> Result<Option<ProductLine>, db::Error> {
+        self.lines.load(id)
+    }
+
+    /// the product line an id sent by the client refers to, if there is one
+    pub fn find_line(&self, id: &ID) -> Result<Option<ProductLine>, db::Error> {
+        match node::local_id(Kind::ProductLine, id) {
+            Some(id) => self.line(id),
+            None => Ok(None),
+        }
+    }
+
+    pub fn lines_for_brand(&self, brand_id: i32) -> Result<Vec<ProductLine>, db::Error> {
+        let lines = self.lines_by_brand.load(brand_id)?;
+        for line in &lines {
+            self.lines.prime(line.id, Some(line.clone()));
+        }
+
+        Ok(lines)
+    }
+
+    pub fn create_line(&self, input: ProductLineInput) -> Result<ProductLine, errors::Error> {
+        let line = input.into_line(0, self)?;
+        let line = self.repo.insert_line(&line)?;
+
+        self.lines.replace(line.id, Some(line.clone()));
+        self.lines_by_brand.clear();
+        Ok(line)
+    }
+
+    pub fn update_line(
+        &self,
+        id: i32,
+        patch: ProductLinePatch,
+    ) -> Result<ProductLine, errors::Error> {
+        let line = match self.line(id)? {
+            Some(line) => patch.apply(line, self)?,
+            None => return Err(not_found("product line", id)),
+        };
+        if !self.repo.update_line(&line)? {
+            return Err(not_found("product line", id));
+        }
+
+        self.lines.replace(id, Some(line.clone()));
+        self.lines_by_brand.clear();
+        self.catalog.clear();
+        // the line might have moved to another brand
+        self.index.invalidate();
+        self.search_index.invalidate();
+        Ok(line)
+    }
+
+    pub fn delete_line(&self, id: i32) -> Result<(), errors::Error> {
+        if !self.paints_for_line(id)?.is_empty() {
+            let mut validation = errors::Validation::new();
+            validation.add("id", "the product line still has paints");
+            validation.finish()?;
+        }
+        if !self.repo.delete_line(id)? {
+            return Err(not_found("product line", id));
+        }
+
+        self.lines.replace(id, None);
+        self.lines_by_brand.clear();
+        Ok(())
+    }
+
+    /// remember paints that are about to be asked for so they're loaded in a single batch
+    pub fn expect_paints<I: IntoIterator<Item = i32>>(&self, ids: I) {
+        self.paints.expect(ids);
+    }
+
+    /// fill the cache with paints we've loaded some other way
+    fn prime_paints(&self, paints: &[Paint]) {
+        for paint in paints {
+            self.paints.prime(paint.id, Some(paint.clone()));
+        }
+
+        // the lines are usually the next thing asked for so we want them in a single batch
+        self.lines.expect(paints.iter().map(|paint| paint.line_id));
+    }
+}
+
+/// the error for a record that doesn't exist
+fn not_found(kind: &str, id: i32) -> errors::Error {
+    errors::Error::NotFound(format!("{} {}", kind, id))
 }
\ No newline at end of file
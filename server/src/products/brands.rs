@@ -0,0 +1,161 @@
+// external crates
+use juniper::{FieldResult, ID};
+
+use super::{Client, Paint};
+use crate::node::{self, Kind, Node};
+use crate::{api, errors};
+
+/// a company that makes paint (ie, Citadel or Vallejo)
+#[derive(Clone, Debug)]
+pub struct Brand {
+    pub id: i32,
+    pub name: String,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl Brand {
+    /// the globally unique identifier of the brand
+    fn id(&self) -> ID {
+        node::global_id(Kind::Brand, self.id)
+    }
+
+    /// the name the brand sells its paints under
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the ranges of paint the brand sells
+    fn lines(&self, context: &api::Context) -> FieldResult<Vec<ProductLine>> {
+        Ok(context.products.lines_for_brand(self.id)?)
+    }
+}
+
+connection!(BrandConnection, BrandEdge, Brand);
+
+/// a range of paints sold by a brand (ie, Citadel's Base or Vallejo's Model Color)
+#[derive(Clone, Debug)]
+pub struct ProductLine {
+    pub id: i32,
+    pub brand_id: i32,
+    pub name: String,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl ProductLine {
+    /// the globally unique identifier of the product line
+    fn id(&self) -> ID {
+        node::global_id(Kind::ProductLine, self.id)
+    }
+
+    /// the name of the range
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the brand that sells the range
+    fn brand(&self, context: &api::Context) -> FieldResult<Brand> {
+        Ok(context.products.brand(self.brand_id)?.ok_or("missing brand")?)
+    }
+
+    /// the paints in the range
+    fn paints(&self, context: &api::Context) -> FieldResult<Vec<Paint>> {
+        Ok(context.products.paints_for_line(self.id)?)
+    }
+}
+
+/// the details of a brand
+#[derive(juniper::GraphQLInputObject)]
+pub struct BrandInput {
+    pub name: String,
+}
+
+impl BrandInput {
+    /// check the input over and turn it into a brand that hasn't been stored yet
+    pub(super) fn into_brand(self, id: i32, client: &Client) -> Result<Brand, errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("name", &self.name);
+
+        // two brands with the same name would be impossible to tell apart
+        let name = self.name.trim();
+        let taken = client
+            .all_brands()?
+            .iter()
+            .any(|brand| brand.id != id && brand.name.eq_ignore_ascii_case(name));
+        if taken {
+            validation.add("name", "is already taken by another brand");
+        }
+        validation.finish()?;
+
+        Ok(Brand {
+            id,
+            name: name.to_string(),
+        })
+    }
+}
+
+/// everything needed to add a product line to a brand
+#[derive(juniper::GraphQLInputObject)]
+pub struct ProductLineInput {
+    /// the id of the brand that sells the range
+    pub brand: ID,
+    pub name: String,
+}
+
+impl ProductLineInput {
+    /// check the input over and turn it into a product line that hasn't been stored yet
+    pub(super) fn into_line(self, id: i32, client: &Client) -> Result<ProductLine, errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("name", &self.name);
+
+        let name = self.name.trim();
+        let brand = client.find_brand(&self.brand)?;
+        match &brand {
+            Some(brand) => {
+                // a brand can't sell two ranges with the same name
+                let taken = client
+                    .lines_for_brand(brand.id)?
+                    .iter()
+                    .any(|line| line.id != id && line.name.eq_ignore_ascii_case(name));
+                if taken {
+                    validation.add("name", "is already taken by another line from the brand");
+                }
+            }
+            None => validation.add("brand", "does not exist"),
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the brand was missing
+        Ok(ProductLine {
+            id,
+            brand_id: brand.unwrap().id,
+            name: name.to_string(),
+        })
+    }
+}
+
+/// the changes to make to an existing product line. fields that are left out are unchanged
+#[derive(juniper::GraphQLInputObject)]
+pub struct ProductLinePatch {
+    pub brand: Option<ID>,
+    pub name: Option<String>,
+}
+
+impl ProductLinePatch {
+    /// apply the changes to the line, validating the result as if it were brand new
+    pub(super) fn apply(self, line: ProductLine, client: &Client) -> Result<ProductLine, errors::Error> {
+        let input = ProductLineInput {
+            brand: self
+                .brand
+                .unwrap_or_else(|| node::global_id(Kind::Brand, line.brand_id)),
+            name: self.name.unwrap_or(line.name),
+        };
+
+        input.into_line(line.id, client)
+    }
+}
@@ -0,0 +1,279 @@
+// external crates
+use juniper::ID;
+
+use super::{not_found, Client, Color, Paint};
+use crate::{api, db, errors};
+
+/// the most computed matches we'll suggest from a brand that was asked for by name
+const COMPUTED_PER_BRAND: usize = 3;
+
+/// the most paints we'll hand back when searching for a color
+const MAX_NEAR_COLOR: i32 = 100;
+
+/// how different two colors can look (in CIEDE2000) before we stop suggesting one for
+/// the other
+const MAX_COMPUTED_DISTANCE: f64 = 10.0;
+
+/// how we came to believe that two paints are interchangeable
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum EquivalenceSource {
+    /// the manufacturer publishes the pairing in a conversion chart
+    Official,
+    /// painters have tried the pairing and vouch for it
+    Community,
+    /// the colors are close but nobody has vetted the pairing
+    Computed,
+}
+
+impl EquivalenceSource {
+    /// the name we use for the source when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EquivalenceSource::Official => "official",
+            EquivalenceSource::Community => "community",
+            EquivalenceSource::Computed => "computed",
+        }
+    }
+}
+
+/// a curated pairing of two paints, seen from the side of paint_id
+#[derive(Clone, Debug)]
+pub struct Equivalence {
+    pub paint_id: i32,
+    pub equivalent_id: i32,
+    pub source: EquivalenceSource,
+}
+
+/// a paint that can stand in for another one
+pub struct PaintEquivalent {
+    pub paint: Paint,
+    pub source: EquivalenceSource,
+    pub color_distance: f64,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl PaintEquivalent {
+    /// the paint that can be used instead
+    fn paint(&self) -> &Paint {
+        &self.paint
+    }
+
+    /// where the pairing comes from
+    fn source(&self) -> EquivalenceSource {
+        self.source
+    }
+
+    /// how different the two colors look (the CIEDE2000 delta-E). smaller is closer
+    fn colorDistance(&self) -> f64 {
+        self.color_distance
+    }
+}
+
+/// a paint that came up when searching for a color
+pub struct PaintMatch {
+    pub paint: Paint,
+    pub delta_e: f64,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl PaintMatch {
+    /// the paint whose color is close to the one searched for
+    fn paint(&self) -> &Paint {
+        &self.paint
+    }
+
+    /// how different the paint looks from the color searched for (the CIEDE2000 delta-E)
+    fn deltaE(&self) -> f64 {
+        self.delta_e
+    }
+}
+
+/// a pairing of two paints to record
+#[derive(juniper::GraphQLInputObject)]
+pub struct EquivalenceInput {
+    pub paint: ID,
+    pub equivalent: ID,
+    /// where the pairing comes from. computed pairings can't be stored
+    pub source: EquivalenceSource,
+}
+
+impl Client {
+    /// the paints that can stand in for the given one, optionally only from a single
+    /// brand. curated pairings come first followed by the closest colors we could find
+    pub fn equivalents(&self, paint: &Paint, brand: Option<&ID>) -> Result<Vec<PaintEquivalent>, db::Error> {
+        let brand_id = match brand {
+            Some(id) => match self.find_brand(id)? {
+                Some(brand) => Some(brand.id),
+                // there can't be any equivalents from a brand that doesn't exist
+                None => return Ok(Vec::new()),
+            },
+            None => None,
+        };
+
+        let own_brand = self.brand_of(paint)?;
+        let wanted = |candidate_brand: Option<i32>| match brand_id {
+            Some(brand_id) => candidate_brand == Some(brand_id),
+            None => candidate_brand != own_brand,
+        };
+
+        // start with the pairings someone has vouched for
+        let equivalences = self.equivalences.load(paint.id)?;
+        self.paints.expect(equivalences.iter().map(|equivalence| equivalence.equivalent_id));
+
+        let mut equivalents = Vec::new();
+        for equivalence in equivalences {
+            if let Some(candidate) = self.paint(equivalence.equivalent_id)? {
+                if wanted(self.brand_of(&candidate)?) {
+                    equivalents.push(PaintEquivalent {
+                        color_distance: paint.color.distance(candidate.color),
+                        paint: candidate,
+                        source: equivalence.source,
+                    });
+                }
+            }
+        }
+        equivalents.sort_by(|a, b| {
+            (a.source != EquivalenceSource::Official)
+                .cmp(&(b.source != EquivalenceSource::Official))
+                .then(a.color_distance.partial_cmp(&b.color_distance).unwrap())
+        });
+
+        // fill in with the closest colors that have the same finish. we only want the single
+        // closest match from each brand unless we were asked about one brand in particular
+        let (brand_ids, limit) = match brand_id {
+            Some(brand_id) => (vec![brand_id], COMPUTED_PER_BRAND),
+            None => (
+                self.all_brands()?
+                    .into_iter()
+                    .map(|brand| brand.id)
+                    .filter(|id| Some(*id) != own_brand)
+                    .collect(),
+                1,
+            ),
+        };
+        let mut computed = Vec::new();
+        for brand_id in brand_ids {
+            let matches = self.index.nearest(&*self.repo, paint.color, limit, |entry| {
+                entry.brand_id == brand_id
+                    && entry.finish == paint.finish
+                    && entry.paint_id != paint.id
+                    && !equivalents.iter().any(|known| known.paint.id == entry.paint_id)
+            })?;
+            computed.extend(
+                matches
+                    .into_iter()
+                    .filter(|(_, distance)| *distance <= MAX_COMPUTED_DISTANCE),
+            );
+        }
+        computed.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        self.paints.expect(computed.iter().map(|(entry, _)| entry.paint_id));
+        for (entry, distance) in computed {
+            if let Some(candidate) = self.paint(entry.paint_id)? {
+                equivalents.push(PaintEquivalent {
+                    paint: candidate,
+                    source: EquivalenceSource::Computed,
+                    color_distance: distance,
+                });
+            }
+        }
+
+        Ok(equivalents)
+    }
+
+    /// the paints that look the most like the given color, closest first, optionally only
+    /// from some of the brands
+    pub fn paints_near_color(
+        &self,
+        hex: &str,
+        limit: i32,
+        brands: Option<Vec<ID>>,
+    ) -> Result<Vec<PaintMatch>, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let color = Color::from_hex(hex);
+        if color.is_none() {
+            validation.add("hex", "must be a hex color like #9a1115");
+        }
+        if !(1..=MAX_NEAR_COLOR).contains(&limit) {
+            validation.add("limit", &format!("must be between 1 and {}", MAX_NEAR_COLOR));
+        }
+        let mut brand_ids = Vec::new();
+        for id in brands.iter().flatten() {
+            match self.find_brand(id)? {
+                Some(brand) => brand_ids.push(brand.id),
+                None => validation.add("brands", &format!("brand {} does not exist", &**id)),
+            }
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the color didn't parse
+        let nearest = self.index.nearest(&*self.repo, color.unwrap(), limit as usize, |entry| {
+            brands.is_none() || brand_ids.contains(&entry.brand_id)
+        })?;
+        self.paints.expect(nearest.iter().map(|(entry, _)| entry.paint_id));
+
+        let mut matches = Vec::new();
+        for (entry, delta_e) in nearest {
+            // the index can be a moment behind a paint that was just removed
+            if let Some(paint) = self.paint(entry.paint_id)? {
+                matches.push(PaintMatch { paint, delta_e });
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// record that two paints can stand in for each other
+    pub fn add_equivalence(&self, input: EquivalenceInput) -> Result<Paint, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let paint = self.find_paint(&input.paint)?;
+        if paint.is_none() {
+            validation.add("paint", "does not exist");
+        }
+        let equivalent = self.find_paint(&input.equivalent)?;
+        if equivalent.is_none() {
+            validation.add("equivalent", "does not exist");
+        }
+        if input.paint == input.equivalent {
+            validation.add("equivalent", "must be a different paint");
+        }
+        if input.source == EquivalenceSource::Computed {
+            validation.add("source", "computed equivalents can't be stored");
+        }
+        validation.finish()?;
+
+        // the validation would have failed if either paint was missing
+        let (paint, equivalent) = (paint.unwrap(), equivalent.unwrap());
+        self.repo.insert_equivalence(&Equivalence {
+            paint_id: paint.id,
+            equivalent_id: equivalent.id,
+            source: input.source,
+        })?;
+
+        self.equivalences.clear();
+        Ok(paint)
+    }
+
+    /// forget that two paints can stand in for each other
+    pub fn remove_equivalence(&self, paint_id: i32, equivalent_id: i32) -> Result<Paint, errors::Error> {
+        let paint = self.paint(paint_id)?.ok_or_else(|| not_found("paint", paint_id))?;
+        if !self.repo.delete_equivalence(paint_id, equivalent_id)? {
+            return Err(errors::Error::NotFound(format!(
+                "an equivalence between paint {} and paint {}",
+                paint_id, equivalent_id
+            )));
+        }
+
+        self.equivalences.clear();
+        Ok(paint)
+    }
+
+    /// the id of the brand that makes the paint
+    fn brand_of(&self, paint: &Paint) -> Result<Option<i32>, db::Error> {
+        Ok(self.line(paint.line_id)?.map(|line| line.brand_id))
+    }
+}
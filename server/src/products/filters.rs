@@ -0,0 +1,166 @@
+// external crates
+use juniper::ID;
+
+use super::{Client, Finish};
+use crate::errors;
+
+/// narrows down the paints in the catalog. every condition that's given has to hold
+#[derive(juniper::GraphQLInputObject, Default)]
+pub struct PaintFilter {
+    /// only paints made by the brand
+    pub brand: Option<ID>,
+    /// only paints sold in the product line
+    pub product_line: Option<ID>,
+    pub finish: Option<Finish>,
+    /// only paints whose hue (in degrees) falls in the range. a range whose minimum is
+    /// bigger than its maximum wraps around through 0 (ie, 330 to 30 for reds)
+    pub hue: Option<FloatRange>,
+    /// only paints whose lightness (from 0 to 100) falls in the range
+    pub lightness: Option<FloatRange>,
+    pub discontinued: Option<bool>,
+    /// only paints that are (or aren't) used in at least one product video
+    pub has_video: Option<bool>,
+}
+
+/// an inclusive range of numbers. a missing end leaves that side open
+#[derive(juniper::GraphQLInputObject)]
+pub struct FloatRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// the order to list paints in
+#[derive(juniper::GraphQLInputObject)]
+pub struct PaintOrderBy {
+    pub field: PaintSortField,
+    /// defaults to ascending
+    pub direction: Option<SortDirection>,
+}
+
+/// the things paints can be sorted by
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum PaintSortField {
+    Name,
+    Brand,
+    Hue,
+    Lightness,
+    /// the day the paint was released. paints we don't have a date for always come last
+    ReleaseDate,
+}
+
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// a filter and order for paints in terms storage understands. leaving everything out
+/// lists the whole catalog by brand, line and then name
+#[derive(Clone, Debug, Default)]
+pub struct PaintQuery {
+    pub brand_id: Option<i32>,
+    pub line_id: Option<i32>,
+    pub finish: Option<Finish>,
+    pub hue: Option<(f64, f64)>,
+    pub lightness: Option<(f64, f64)>,
+    pub discontinued: Option<bool>,
+    pub has_video: Option<bool>,
+    pub order: Option<(PaintSortField, SortDirection)>,
+}
+
+impl PaintQuery {
+    /// whether a paint with the given hue falls in the hue range, if there is one
+    pub fn matches_hue(&self, hue: f64) -> bool {
+        match self.hue {
+            Some((min, max)) if min <= max => min <= hue && hue <= max,
+            // the range wraps around through 0
+            Some((min, max)) => hue >= min || hue <= max,
+            None => true,
+        }
+    }
+
+    /// whether a paint with the given lightness falls in the lightness range, if there is one
+    pub fn matches_lightness(&self, lightness: f64) -> bool {
+        match self.lightness {
+            Some((min, max)) => min <= lightness && lightness <= max,
+            None => true,
+        }
+    }
+
+    /// check a filter and order sent by the client over and turn them into a query
+    pub(super) fn new(
+        filter: Option<PaintFilter>,
+        order_by: Option<PaintOrderBy>,
+        client: &Client,
+    ) -> Result<PaintQuery, errors::Error> {
+        let filter = filter.unwrap_or_default();
+        let mut validation = errors::Validation::new();
+
+        let mut brand_id = None;
+        if let Some(brand) = &filter.brand {
+            match client.find_brand(brand)? {
+                Some(brand) => brand_id = Some(brand.id),
+                None => validation.add("filter.brand", "does not exist"),
+            }
+        }
+        let mut line_id = None;
+        if let Some(line) = &filter.product_line {
+            match client.find_line(line)? {
+                Some(line) => line_id = Some(line.id),
+                None => validation.add("filter.productLine", "does not exist"),
+            }
+        }
+        let hue = range("filter.hue", filter.hue, 0.0, 360.0, &mut validation);
+        let lightness = range("filter.lightness", filter.lightness, 0.0, 100.0, &mut validation);
+        if let (Some(min), Some(max)) = lightness {
+            if min > max {
+                validation.add("filter.lightness", "the minimum can't be bigger than the maximum");
+            }
+        }
+        validation.finish()?;
+
+        Ok(PaintQuery {
+            brand_id,
+            line_id,
+            finish: filter.finish,
+            hue: bounds(hue, 0.0, 360.0),
+            lightness: bounds(lightness, 0.0, 100.0),
+            discontinued: filter.discontinued,
+            has_video: filter.has_video,
+            order: order_by.map(|order| {
+                (order.field, order.direction.unwrap_or(SortDirection::Asc))
+            }),
+        })
+    }
+}
+
+/// the ends of a range, checking that they fall within the limits
+fn range(
+    field: &str,
+    range: Option<FloatRange>,
+    lowest: f64,
+    highest: f64,
+    validation: &mut errors::Validation,
+) -> (Option<f64>, Option<f64>) {
+    let range = match range {
+        Some(range) => range,
+        None => return (None, None),
+    };
+
+    for end in range.min.iter().chain(range.max.iter()) {
+        if *end < lowest || *end > highest {
+            validation.add(field, &format!("must be between {} and {}", lowest, highest));
+            break;
+        }
+    }
+
+    (range.min, range.max)
+}
+
+/// fill in the open ends of a range, leaving it out entirely if both ends are open
+fn bounds(range: (Option<f64>, Option<f64>), lowest: f64, highest: f64) -> Option<(f64, f64)> {
+    match range {
+        (None, None) => None,
+        (min, max) => Some((min.unwrap_or(lowest), max.unwrap_or(highest))),
+    }
+}
@@ -0,0 +1,296 @@
+// external crates
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::{Color, Finish, Paint, PaintQuery, Repository};
+use crate::colors::Lab;
+use crate::db;
+use crate::kdtree::KdTree;
+
+/// how many candidates we rank for each match asked for at first. the more of them there
+/// are the closer the worst of the matches is likely to be, which keeps the second look small
+const OVERSAMPLE: usize = 4;
+const MIN_CANDIDATES: usize = 16;
+
+/// the most chroma any color we can store has in CIELAB, which is sRGB's pure blue at
+/// just under 134
+const MAX_CHROMA: f64 = 134.0;
+
+/// a spatial index over the colors of every paint in the catalog so nearest color lookups
+/// don't have to scan the whole catalog
+#[derive(Clone, Default)]
+pub struct ColorIndex {
+    tree: Shared<KdTree<Entry>>,
+}
+
+/// what the index knows about each paint, which is enough to filter the matches without
+/// going back to storage
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub paint_id: i32,
+    pub brand_id: i32,
+    pub finish: Finish,
+    pub color: Color,
+}
+
+impl ColorIndex {
+    pub fn new() -> ColorIndex {
+        ColorIndex::default()
+    }
+
+    /// the up to `limit` paints that look the most like the given color and pass the
+    /// filter, closest first, along with their CIEDE2000 distance from it
+    pub fn nearest<F>(
+        &self,
+        repo: &dyn Repository,
+        color: Color,
+        limit: usize,
+        accept: F,
+    ) -> Result<Vec<(Entry, f64)>, db::Error>
+    where
+        F: Fn(&Entry) -> bool,
+    {
+        let tree = self.tree.get(|| build(repo))?;
+        let target = color.to_lab();
+        let rank = |found: Vec<(&Entry, f64)>| {
+            let mut matches: Vec<(Entry, f64)> = found
+                .into_iter()
+                .map(|(entry, _)| (entry.clone(), target.delta_e(entry.color.to_lab())))
+                .collect();
+            matches.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            matches.truncate(limit);
+            matches
+        };
+
+        // the tree only knows about straight line distances in CIELAB, which don't always
+        // agree with CIEDE2000. the closest paints in a straight line tell us how far off
+        // the matches can be at worst, and anything that could beat them is close enough
+        // in a straight line for a second look to find it
+        let candidates = (limit * OVERSAMPLE).max(MIN_CANDIDATES);
+        let found = tree.nearest(point(target), candidates, &accept);
+        if found.len() < candidates {
+            // there's nothing else that passes the filter
+            return Ok(rank(found));
+        }
+        let matches = rank(found);
+        let worst = match matches.last() {
+            Some((_, worst)) => *worst,
+            None => return Ok(matches),
+        };
+
+        let radius = reach(target, worst);
+        Ok(rank(tree.within(point(target), radius, &accept)))
+    }
+
+    /// forget the current tree so the next lookup sees the latest catalog
+    pub fn invalidate(&self) {
+        self.tree.invalidate();
+    }
+}
+
+/// an index that's shared between every request and thrown away whenever the data it
+/// covers changes, to be rebuilt by the next lookup
+pub(super) struct Shared<T> {
+    slot: Arc<RwLock<Option<Arc<T>>>>,
+}
+
+impl<T> Shared<T> {
+    /// the current index, building it first if the data changed since the last one
+    pub fn get<F>(&self, build: F) -> Result<Arc<T>, db::Error>
+    where
+        F: FnOnce() -> Result<T, db::Error>,
+    {
+        if let Some(index) = &*self.slot.read().unwrap_or_else(|err| err.into_inner()) {
+            return Ok(index.clone());
+        }
+
+        // hold onto the write lock while we build so concurrent lookups don't all do it too
+        let mut slot = self.slot.write().unwrap_or_else(|err| err.into_inner());
+        if let Some(index) = &*slot {
+            return Ok(index.clone());
+        }
+        let index = Arc::new(build()?);
+        *slot = Some(index.clone());
+
+        Ok(index)
+    }
+
+    /// forget the current index so the next lookup sees the latest data
+    pub fn invalidate(&self) {
+        *self.slot.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+// deriving these would needlessly require them of the index itself
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Shared<T> {
+        Shared {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T> Default for Shared<T> {
+    fn default() -> Shared<T> {
+        Shared {
+            slot: Arc::new(RwLock::new(None)),
+        }
+    }
+}
+
+/// build a tree over every paint in the catalog
+fn build(repo: &dyn Repository) -> Result<KdTree<Entry>, db::Error> {
+    let paints = repo.find_paints(&PaintQuery::default())?;
+
+    // we need the product lines to figure out who makes each paint
+    let mut line_ids: Vec<i32> = paints.iter().map(|paint| paint.line_id).collect();
+    line_ids.sort();
+    line_ids.dedup();
+    let brands: HashMap<i32, i32> = repo
+        .lines_by_id(&line_ids)?
+        .into_iter()
+        .flatten()
+        .map(|line| (line.id, line.brand_id))
+        .collect();
+
+    Ok(KdTree::build(
+        paints
+            .into_iter()
+            .filter_map(|paint: Paint| {
+                let brand_id = *brands.get(&paint.line_id)?;
+                Some((
+                    point(paint.color.to_lab()),
+                    Entry {
+                        paint_id: paint.id,
+                        brand_id,
+                        finish: paint.finish,
+                        color: paint.color,
+                    },
+                ))
+            })
+            .collect(),
+    ))
+}
+
+/// how far away in a straight line a color can be while still being no more than `worst`
+/// from the target by CIEDE2000. CIEDE2000 divides the lightness, chroma and hue
+/// differences by weights that grow with the colors' mean chroma, the biggest of which is
+/// 1 + 0.045 C (stretching the a axis adds less than 7 to C, and the lightness weight never
+/// passes 1.75). the term that rotates blues takes away at most sin(60°) of what's left, so
+/// the straight line distance is at most the weight over √(1 - sin(60°)), or under 2.74
+/// times the weight, times the CIEDE2000 difference
+fn reach(target: Lab, worst: f64) -> f64 {
+    let chroma = target.a.hypot(target.b);
+    let spread = |mean_chroma: f64| 2.74 * (1.0 + 0.045 * (mean_chroma + 7.0)) * worst;
+
+    // the other color has no more chroma than any color can
+    let anywhere = spread((chroma + MAX_CHROMA) / 2.0);
+
+    // or than the target plus however far away it is, which for small differences is
+    // a lot closer
+    let slope = 2.74 * 0.045 / 2.0 * worst;
+    if slope < 1.0 {
+        anywhere.min(spread(chroma) / (1.0 - slope))
+    } else {
+        anywhere
+    }
+}
+
+fn point(lab: Lab) -> [f64; 3] {
+    [lab.l, lab.a, lab.b]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory;
+
+    /// a cheap deterministic source of colors
+    struct Colors(u64);
+
+    impl Colors {
+        fn next(&mut self) -> Color {
+            let mut channel = || {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 % 256) as u8
+            };
+
+            Color {
+                red: channel(),
+                green: channel(),
+                blue: channel(),
+            }
+        }
+    }
+
+    /// check the index against going through every paint in the store for each target
+    fn matches_brute_force(store: &memory::Store, targets: &[Color]) {
+        let index = ColorIndex::new();
+        let paints = store.find_paints(&PaintQuery::default()).unwrap();
+
+        for &target in targets {
+            let mut expected: Vec<f64> = paints
+                .iter()
+                .map(|paint| target.to_lab().delta_e(paint.color.to_lab()))
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            // the distances are what matter since paints the same distance away can come
+            // back in either order
+            for &limit in &[1, 3, 10] {
+                let found: Vec<f64> = index
+                    .nearest(store, target, limit, |_| true)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(_, delta_e)| delta_e)
+                    .collect();
+                let closest = &expected[..limit.min(expected.len())];
+                assert_eq!(found, closest, "{:?}", target);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_closest_fixtures() {
+        let store = memory::Store::seeded();
+
+        // a coarse grid over every color, along with the paints' own colors
+        let mut targets: Vec<Color> = store
+            .read()
+            .paints
+            .iter()
+            .map(|paint| paint.color)
+            .collect();
+        for &red in &[0, 64, 128, 192, 255] {
+            for &green in &[0, 64, 128, 192, 255] {
+                for &blue in &[0, 64, 128, 192, 255] {
+                    targets.push(Color { red, green, blue });
+                }
+            }
+        }
+
+        matches_brute_force(&store, &targets);
+    }
+
+    #[test]
+    fn finds_the_closest_in_a_crowded_catalog() {
+        // with enough paints around, the closest in a straight line often aren't the
+        // closest by CIEDE2000
+        let store = memory::Store::seeded();
+        let mut colors = Colors(0x2545_f491_4f6c_dd1d);
+        let mut paints = store.read().paints.clone();
+        for id in 100..2100 {
+            paints.push(Paint {
+                id,
+                color: colors.next(),
+                ..paints[0].clone()
+            });
+        }
+        store.write().paints = paints;
+
+        let targets: Vec<Color> = (0..200).map(|_| colors.next()).collect();
+        matches_brute_force(&store, &targets);
+    }
+}
@@ -0,0 +1,402 @@
+// external crates
+use std::cmp::Ordering;
+
+use super::{
+    Brand, Equivalence, Paint, PaintQuery, PaintSortField, PaintUsage, ProductLine, ProductVideo,
+    Repository, SortDirection,
+};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn find_paints(&self, query: &PaintQuery) -> Result<Vec<Paint>, db::Error> {
+        let tables = self.read();
+        let line = |paint: &Paint| tables.product_lines.iter().find(|line| line.id == paint.line_id);
+        let brand_name = |paint: &Paint| {
+            line(paint)
+                .and_then(|line| tables.brands.iter().find(|brand| brand.id == line.brand_id))
+                .map(|brand| brand.name.clone())
+        };
+        let has_video = |paint: &Paint| tables.paint_usages.iter().any(|usage| usage.paint_id == paint.id);
+
+        let mut paints: Vec<Paint> = tables
+            .paints
+            .iter()
+            .filter(|paint| {
+                let lab = paint.color.to_lab();
+                (query.brand_id.is_none() || line(paint).map(|line| line.brand_id) == query.brand_id)
+                    && (query.line_id.is_none() || Some(paint.line_id) == query.line_id)
+                    && (query.finish.is_none() || Some(paint.finish) == query.finish)
+                    && query.matches_hue(lab.hue())
+                    && query.matches_lightness(lab.l)
+                    && (query.discontinued.is_none() || Some(paint.discontinued) == query.discontinued)
+                    && (query.has_video.is_none() || Some(has_video(paint)) == query.has_video)
+            })
+            .cloned()
+            .collect();
+
+        // match the order the database hands paints back in
+        let catalog_key = |paint: &Paint| {
+            (
+                brand_name(paint),
+                line(paint).map(|line| line.name.clone()),
+                paint.name.clone(),
+                paint.id,
+            )
+        };
+        paints.sort_by(|a, b| {
+            let ordering = match query.order {
+                Some((field, direction)) => {
+                    let ordering = match field {
+                        PaintSortField::Name => a.name.cmp(&b.name),
+                        PaintSortField::Brand => brand_name(a).cmp(&brand_name(b)),
+                        PaintSortField::Hue => compare(a.color.to_lab().hue(), b.color.to_lab().hue()),
+                        PaintSortField::Lightness => compare(a.color.to_lab().l, b.color.to_lab().l),
+                        PaintSortField::ReleaseDate => a.released_on.cmp(&b.released_on),
+                    };
+                    let ordering = match direction {
+                        SortDirection::Asc => ordering,
+                        SortDirection::Desc => ordering.reverse(),
+                    };
+
+                    // paints without a release date come last whichever way they're sorted
+                    if field == PaintSortField::ReleaseDate {
+                        a.released_on.is_none().cmp(&b.released_on.is_none()).then(ordering)
+                    } else {
+                        ordering
+                    }
+                }
+                None => Ordering::Equal,
+            };
+
+            ordering.then_with(|| catalog_key(a).cmp(&catalog_key(b)))
+        });
+
+        Ok(paints)
+    }
+
+    fn paints_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Paint>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.paints.iter().find(|paint| paint.id == *id).cloned())
+            .collect())
+    }
+
+    fn paints_by_line(&self, line_ids: &[i32]) -> Result<Vec<Vec<Paint>>, db::Error> {
+        let tables = self.read();
+
+        Ok(line_ids
+            .iter()
+            .map(|line_id| {
+                let mut paints: Vec<Paint> = tables
+                    .paints
+                    .iter()
+                    .filter(|paint| paint.line_id == *line_id)
+                    .cloned()
+                    .collect();
+                paints.sort_by(|a, b| a.name.cmp(&b.name));
+                paints
+            })
+            .collect())
+    }
+
+    fn insert_paint(&self, paint: &Paint) -> Result<Paint, db::Error> {
+        let mut tables = self.write();
+
+        let paint = Paint {
+            id: memory::next_id(tables.paints.iter().map(|paint| paint.id)),
+            ..paint.clone()
+        };
+        tables.paints.push(paint.clone());
+
+        Ok(paint)
+    }
+
+    fn update_paint(&self, paint: &Paint) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().paints, paint, |paint| paint.id))
+    }
+
+    fn delete_paint(&self, id: i32) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        // the database cascades the delete to the paint's equivalences, usages, prices,
+        // alerts and the inventories, wishlists and recipe steps it's in. notifications
+        // about it are kept
+        tables
+            .paint_equivalents
+            .retain(|equivalence| equivalence.paint_id != id && equivalence.equivalent_id != id);
+        tables.paint_usages.retain(|usage| usage.paint_id != id);
+        tables.inventory_items.retain(|item| item.paint_id != id);
+        tables.wishlist_items.retain(|item| item.paint_id != id);
+        tables.price_observations.retain(|observation| observation.paint_id != id);
+        tables.price_alerts.retain(|alert| alert.paint_id != id);
+        for notification in &mut tables.notifications {
+            if notification.paint_id == Some(id) {
+                notification.paint_id = None;
+            }
+        }
+        for step in &mut tables.recipe_steps {
+            step.paint_ids.retain(|paint_id| *paint_id != id);
+        }
+
+        Ok(memory::remove(&mut tables.paints, id, |paint| paint.id))
+    }
+
+    fn all_brands(&self) -> Result<Vec<Brand>, db::Error> {
+        let mut brands = self.read().brands.clone();
+        brands.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(brands)
+    }
+
+    fn brands_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Brand>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.brands.iter().find(|brand| brand.id == *id).cloned())
+            .collect())
+    }
+
+    fn insert_brand(&self, brand: &Brand) -> Result<Brand, db::Error> {
+        let mut tables = self.write();
+
+        let brand = Brand {
+            id: memory::next_id(tables.brands.iter().map(|brand| brand.id)),
+            ..brand.clone()
+        };
+        tables.brands.push(brand.clone());
+
+        Ok(brand)
+    }
+
+    fn update_brand(&self, brand: &Brand) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().brands, brand, |brand| brand.id))
+    }
+
+    fn delete_brand(&self, id: i32) -> Result<bool, db::Error> {
+        Ok(memory::remove(&mut self.write().brands, id, |brand| brand.id))
+    }
+
+    fn lines_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductLine>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.product_lines.iter().find(|line| line.id == *id).cloned())
+            .collect())
+    }
+
+    fn lines_by_brand(&self, brand_ids: &[i32]) -> Result<Vec<Vec<ProductLine>>, db::Error> {
+        let tables = self.read();
+
+        Ok(brand_ids
+            .iter()
+            .map(|brand_id| {
+                let mut lines: Vec<ProductLine> = tables
+                    .product_lines
+                    .iter()
+                    .filter(|line| line.brand_id == *brand_id)
+                    .cloned()
+                    .collect();
+                lines.sort_by(|a, b| a.name.cmp(&b.name));
+                lines
+            })
+            .collect())
+    }
+
+    fn insert_line(&self, line: &ProductLine) -> Result<ProductLine, db::Error> {
+        let mut tables = self.write();
+
+        let line = ProductLine {
+            id: memory::next_id(tables.product_lines.iter().map(|line| line.id)),
+            ..line.clone()
+        };
+        tables.product_lines.push(line.clone());
+
+        Ok(line)
+    }
+
+    fn update_line(&self, line: &ProductLine) -> Result<bool, db::Error> {
+        Ok(memory::replace(&mut self.write().product_lines, line, |line| line.id))
+    }
+
+    fn delete_line(&self, id: i32) -> Result<bool, db::Error> {
+        Ok(memory::remove(&mut self.write().product_lines, id, |line| line.id))
+    }
+
+    fn equivalences_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<Equivalence>>, db::Error> {
+        let tables = self.read();
+
+        Ok(paint_ids
+            .iter()
+            .map(|paint_id| {
+                tables
+                    .paint_equivalents
+                    .iter()
+                    .filter_map(|equivalence| {
+                        if equivalence.paint_id == *paint_id {
+                            Some(equivalence.clone())
+                        } else if equivalence.equivalent_id == *paint_id {
+                            Some(Equivalence {
+                                paint_id: equivalence.equivalent_id,
+                                equivalent_id: equivalence.paint_id,
+                                source: equivalence.source,
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn insert_equivalence(&self, equivalence: &Equivalence) -> Result<(), db::Error> {
+        let mut tables = self.write();
+
+        // match the database by storing each pair once, lowest id first
+        let (paint_id, equivalent_id) = ordered(equivalence.paint_id, equivalence.equivalent_id);
+        tables
+            .paint_equivalents
+            .retain(|known| (known.paint_id, known.equivalent_id) != (paint_id, equivalent_id));
+        tables.paint_equivalents.push(Equivalence {
+            paint_id,
+            equivalent_id,
+            source: equivalence.source,
+        });
+
+        Ok(())
+    }
+
+    fn delete_equivalence(&self, paint_id: i32, equivalent_id: i32) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        let pair = ordered(paint_id, equivalent_id);
+        let before = tables.paint_equivalents.len();
+        tables
+            .paint_equivalents
+            .retain(|known| (known.paint_id, known.equivalent_id) != pair);
+
+        Ok(tables.paint_equivalents.len() != before)
+    }
+
+    fn all_videos(&self) -> Result<Vec<ProductVideo>, db::Error> {
+        let mut videos = self.read().product_videos.clone();
+        videos.sort_by(newest_first);
+
+        Ok(videos)
+    }
+
+    fn videos_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductVideo>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.product_videos.iter().find(|video| video.id == *id).cloned())
+            .collect())
+    }
+
+    fn videos_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<ProductVideo>>, db::Error> {
+        let tables = self.read();
+
+        Ok(paint_ids
+            .iter()
+            .map(|paint_id| {
+                let mut videos: Vec<ProductVideo> = tables
+                    .product_videos
+                    .iter()
+                    .filter(|video| {
+                        tables
+                            .paint_usages
+                            .iter()
+                            .any(|usage| usage.video_id == video.id && usage.paint_id == *paint_id)
+                    })
+                    .cloned()
+                    .collect();
+                videos.sort_by(newest_first);
+                videos
+            })
+            .collect())
+    }
+
+    fn usages_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<PaintUsage>>, db::Error> {
+        let tables = self.read();
+
+        Ok(video_ids
+            .iter()
+            .map(|video_id| {
+                let mut usages: Vec<PaintUsage> = tables
+                    .paint_usages
+                    .iter()
+                    .filter(|usage| usage.video_id == *video_id)
+                    .cloned()
+                    .collect();
+                usages.sort_by_key(|usage| (usage.start_seconds, usage.paint_id));
+                usages
+            })
+            .collect())
+    }
+
+    fn insert_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<ProductVideo, db::Error> {
+        let mut tables = self.write();
+
+        let video = ProductVideo {
+            id: memory::next_id(tables.product_videos.iter().map(|video| video.id)),
+            ..video.clone()
+        };
+        tables.product_videos.push(video.clone());
+        tables.paint_usages.extend(usages.iter().map(|usage| PaintUsage {
+            video_id: video.id,
+            ..usage.clone()
+        }));
+
+        Ok(video)
+    }
+
+    fn update_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        if !memory::replace(&mut tables.product_videos, video, |video| video.id) {
+            return Ok(false);
+        }
+        tables.paint_usages.retain(|usage| usage.video_id != video.id);
+        tables.paint_usages.extend(usages.iter().map(|usage| PaintUsage {
+            video_id: video.id,
+            ..usage.clone()
+        }));
+
+        Ok(true)
+    }
+
+    fn delete_video(&self, id: i32) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        tables.paint_usages.retain(|usage| usage.video_id != id);
+        // recipes linked to the video are kept without it
+        for recipe in &mut tables.recipes {
+            if recipe.video_id == Some(id) {
+                recipe.video_id = None;
+            }
+        }
+        Ok(memory::remove(&mut tables.product_videos, id, |video| video.id))
+    }
+}
+
+/// a pair of paint ids with the lowest one first
+fn ordered(a: i32, b: i32) -> (i32, i32) {
+    (a.min(b), a.max(b))
+}
+
+/// compare two numbers that are never NaN
+fn compare(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// match the order the database hands videos back in
+fn newest_first(a: &ProductVideo, b: &ProductVideo) -> Ordering {
+    b.published_on
+        .cmp(&a.published_on)
+        .then_with(|| a.title.cmp(&b.title))
+}
@@ -0,0 +1,409 @@
+// external crates
+use chrono::{DateTime, NaiveDate, Utc};
+use juniper::{FieldResult, ID};
+
+use super::{Brand, Client, PaintEquivalent, ProductLine, ProductVideo};
+use crate::colors::Lab;
+use crate::node::{self, Kind, Node};
+use crate::{api, errors, pricing};
+
+/// the kind of surface a paint dries to (or the job it's designed for)
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Finish {
+    Matte,
+    Gloss,
+    Metallic,
+    Wash,
+    Contrast,
+    Ink,
+    Primer,
+}
+
+impl Finish {
+    /// the name we use for the finish when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Finish::Matte => "matte",
+            Finish::Gloss => "gloss",
+            Finish::Metallic => "metallic",
+            Finish::Wash => "wash",
+            Finish::Contrast => "contrast",
+            Finish::Ink => "ink",
+            Finish::Primer => "primer",
+        }
+    }
+}
+
+/// a color in the sRGB space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    /// parse a color out of a hex string like #9a1115 (the leading # is optional)
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let digits = hex.trim_start_matches('#');
+        if digits.len() != 6 || !digits.is_ascii() {
+            return None;
+        }
+
+        // each channel is a pair of hex digits
+        let channel = |start: usize| u8::from_str_radix(&digits[start..start + 2], 16).ok();
+
+        Some(Color {
+            red: channel(0)?,
+            green: channel(2)?,
+            blue: channel(4)?,
+        })
+    }
+
+    /// the lowercase hex representation of the color, including the leading #
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// the color in the CIELAB space
+    pub fn to_lab(self) -> Lab {
+        Lab::from_srgb(self.red, self.green, self.blue)
+    }
+
+    /// how different two colors look, as the CIEDE2000 difference between them
+    pub fn distance(self, other: Color) -> f64 {
+        self.to_lab().delta_e(other.to_lab())
+    }
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl Color {
+    /// the hex representation of the color (ie, #9a1115)
+    fn hex(&self) -> String {
+        self.to_hex()
+    }
+
+    /// the red channel of the color (0-255)
+    fn red(&self) -> i32 {
+        self.red as i32
+    }
+
+    /// the green channel of the color (0-255)
+    fn green(&self) -> i32 {
+        self.green as i32
+    }
+
+    /// the blue channel of the color (0-255)
+    fn blue(&self) -> i32 {
+        self.blue as i32
+    }
+
+    /// the hue of the color in degrees (0-360), measured in CIELCh
+    fn hue(&self) -> f64 {
+        self.to_lab().hue()
+    }
+
+    /// how light the color looks, from 0 (black) to 100 (white), measured in CIELAB
+    fn lightness(&self) -> f64 {
+        self.to_lab().l
+    }
+}
+
+/// a single pot of paint
+#[derive(Clone, Debug)]
+pub struct Paint {
+    pub id: i32,
+    pub line_id: i32,
+    pub name: String,
+    pub sku: Option<String>,
+    pub color: Color,
+    pub finish: Finish,
+    pub volume_ml: f64,
+    pub discontinued: bool,
+    pub released_on: Option<NaiveDate>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl Paint {
+    /// the globally unique identifier of the paint
+    fn id(&self) -> ID {
+        node::global_id(Kind::Paint, self.id)
+    }
+
+    /// the company that makes the paint (ie, Citadel)
+    fn brand(&self, context: &api::Context) -> FieldResult<Brand> {
+        let line = context.products.line(self.line_id)?.ok_or("missing product line")?;
+        Ok(context.products.brand(line.brand_id)?.ok_or("missing brand")?)
+    }
+
+    /// the range the paint is sold in (ie, Base)
+    fn productLine(&self, context: &api::Context) -> FieldResult<ProductLine> {
+        Ok(context.products.line(self.line_id)?.ok_or("missing product line")?)
+    }
+
+    /// the name printed on the pot
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the manufacturer's stock keeping unit, if we know it
+    fn sku(&self) -> Option<&str> {
+        self.sku.as_deref()
+    }
+
+    /// the color of the paint once it has dried
+    fn color(&self) -> &Color {
+        &self.color
+    }
+
+    /// the finish of the paint once it has dried
+    fn finish(&self) -> Finish {
+        self.finish
+    }
+
+    /// the amount of paint in a single pot, in milliliters
+    fn volume(&self) -> f64 {
+        self.volume_ml
+    }
+
+    /// whether the paint is no longer being produced
+    fn discontinued(&self) -> bool {
+        self.discontinued
+    }
+
+    /// the day the paint went on sale, if we know it
+    fn releasedOn(&self) -> Option<NaiveDate> {
+        self.released_on
+    }
+
+    /// paints from other brands that can be used instead of this one, or only the ones
+    /// from a single brand if one is given
+    fn equivalents(&self, context: &api::Context, brand: Option<ID>) -> FieldResult<Vec<PaintEquivalent>> {
+        Ok(context.products.equivalents(self, brand.as_ref())?)
+    }
+
+    /// the videos that show the paint being used, newest first
+    fn videos(&self, context: &api::Context) -> FieldResult<Vec<ProductVideo>> {
+        Ok(context.products.videos_for_paint(self.id)?)
+    }
+
+    /// the latest price at every retailer that has been seen selling the paint. prices
+    /// where it's in stock come first, cheapest first, so the first one is the best place
+    /// to buy it right now
+    fn prices(&self, context: &api::Context) -> FieldResult<Vec<pricing::PriceObservation>> {
+        Ok(context.pricing.current_prices(self.id, &context.currency)?)
+    }
+
+    /// every price seen for the paint, oldest first, for charting how it's changed. it can
+    /// be narrowed down to a single retailer or to prices seen since some point in time
+    fn priceHistory(
+        &self,
+        context: &api::Context,
+        retailer: Option<ID>,
+        since: Option<DateTime<Utc>>,
+    ) -> FieldResult<Vec<pricing::PriceObservation>> {
+        let retailer_id = match retailer {
+            Some(id) => match node::local_id(Kind::Retailer, &id) {
+                Some(id) => Some(id),
+                // an id for something other than a retailer can't have any prices
+                None => return Ok(Vec::new()),
+            },
+            None => None,
+        };
+        Ok(context.pricing.price_history(self.id, retailer_id, since)?)
+    }
+}
+
+connection!(PaintConnection, PaintEdge, Paint);
+
+/// everything needed to add a paint to the catalog
+#[derive(juniper::GraphQLInputObject)]
+pub struct PaintInput {
+    /// the id of the product line the paint is sold in
+    pub product_line: ID,
+    pub name: String,
+    pub sku: Option<String>,
+    /// the dried color of the paint as a hex string (ie, #9a1115)
+    pub hex: String,
+    pub finish: Finish,
+    /// the amount of paint in a single pot, in milliliters
+    pub volume: f64,
+    pub discontinued: Option<bool>,
+    pub released_on: Option<NaiveDate>,
+}
+
+impl PaintInput {
+    /// check the input over and turn it into a paint that hasn't been stored yet
+    pub(super) fn into_paint(self, id: i32, client: &Client) -> Result<Paint, errors::Error> {
+        let mut validation = errors::Validation::new();
+        let line = client.find_line(&self.product_line)?;
+        if line.is_none() {
+            validation.add("productLine", "does not exist");
+        }
+        validation.require("name", &self.name);
+        if let Some(sku) = &self.sku {
+            validation.require("sku", sku);
+        }
+        let color = Color::from_hex(&self.hex);
+        if color.is_none() {
+            validation.add("hex", "must be a color like #9a1115");
+        }
+        if self.volume <= 0.0 {
+            validation.add("volume", "must be greater than zero");
+        }
+        validation.finish()?;
+
+        // the validation would have failed if the line or color were missing
+        Ok(Paint {
+            id,
+            line_id: line.unwrap().id,
+            name: self.name.trim().to_string(),
+            sku: self.sku.map(|sku| sku.trim().to_string()),
+            color: color.unwrap(),
+            finish: self.finish,
+            volume_ml: self.volume,
+            discontinued: self.discontinued.unwrap_or(false),
+            released_on: self.released_on,
+        })
+    }
+}
+
+/// the changes to make to an existing paint. fields that are left out are unchanged
+#[derive(juniper::GraphQLInputObject)]
+pub struct PaintPatch {
+    pub product_line: Option<ID>,
+    pub name: Option<String>,
+    /// an empty sku removes the one the paint has
+    pub sku: Option<String>,
+    pub hex: Option<String>,
+    pub finish: Option<Finish>,
+    pub volume: Option<f64>,
+    pub discontinued: Option<bool>,
+    pub released_on: Option<NaiveDate>,
+    /// remove the release date the paint has, for when it turns out to be wrong
+    pub clear_released_on: Option<bool>,
+}
+
+impl PaintPatch {
+    /// apply the changes to the paint, validating the result as if it were brand new
+    pub(super) fn apply(self, paint: Paint, client: &Client) -> Result<Paint, errors::Error> {
+        let clear_released_on = self.clear_released_on.unwrap_or(false);
+        if clear_released_on && self.released_on.is_some() {
+            let mut validation = errors::Validation::new();
+            validation.add("clearReleasedOn", "cannot be combined with releasedOn");
+            validation.finish()?;
+        }
+
+        let id = paint.id;
+        let color = paint.color;
+        let input = PaintInput {
+            product_line: self
+                .product_line
+                .unwrap_or_else(|| node::global_id(Kind::ProductLine, paint.line_id)),
+            name: self.name.unwrap_or(paint.name),
+            sku: match self.sku {
+                Some(sku) if sku.is_empty() => None,
+                Some(sku) => Some(sku),
+                None => paint.sku,
+            },
+            hex: self.hex.unwrap_or_else(|| color.to_hex()),
+            finish: self.finish.unwrap_or(paint.finish),
+            volume: self.volume.unwrap_or(paint.volume_ml),
+            discontinued: Some(self.discontinued.unwrap_or(paint.discontinued)),
+            released_on: if clear_released_on {
+                None
+            } else {
+                self.released_on.or(paint.released_on)
+            },
+        };
+
+        input.into_paint(id, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::products::{ColorIndex, SearchIndex};
+    use crate::storage;
+
+    /// a patch that leaves everything as it is
+    fn unchanged() -> PaintPatch {
+        PaintPatch {
+            product_line: None,
+            name: None,
+            sku: None,
+            hex: None,
+            finish: None,
+            volume: None,
+            discontinued: None,
+            released_on: None,
+            clear_released_on: None,
+        }
+    }
+
+    /// a patch that only touches the sku
+    fn sku(sku: Option<&str>) -> PaintPatch {
+        PaintPatch {
+            sku: sku.map(str::to_string),
+            ..unchanged()
+        }
+    }
+
+    fn client() -> Client {
+        let backend = storage::Backend::memory();
+        Client::new(backend.products(), ColorIndex::new(), SearchIndex::new())
+    }
+
+    #[test]
+    fn patches_set_keep_and_clear_the_sku() {
+        let client = client();
+
+        let updated = client.update_paint(1, sku(Some("21-25"))).unwrap();
+        assert_eq!(updated.sku.as_deref(), Some("21-25"));
+        let updated = client.update_paint(1, sku(None)).unwrap();
+        assert_eq!(updated.sku.as_deref(), Some("21-25"));
+        let updated = client.update_paint(1, sku(Some(""))).unwrap();
+        assert_eq!(updated.sku, None);
+
+        // anything else blank is still a mistake
+        assert!(client.update_paint(1, sku(Some(" "))).is_err());
+    }
+    #[test]
+    fn patches_set_keep_and_clear_the_release_date() {
+        let client = client();
+        let released_on = NaiveDate::from_ymd_opt(2012, 6, 2).unwrap();
+
+        // the fixtures know when the citadel base paints came out
+        let updated = client.update_paint(1, unchanged()).unwrap();
+        assert_eq!(updated.released_on, Some(released_on));
+
+        let clear = PaintPatch {
+            clear_released_on: Some(true),
+            ..unchanged()
+        };
+        assert_eq!(client.update_paint(1, clear).unwrap().released_on, None);
+
+        let set = PaintPatch {
+            released_on: Some(released_on),
+            ..unchanged()
+        };
+        let updated = client.update_paint(1, set).unwrap();
+        assert_eq!(updated.released_on, Some(released_on));
+
+        // asking for both is a mistake rather than a coin toss
+        let both = PaintPatch {
+            released_on: Some(released_on),
+            clear_released_on: Some(true),
+            ..unchanged()
+        };
+        assert!(client.update_paint(1, both).is_err());
+        let kept = client.paint(1).unwrap().unwrap();
+        assert_eq!(kept.released_on, Some(released_on));
+    }
+}
@@ -0,0 +1,373 @@
+// external crates
+use juniper::{FieldResult, ID};
+use std::collections::{HashMap, HashSet};
+
+use super::index::Shared;
+use super::{Brand, Client, Color, Paint, PaintQuery, ProductVideo, Repository};
+use crate::fulltext::{self, TextIndex};
+use crate::node::{self, Kind};
+use crate::trie::Trie;
+use crate::{api, db, errors};
+
+/// the most records a single search hands back
+const MAX_RESULTS: usize = 50;
+
+/// the most paints we'll suggest for what someone has typed so far
+const MAX_SUGGESTIONS: i32 = 50;
+
+/// how much a match counts for in each of the fields we search. names count the most,
+/// while the brand and line of a paint or the channel of a video only help rank it
+const NAME_WEIGHT: f64 = 1.0;
+const SKU_WEIGHT: f64 = 1.0;
+const CONTEXT_WEIGHT: f64 = 0.5;
+
+/// the kinds of records a search can turn up
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SearchType {
+    Paint,
+    Brand,
+    ProductVideo,
+}
+
+/// a record that came up in a search
+pub enum SearchResult {
+    Paint(Paint),
+    Brand(Brand),
+    ProductVideo(ProductVideo),
+}
+
+juniper::graphql_union!(SearchResult: api::Context |&self| {
+    description: "a record that came up in a search"
+
+    instance_resolvers: |_| {
+        &Paint => match *self { SearchResult::Paint(ref paint) => Some(paint), _ => None },
+        &Brand => match *self { SearchResult::Brand(ref brand) => Some(brand), _ => None },
+        &ProductVideo => match *self { SearchResult::ProductVideo(ref video) => Some(video), _ => None },
+    }
+});
+
+/// a paint whose name starts with what someone has typed so far. it carries everything a
+/// dropdown needs to show it without looking anything else up
+#[derive(Clone, Debug)]
+pub struct PaintSuggestion {
+    pub paint_id: i32,
+    pub name: String,
+    pub brand: String,
+    pub color: Color,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl PaintSuggestion {
+    /// the globally unique identifier of the paint
+    fn id(&self) -> ID {
+        node::global_id(Kind::Paint, self.paint_id)
+    }
+
+    /// the name printed on the pot
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the name of the company that makes the paint
+    fn brand(&self) -> &str {
+        &self.brand
+    }
+
+    /// the hex representation of the paint's color, for drawing a swatch
+    fn hex(&self) -> String {
+        self.color.to_hex()
+    }
+
+    /// everything else about the paint
+    fn paint(&self, context: &api::Context) -> FieldResult<Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+}
+
+/// a full text index over the names of the paints, brands and videos we know of along with
+/// prefix trees over the paint names for suggesting paints as someone types. like the color
+/// index they're shared between every request and rebuilt after something changes.
+#[derive(Clone, Default)]
+pub struct SearchIndex {
+    text: Shared<TextIndex<Hit>>,
+    suggestions: Shared<Suggestions>,
+}
+
+/// the prefix trees behind paint suggestions
+struct Suggestions {
+    /// keyed by the whole name of each paint
+    names: Trie<PaintSuggestion>,
+    /// keyed by the rest of the name from each word after the first, so typing "red" also
+    /// turns up Mephiston Red
+    words: Trie<PaintSuggestion>,
+}
+
+/// the record behind a document in the index
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub kind: SearchType,
+    pub id: i32,
+}
+
+impl SearchIndex {
+    pub fn new() -> SearchIndex {
+        SearchIndex::default()
+    }
+
+    /// the up to `limit` records that best match the text and pass the filter, best first
+    pub fn search<F>(
+        &self,
+        repo: &dyn Repository,
+        text: &str,
+        limit: usize,
+        accept: F,
+    ) -> Result<Vec<Hit>, db::Error>
+    where
+        F: Fn(&Hit) -> bool,
+    {
+        let index = self.text.get(|| build_text(repo))?;
+
+        Ok(index
+            .search(text, limit, accept)
+            .into_iter()
+            .map(|(hit, _)| *hit)
+            .collect())
+    }
+
+    /// the up to `limit` paints whose names (or any word in them) start with the prefix.
+    /// paints whose whole name matches come first, each group in alphabetical order
+    pub fn suggest(
+        &self,
+        repo: &dyn Repository,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<PaintSuggestion>, db::Error> {
+        // a prefix with nothing but punctuation in it would otherwise match every name
+        let prefix = fulltext::words(prefix).join(" ");
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+        let suggestions = self.suggestions.get(|| build_suggestions(repo))?;
+
+        let mut found: Vec<PaintSuggestion> = suggestions
+            .names
+            .with_prefix(&prefix, limit, |_| true)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        // a paint can match more than one of its words but should only show up once
+        let mut seen: HashSet<i32> = found.iter().map(|suggestion| suggestion.paint_id).collect();
+        let more = suggestions
+            .words
+            .with_prefix(&prefix, limit - found.len(), |suggestion| seen.insert(suggestion.paint_id));
+        found.extend(more.into_iter().cloned());
+
+        Ok(found)
+    }
+
+    /// forget the current indexes so the next lookup sees the latest records
+    pub fn invalidate(&self) {
+        self.text.invalidate();
+        self.suggestions.invalidate();
+    }
+}
+
+/// build an index over every paint, brand and video
+fn build_text(repo: &dyn Repository) -> Result<TextIndex<Hit>, db::Error> {
+    let brands = repo.all_brands()?;
+    let paints = repo.find_paints(&PaintQuery::default())?;
+    let videos = repo.all_videos()?;
+
+    // paints are easier to find by the brand and line they belong to as well
+    let lines: HashMap<i32, String> = brands_and_lines(repo, &paints)?
+        .into_iter()
+        .map(|(line_id, (brand, line))| (line_id, format!("{} {}", brand, line)))
+        .collect();
+
+    let mut documents = Vec::new();
+    for brand in &brands {
+        documents.push((
+            Hit {
+                kind: SearchType::Brand,
+                id: brand.id,
+            },
+            vec![(brand.name.clone(), NAME_WEIGHT)],
+        ));
+    }
+    for paint in paints {
+        let mut fields = vec![(paint.name, NAME_WEIGHT)];
+        if let Some(sku) = paint.sku {
+            fields.push((sku, SKU_WEIGHT));
+        }
+        if let Some(line) = lines.get(&paint.line_id) {
+            fields.push((line.clone(), CONTEXT_WEIGHT));
+        }
+        documents.push((
+            Hit {
+                kind: SearchType::Paint,
+                id: paint.id,
+            },
+            fields,
+        ));
+    }
+    for video in videos {
+        documents.push((
+            Hit {
+                kind: SearchType::ProductVideo,
+                id: video.id,
+            },
+            vec![(video.title, NAME_WEIGHT), (video.channel, CONTEXT_WEIGHT)],
+        ));
+    }
+
+    Ok(TextIndex::build(documents))
+}
+
+/// build the prefix trees over the names of every paint
+fn build_suggestions(repo: &dyn Repository) -> Result<Suggestions, db::Error> {
+    let paints = repo.find_paints(&PaintQuery::default())?;
+    let lines = brands_and_lines(repo, &paints)?;
+
+    let mut names = Vec::new();
+    let mut words = Vec::new();
+    for paint in paints {
+        let suggestion = PaintSuggestion {
+            paint_id: paint.id,
+            brand: lines
+                .get(&paint.line_id)
+                .map(|(brand, _)| brand.clone())
+                .unwrap_or_default(),
+            name: paint.name,
+            color: paint.color,
+        };
+
+        let name = fulltext::words(&suggestion.name);
+        for start in 1..name.len() {
+            words.push((name[start..].join(" "), suggestion.clone()));
+        }
+        names.push((name.join(" "), suggestion));
+    }
+
+    Ok(Suggestions {
+        names: Trie::build(names),
+        words: Trie::build(words),
+    })
+}
+
+/// the names of the brand and the line each of the paints is sold in, by the id of the line
+fn brands_and_lines(
+    repo: &dyn Repository,
+    paints: &[Paint],
+) -> Result<HashMap<i32, (String, String)>, db::Error> {
+    let mut line_ids: Vec<i32> = paints.iter().map(|paint| paint.line_id).collect();
+    line_ids.sort();
+    line_ids.dedup();
+    let lines: Vec<_> = repo.lines_by_id(&line_ids)?.into_iter().flatten().collect();
+
+    let mut brand_ids: Vec<i32> = lines.iter().map(|line| line.brand_id).collect();
+    brand_ids.sort();
+    brand_ids.dedup();
+    let brands: HashMap<i32, String> = repo
+        .brands_by_id(&brand_ids)?
+        .into_iter()
+        .flatten()
+        .map(|brand| (brand.id, brand.name))
+        .collect();
+
+    Ok(lines
+        .into_iter()
+        .map(|line| {
+            let brand = brands.get(&line.brand_id).cloned().unwrap_or_default();
+            (line.id, (brand, line.name))
+        })
+        .collect())
+}
+
+impl Client {
+    /// the paints, brands and videos that best match the text, best first, optionally only
+    /// of some kinds
+    pub fn search(
+        &self,
+        text: &str,
+        types: Option<Vec<SearchType>>,
+    ) -> Result<Vec<SearchResult>, errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("text", text);
+        validation.finish()?;
+
+        let hits = self.search_index.search(&*self.repo, text, MAX_RESULTS, |hit| match &types {
+            Some(types) => types.contains(&hit.kind),
+            None => true,
+        })?;
+
+        // load everything in a single batch per kind of record
+        for hit in &hits {
+            match hit.kind {
+                SearchType::Paint => self.paints.expect(Some(hit.id)),
+                SearchType::Brand => self.brands.expect(Some(hit.id)),
+                SearchType::ProductVideo => self.videos.expect(Some(hit.id)),
+            }
+        }
+
+        let mut results = Vec::new();
+        for hit in hits {
+            // the index can lag behind storage so records might have gone missing since
+            let result = match hit.kind {
+                SearchType::Paint => self.paint(hit.id)?.map(SearchResult::Paint),
+                SearchType::Brand => self.brand(hit.id)?.map(SearchResult::Brand),
+                SearchType::ProductVideo => self.video(hit.id)?.map(SearchResult::ProductVideo),
+            };
+            results.extend(result);
+        }
+
+        Ok(results)
+    }
+
+    /// the paints whose names start with what someone has typed so far
+    pub fn suggest_paints(
+        &self,
+        prefix: &str,
+        limit: i32,
+    ) -> Result<Vec<PaintSuggestion>, errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("prefix", prefix);
+        if !(1..=MAX_SUGGESTIONS).contains(&limit) {
+            validation.add("limit", &format!("must be between 1 and {}", MAX_SUGGESTIONS));
+        }
+        validation.finish()?;
+
+        Ok(self.search_index.suggest(&*self.repo, prefix, limit as usize)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+
+    /// the names of the paints suggested for the prefix
+    fn suggested(prefix: &str) -> Vec<String> {
+        let backend = storage::Backend::memory();
+        SearchIndex::new()
+            .suggest(&*backend.products(), prefix, 10)
+            .unwrap()
+            .into_iter()
+            .map(|suggestion| suggestion.name)
+            .collect()
+    }
+
+    #[test]
+    fn suggests_names_by_prefix() {
+        assert_eq!(suggested("abad"), vec!["Abaddon Black"]);
+        assert_eq!(suggested("  ABAD! "), vec!["Abaddon Black"]);
+    }
+
+    #[test]
+    fn punctuation_suggests_nothing() {
+        assert!(suggested("-").is_empty());
+        assert!(suggested("?! ...").is_empty());
+    }
+}
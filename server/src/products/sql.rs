@@ -0,0 +1,557 @@
+// external crates
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Row, Transaction, NO_PARAMS};
+
+use super::{
+    Brand, Color, Equivalence, EquivalenceSource, Finish, Paint, PaintQuery, PaintSortField,
+    PaintUsage, ProductLine, ProductVideo, Repository, SortDirection,
+};
+use crate::db;
+
+/// the columns we need to select in order to build a Paint
+const PAINT_COLUMNS: &str =
+    "paints.id, paints.product_line_id, paints.name, paints.sku, paints.hex, paints.finish, \
+     paints.volume_ml, paints.discontinued, paints.released_on";
+
+/// the columns we need to select in order to build a ProductVideo
+const VIDEO_COLUMNS: &str =
+    "product_videos.id, product_videos.title, product_videos.channel, product_videos.url, \
+     product_videos.duration_seconds, product_videos.published_on";
+
+impl Repository for db::Pool {
+    fn find_paints(&self, query: &PaintQuery) -> Result<Vec<Paint>, db::Error> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(brand_id) = query.brand_id {
+            conditions.push("product_lines.brand_id = ?");
+            values.push(Box::new(brand_id));
+        }
+        if let Some(line_id) = query.line_id {
+            conditions.push("paints.product_line_id = ?");
+            values.push(Box::new(line_id));
+        }
+        if let Some(finish) = query.finish {
+            conditions.push("paints.finish = ?");
+            values.push(Box::new(finish));
+        }
+        if let Some((min, max)) = query.hue {
+            // a range that wraps around through 0 takes in both ends of the circle
+            conditions.push(if min <= max {
+                "paint_hue(paints.hex) BETWEEN ? AND ?"
+            } else {
+                "(paint_hue(paints.hex) >= ? OR paint_hue(paints.hex) <= ?)"
+            });
+            values.push(Box::new(min));
+            values.push(Box::new(max));
+        }
+        if let Some((min, max)) = query.lightness {
+            conditions.push("paint_lightness(paints.hex) BETWEEN ? AND ?");
+            values.push(Box::new(min));
+            values.push(Box::new(max));
+        }
+        if let Some(discontinued) = query.discontinued {
+            conditions.push("paints.discontinued = ?");
+            values.push(Box::new(discontinued));
+        }
+        match query.has_video {
+            Some(true) => conditions.push(
+                "EXISTS (SELECT 1 FROM paint_usages WHERE paint_usages.paint_id = paints.id)",
+            ),
+            Some(false) => conditions.push(
+                "NOT EXISTS (SELECT 1 FROM paint_usages WHERE paint_usages.paint_id = paints.id)",
+            ),
+            None => {}
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        // whatever the client sorts by, ties fall back on the catalog order
+        let order = match query.order {
+            Some((field, direction)) => {
+                let column = match field {
+                    PaintSortField::Name => "paints.name",
+                    PaintSortField::Brand => "brands.name",
+                    PaintSortField::Hue => "paint_hue(paints.hex)",
+                    PaintSortField::Lightness => "paint_lightness(paints.hex)",
+                    // paints without a release date come last whichever way they're sorted
+                    PaintSortField::ReleaseDate => "paints.released_on IS NULL, paints.released_on",
+                };
+                let direction = match direction {
+                    SortDirection::Asc => "ASC",
+                    SortDirection::Desc => "DESC",
+                };
+                format!("{} {}, ", column, direction)
+            }
+            None => String::new(),
+        };
+
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM paints
+             JOIN product_lines ON product_lines.id = paints.product_line_id
+             JOIN brands ON brands.id = product_lines.brand_id
+             {}
+             ORDER BY {}brands.name, product_lines.name, paints.name, paints.id",
+            PAINT_COLUMNS, filter, order
+        ))?;
+
+        let paints = statement
+            .query_map(&values, paint_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(paints)
+    }
+
+    fn paints_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Paint>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM paints WHERE id IN ({{keys}})", PAINT_COLUMNS),
+            ids,
+            paint_from_row,
+            |paint| paint.id,
+        )
+    }
+
+    fn paints_by_line(&self, line_ids: &[i32]) -> Result<Vec<Vec<Paint>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM paints WHERE product_line_id IN ({{keys}}) ORDER BY name",
+                PAINT_COLUMNS
+            ),
+            line_ids,
+            paint_from_row,
+            |paint| paint.line_id,
+        )
+    }
+
+    fn insert_paint(&self, paint: &Paint) -> Result<Paint, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO paints
+                 (product_line_id, name, sku, hex, finish, volume_ml, discontinued, released_on)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                paint.line_id,
+                paint.name,
+                paint.sku,
+                paint.color,
+                paint.finish,
+                paint.volume_ml,
+                paint.discontinued,
+                paint.released_on,
+            ],
+        )?;
+
+        Ok(Paint {
+            id: conn.last_insert_rowid() as i32,
+            ..paint.clone()
+        })
+    }
+
+    fn update_paint(&self, paint: &Paint) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        let changed = conn.execute(
+            "UPDATE paints
+             SET product_line_id = ?, name = ?, sku = ?, hex = ?, finish = ?, volume_ml = ?,
+                 discontinued = ?, released_on = ?
+             WHERE id = ?",
+            params![
+                paint.line_id,
+                paint.name,
+                paint.sku,
+                paint.color,
+                paint.finish,
+                paint.volume_ml,
+                paint.discontinued,
+                paint.released_on,
+                paint.id,
+            ],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_paint(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM paints WHERE id = ?", [id])? > 0)
+    }
+
+    fn all_brands(&self) -> Result<Vec<Brand>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare("SELECT id, name FROM brands ORDER BY name")?;
+
+        let brands = statement
+            .query_map(NO_PARAMS, brand_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(brands)
+    }
+
+    fn brands_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Brand>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            "SELECT id, name FROM brands WHERE id IN ({keys})",
+            ids,
+            brand_from_row,
+            |brand| brand.id,
+        )
+    }
+
+    fn insert_brand(&self, brand: &Brand) -> Result<Brand, db::Error> {
+        let conn = self.get()?;
+        conn.execute("INSERT INTO brands (name) VALUES (?)", [&brand.name])?;
+
+        Ok(Brand {
+            id: conn.last_insert_rowid() as i32,
+            ..brand.clone()
+        })
+    }
+
+    fn update_brand(&self, brand: &Brand) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        let changed = conn.execute(
+            "UPDATE brands SET name = ? WHERE id = ?",
+            params![brand.name, brand.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_brand(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM brands WHERE id = ?", [id])? > 0)
+    }
+
+    fn lines_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductLine>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            "SELECT id, brand_id, name FROM product_lines WHERE id IN ({keys})",
+            ids,
+            line_from_row,
+            |line| line.id,
+        )
+    }
+
+    fn lines_by_brand(&self, brand_ids: &[i32]) -> Result<Vec<Vec<ProductLine>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            "SELECT id, brand_id, name FROM product_lines WHERE brand_id IN ({keys}) ORDER BY name",
+            brand_ids,
+            line_from_row,
+            |line| line.brand_id,
+        )
+    }
+
+    fn insert_line(&self, line: &ProductLine) -> Result<ProductLine, db::Error> {
+        let conn = self.get()?;
+        conn.execute(
+            "INSERT INTO product_lines (brand_id, name) VALUES (?, ?)",
+            params![line.brand_id, line.name],
+        )?;
+
+        Ok(ProductLine {
+            id: conn.last_insert_rowid() as i32,
+            ..line.clone()
+        })
+    }
+
+    fn update_line(&self, line: &ProductLine) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        let changed = conn.execute(
+            "UPDATE product_lines SET brand_id = ?, name = ? WHERE id = ?",
+            params![line.brand_id, line.name, line.id],
+        )?;
+
+        Ok(changed > 0)
+    }
+
+    fn delete_line(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM product_lines WHERE id = ?", [id])? > 0)
+    }
+
+    fn equivalences_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<Equivalence>>, db::Error> {
+        // each pair is stored once so we have to look at it from both ends
+        db::load_grouped(
+            &*self.get()?,
+            "SELECT paint_id, equivalent_id, source FROM paint_equivalents \
+             WHERE paint_id IN ({keys}) \
+             UNION ALL \
+             SELECT equivalent_id, paint_id, source FROM paint_equivalents \
+             WHERE equivalent_id IN ({keys})",
+            paint_ids,
+            equivalence_from_row,
+            |equivalence| equivalence.paint_id,
+        )
+    }
+
+    fn insert_equivalence(&self, equivalence: &Equivalence) -> Result<(), db::Error> {
+        let (paint_id, equivalent_id) = ordered(equivalence.paint_id, equivalence.equivalent_id);
+        self.get()?.execute(
+            "INSERT OR REPLACE INTO paint_equivalents (paint_id, equivalent_id, source) \
+             VALUES (?, ?, ?)",
+            params![paint_id, equivalent_id, equivalence.source],
+        )?;
+
+        Ok(())
+    }
+
+    fn delete_equivalence(&self, paint_id: i32, equivalent_id: i32) -> Result<bool, db::Error> {
+        let (paint_id, equivalent_id) = ordered(paint_id, equivalent_id);
+        let deleted = self.get()?.execute(
+            "DELETE FROM paint_equivalents WHERE paint_id = ? AND equivalent_id = ?",
+            [paint_id, equivalent_id],
+        )?;
+
+        Ok(deleted > 0)
+    }
+
+    fn all_videos(&self) -> Result<Vec<ProductVideo>, db::Error> {
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM product_videos ORDER BY published_on DESC, title",
+            VIDEO_COLUMNS
+        ))?;
+
+        let videos = statement
+            .query_map(NO_PARAMS, video_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(videos)
+    }
+
+    fn videos_by_id(&self, ids: &[i32]) -> Result<Vec<Option<ProductVideo>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM product_videos WHERE id IN ({{keys}})", VIDEO_COLUMNS),
+            ids,
+            video_from_row,
+            |video| video.id,
+        )
+    }
+
+    fn videos_by_paint(&self, paint_ids: &[i32]) -> Result<Vec<Vec<ProductVideo>>, db::Error> {
+        // the paint id rides along at the end of each row so we can group the videos by it
+        let groups = db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT DISTINCT {}, paint_usages.paint_id FROM product_videos
+                 JOIN paint_usages ON paint_usages.video_id = product_videos.id
+                 WHERE paint_usages.paint_id IN ({{keys}})
+                 ORDER BY product_videos.published_on DESC, product_videos.title",
+                VIDEO_COLUMNS
+            ),
+            paint_ids,
+            |row| Ok((row.get::<_, i32>(6)?, video_from_row(row)?)),
+            |(paint_id, _)| *paint_id,
+        )?;
+
+        Ok(groups
+            .into_iter()
+            .map(|group| group.into_iter().map(|(_, video)| video).collect())
+            .collect())
+    }
+
+    fn usages_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<PaintUsage>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            "SELECT video_id, paint_id, start_seconds, note FROM paint_usages
+             WHERE video_id IN ({keys})
+             ORDER BY start_seconds, paint_id",
+            video_ids,
+            usage_from_row,
+            |usage| usage.video_id,
+        )
+    }
+
+    fn insert_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<ProductVideo, db::Error> {
+        let mut conn = self.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO product_videos (title, channel, url, duration_seconds, published_on)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                video.title,
+                video.channel,
+                video.url,
+                video.duration_seconds,
+                video.published_on,
+            ],
+        )?;
+
+        let video = ProductVideo {
+            id: tx.last_insert_rowid() as i32,
+            ..video.clone()
+        };
+        insert_usages(&tx, video.id, usages)?;
+        tx.commit()?;
+
+        Ok(video)
+    }
+
+    fn update_video(&self, video: &ProductVideo, usages: &[PaintUsage]) -> Result<bool, db::Error> {
+        let mut conn = self.get()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE product_videos
+             SET title = ?, channel = ?, url = ?, duration_seconds = ?, published_on = ?
+             WHERE id = ?",
+            params![
+                video.title,
+                video.channel,
+                video.url,
+                video.duration_seconds,
+                video.published_on,
+                video.id,
+            ],
+        )?;
+        if changed == 0 {
+            return Ok(false);
+        }
+
+        // the list of paints is replaced wholesale
+        tx.execute("DELETE FROM paint_usages WHERE video_id = ?", [video.id])?;
+        insert_usages(&tx, video.id, usages)?;
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    fn delete_video(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM product_videos WHERE id = ?", [id])? > 0)
+    }
+}
+
+/// store the paints used in a video
+fn insert_usages(tx: &Transaction, video_id: i32, usages: &[PaintUsage]) -> Result<(), db::Error> {
+    let mut statement = tx.prepare(
+        "INSERT INTO paint_usages (video_id, paint_id, start_seconds, note) VALUES (?, ?, ?, ?)",
+    )?;
+    for usage in usages {
+        statement.execute(params![video_id, usage.paint_id, usage.start_seconds, usage.note])?;
+    }
+
+    Ok(())
+}
+
+/// a pair of paint ids with the lowest one first, the way equivalences are stored
+fn ordered(a: i32, b: i32) -> (i32, i32) {
+    (a.min(b), a.max(b))
+}
+
+/// build a paint out of a row containing PAINT_COLUMNS
+fn paint_from_row(row: &Row) -> rusqlite::Result<Paint> {
+    Ok(Paint {
+        id: row.get(0)?,
+        line_id: row.get(1)?,
+        name: row.get(2)?,
+        sku: row.get(3)?,
+        color: row.get(4)?,
+        finish: row.get(5)?,
+        volume_ml: row.get(6)?,
+        discontinued: row.get(7)?,
+        released_on: row.get(8)?,
+    })
+}
+
+fn brand_from_row(row: &Row) -> rusqlite::Result<Brand> {
+    Ok(Brand {
+        id: row.get("id")?,
+        name: row.get("name")?,
+    })
+}
+
+fn line_from_row(row: &Row) -> rusqlite::Result<ProductLine> {
+    Ok(ProductLine {
+        id: row.get("id")?,
+        brand_id: row.get("brand_id")?,
+        name: row.get("name")?,
+    })
+}
+
+/// build a video out of a row containing VIDEO_COLUMNS
+fn video_from_row(row: &Row) -> rusqlite::Result<ProductVideo> {
+    Ok(ProductVideo {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        channel: row.get(2)?,
+        url: row.get(3)?,
+        duration_seconds: row.get(4)?,
+        published_on: row.get(5)?,
+    })
+}
+
+fn usage_from_row(row: &Row) -> rusqlite::Result<PaintUsage> {
+    Ok(PaintUsage {
+        video_id: row.get("video_id")?,
+        paint_id: row.get("paint_id")?,
+        start_seconds: row.get("start_seconds")?,
+        note: row.get("note")?,
+    })
+}
+
+/// build an equivalence out of a row with the paint, the equivalent and the source
+fn equivalence_from_row(row: &Row) -> rusqlite::Result<Equivalence> {
+    Ok(Equivalence {
+        paint_id: row.get(0)?,
+        equivalent_id: row.get(1)?,
+        source: row.get(2)?,
+    })
+}
+
+impl ToSql for Finish {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Finish {
+    fn column_result(value: ValueRef) -> FromSqlResult<Finish> {
+        match value.as_str()? {
+            "matte" => Ok(Finish::Matte),
+            "gloss" => Ok(Finish::Gloss),
+            "metallic" => Ok(Finish::Metallic),
+            "wash" => Ok(Finish::Wash),
+            "contrast" => Ok(Finish::Contrast),
+            "ink" => Ok(Finish::Ink),
+            "primer" => Ok(Finish::Primer),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+// colors are stored as their hex representation
+impl ToSql for Color {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.to_hex().into())
+    }
+}
+
+impl FromSql for Color {
+    fn column_result(value: ValueRef) -> FromSqlResult<Color> {
+        Color::from_hex(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for EquivalenceSource {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for EquivalenceSource {
+    fn column_result(value: ValueRef) -> FromSqlResult<EquivalenceSource> {
+        match value.as_str()? {
+            "official" => Ok(EquivalenceSource::Official),
+            "community" => Ok(EquivalenceSource::Community),
+            "computed" => Ok(EquivalenceSource::Computed),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// external crates
+use chrono::NaiveDate;
+use juniper::{FieldResult, ID};
+
+use super::{not_found, Client, Paint};
+use crate::node::{self, Kind, Node};
+use crate::{api, db, errors, inventory, recipes};
+
+/// a tutorial or review that shows paints being used
+#[derive(Clone, Debug)]
+pub struct ProductVideo {
+    pub id: i32,
+    pub title: String,
+    pub channel: String,
+    pub url: String,
+    pub duration_seconds: i32,
+    pub published_on: NaiveDate,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl ProductVideo {
+    /// the globally unique identifier of the video
+    fn id(&self) -> ID {
+        node::global_id(Kind::ProductVideo, self.id)
+    }
+
+    /// the title the video was published under
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// the channel (or person) that published the video
+    fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// where the video can be watched
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// how long the video runs for, in seconds
+    fn duration(&self) -> i32 {
+        self.duration_seconds
+    }
+
+    /// the day the video was published
+    fn publishedOn(&self) -> NaiveDate {
+        self.published_on
+    }
+
+    /// the paints used in the video, in the order they show up
+    fn paints(&self, context: &api::Context) -> FieldResult<Vec<PaintUsage>> {
+        Ok(context.products.usages_for_video(self.id)?)
+    }
+
+    /// the recipes that follow along with the video, newest first
+    fn recipes(&self, context: &api::Context) -> FieldResult<Vec<recipes::Recipe>> {
+        Ok(context.recipes.recipes_for_video(self.id)?)
+    }
+
+    /// which of the paints used in the video the viewer owns, and the closest thing they
+    /// own to each of the rest. nothing if they aren't signed in
+    fn inventoryCoverage(
+        &self,
+        context: &api::Context,
+    ) -> FieldResult<Option<inventory::InventoryCoverage>> {
+        let viewer = match context.accounts.viewer()? {
+            Some(viewer) => viewer,
+            None => return Ok(None),
+        };
+        let usages = context.products.usages_for_video(self.id)?;
+
+        Ok(Some(context.inventory.coverage(
+            &viewer,
+            usages.iter().map(|usage| usage.paint_id),
+            &context.products,
+        )?))
+    }
+}
+
+connection!(ProductVideoConnection, ProductVideoEdge, ProductVideo);
+
+/// a point in a video where a paint is used
+#[derive(Clone, Debug)]
+pub struct PaintUsage {
+    pub video_id: i32,
+    pub paint_id: i32,
+    pub start_seconds: i32,
+    pub note: Option<String>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl PaintUsage {
+    /// the paint being used
+    fn paint(&self, context: &api::Context) -> FieldResult<Paint> {
+        Ok(context.products.paint(self.paint_id)?.ok_or("missing paint")?)
+    }
+
+    /// how far into the video the paint shows up, in seconds
+    fn startSeconds(&self) -> i32 {
+        self.start_seconds
+    }
+
+    /// what the paint is being used for (ie, edge highlights)
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    /// a link that starts the video where the paint shows up
+    fn link(&self, context: &api::Context) -> FieldResult<String> {
+        let video = context.products.video(self.video_id)?.ok_or("missing video")?;
+        let separator = if video.url.contains('?') { '&' } else { '?' };
+
+        Ok(format!("{}{}t={}", video.url, separator, self.start_seconds))
+    }
+}
+
+/// everything needed to add a video, including the paints used in it
+#[derive(juniper::GraphQLInputObject)]
+pub struct ProductVideoInput {
+    pub title: String,
+    pub channel: String,
+    pub url: String,
+    /// how long the video runs for, in seconds
+    pub duration: i32,
+    pub published_on: NaiveDate,
+    /// every paint used in the video. this replaces the existing list when updating
+    pub paints: Vec<PaintUsageInput>,
+}
+
+/// a point in a video where a paint is used
+#[derive(juniper::GraphQLInputObject)]
+pub struct PaintUsageInput {
+    pub paint: ID,
+    /// how far into the video the paint shows up, in seconds
+    pub start_seconds: i32,
+    pub note: Option<String>,
+}
+
+impl ProductVideoInput {
+    /// check the input over and turn it into a video (and its paints) that hasn't been
+    /// stored yet
+    pub(super) fn into_video(
+        self,
+        id: i32,
+        client: &Client,
+    ) -> Result<(ProductVideo, Vec<PaintUsage>), errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("title", &self.title);
+        validation.require("channel", &self.channel);
+        let url = self.url.trim();
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            validation.add("url", "must be a web address");
+        }
+        if self.duration <= 0 {
+            validation.add("duration", "must be greater than zero");
+        }
+
+        let mut usages = Vec::new();
+        for (index, usage) in self.paints.into_iter().enumerate() {
+            match client.find_paint(&usage.paint)? {
+                // the same paint can come up more than once but not at the same moment
+                Some(paint) if usages.iter().any(|known: &PaintUsage| {
+                    known.paint_id == paint.id && known.start_seconds == usage.start_seconds
+                }) =>
+                {
+                    validation.add(&format!("paints.{}", index), "is already listed at that time")
+                }
+                Some(paint) => usages.push(PaintUsage {
+                    video_id: id,
+                    paint_id: paint.id,
+                    start_seconds: usage.start_seconds,
+                    note: usage
+                        .note
+                        .map(|note| note.trim().to_string())
+                        .filter(|note| !note.is_empty()),
+                }),
+                None => validation.add(&format!("paints.{}.paint", index), "does not exist"),
+            }
+            if usage.start_seconds < 0 || usage.start_seconds > self.duration {
+                validation.add(
+                    &format!("paints.{}.startSeconds", index),
+                    "must be within the video",
+                );
+            }
+        }
+        validation.finish()?;
+
+        usages.sort_by_key(|usage| usage.start_seconds);
+        let video = ProductVideo {
+            id,
+            title: self.title.trim().to_string(),
+            channel: self.channel.trim().to_string(),
+            url: url.to_string(),
+            duration_seconds: self.duration,
+            published_on: self.published_on,
+        };
+
+        Ok((video, usages))
+    }
+}
+
+impl Client {
+    pub fn all_videos(&self) -> Result<Vec<ProductVideo>, db::Error> {
+        let videos = self.repo.all_videos()?;
+        self.prime_videos(&videos);
+        Ok(videos)
+    }
+
+    pub fn video(&self, id: i32) -> Result<Option<ProductVideo>, db::Error> {
+        self.videos.load(id)
+    }
+
+    /// the video an id sent by the client refers to, if there is one
+    pub fn find_video(&self, id: &ID) -> Result<Option<ProductVideo>, db::Error> {
+        match node::local_id(Kind::ProductVideo, id) {
+            Some(id) => self.video(id),
+            None => Ok(None),
+        }
+    }
+
+    /// the videos that use the paint, newest first
+    pub fn videos_for_paint(&self, paint_id: i32) -> Result<Vec<ProductVideo>, db::Error> {
+        let videos = self.videos_by_paint.load(paint_id)?;
+        self.prime_videos(&videos);
+        Ok(videos)
+    }
+
+    /// the paints used in the video, in the order they show up
+    pub fn usages_for_video(&self, video_id: i32) -> Result<Vec<PaintUsage>, db::Error> {
+        let usages = self.usages.load(video_id)?;
+
+        // the paints are usually the next thing asked for so we want them in a single batch
+        self.paints.expect(usages.iter().map(|usage| usage.paint_id));
+
+        Ok(usages)
+    }
+
+    pub fn create_video(&self, input: ProductVideoInput) -> Result<ProductVideo, errors::Error> {
+        let (video, usages) = input.into_video(0, self)?;
+        let video = self.repo.insert_video(&video, &usages)?;
+
+        self.videos.replace(video.id, Some(video.clone()));
+        self.videos_by_paint.clear();
+        self.search_index.invalidate();
+        Ok(video)
+    }
+
+    pub fn update_video(
+        &self,
+        id: i32,
+        input: ProductVideoInput,
+    ) -> Result<ProductVideo, errors::Error> {
+        if self.video(id)?.is_none() {
+            return Err(not_found("product video", id));
+        }
+        let (video, usages) = input.into_video(id, self)?;
+        if !self.repo.update_video(&video, &usages)? {
+            return Err(not_found("product video", id));
+        }
+
+        self.videos.replace(id, Some(video.clone()));
+        self.usages.replace(id, usages);
+        self.videos_by_paint.clear();
+        self.search_index.invalidate();
+        Ok(video)
+    }
+
+    pub fn delete_video(&self, id: i32) -> Result<(), errors::Error> {
+        if !self.repo.delete_video(id)? {
+            return Err(not_found("product video", id));
+        }
+
+        self.videos.replace(id, None);
+        self.usages.replace(id, Vec::new());
+        self.videos_by_paint.clear();
+        self.search_index.invalidate();
+        Ok(())
+    }
+
+    /// fill the cache with videos we've loaded some other way
+    fn prime_videos(&self, videos: &[ProductVideo]) {
+        for video in videos {
+            self.videos.prime(video.id, Some(video.clone()));
+        }
+
+        // the paints used are usually the next thing asked for so we want them in a single batch
+        self.usages.expect(videos.iter().map(|video| video.id));
+    }
+}
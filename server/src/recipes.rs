@@ -0,0 +1,429 @@
+// external crates
+use chrono::Utc;
+use juniper::ID;
+use std::sync::Arc;
+
+use crate::accounts::{Role, User};
+use crate::node::{self, Kind};
+use crate::{accounts, db, errors, loaders::Loader, products};
+
+// the types that make up the domain
+mod filters;
+mod schemes;
+mod steps;
+// the storage backends that can serve the domain
+mod memory;
+mod sql;
+
+pub use self::filters::*;
+pub use self::schemes::*;
+pub use self::steps::*;
+
+/// the operations a storage backend has to support in order to serve recipes
+pub trait Repository: Send + Sync {
+    /// the recipes that match the query, newest first
+    fn find_recipes(&self, query: &RecipeQuery) -> Result<Vec<Recipe>, db::Error>;
+
+    /// the recipes with the matching ids, in the same order as the ids
+    fn recipes_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Recipe>>, db::Error>;
+
+    /// the recipes linked to each of the videos (newest first), in the same order as the
+    /// video ids
+    fn recipes_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<Recipe>>, db::Error>;
+
+    /// the steps of each of the recipes in the order they're painted, in the same order as
+    /// the recipe ids
+    fn steps_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeStep>>, db::Error>;
+
+    /// the tags on each of the recipes, in the same order as the recipe ids
+    fn tags_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeTag>>, db::Error>;
+
+    /// store a new recipe along with its steps and tags, ignoring its id, and return it
+    /// with the id it was given
+    fn insert_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<Recipe, db::Error>;
+
+    /// overwrite the recipe with the same id, replacing its steps and tags, returning
+    /// false if there isn't one
+    fn update_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<bool, db::Error>;
+
+    /// remove the recipe with the id, returning false if there isn't one
+    fn delete_recipe(&self, id: i32) -> Result<bool, db::Error>;
+}
+
+pub struct Client {
+    repo: Arc<dyn Repository>,
+    recipes: Loader<i32, Option<Recipe>>,
+    recipes_by_video: Loader<i32, Vec<Recipe>>,
+    steps: Loader<i32, Vec<RecipeStep>>,
+    tags: Loader<i32, Vec<RecipeTag>>,
+}
+
+impl Client {
+    pub fn new(repo: Arc<dyn Repository>) -> Client {
+        // every client gets its own loaders so nothing is cached between requests
+        Client {
+            recipes: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.recipes_by_id(ids)
+            }),
+            recipes_by_video: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.recipes_by_video(ids)
+            }),
+            steps: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.steps_by_recipe(ids)
+            }),
+            tags: Loader::new({
+                let repo = repo.clone();
+                move |ids: &[i32]| repo.tags_by_recipe(ids)
+            }),
+            repo,
+        }
+    }
+
+    /// the recipes that match the filter, newest first
+    pub fn find_recipes(
+        &self,
+        filter: Option<RecipeFilter>,
+        products: &products::Client,
+        accounts: &accounts::Client,
+    ) -> Result<Vec<Recipe>, errors::Error> {
+        let query = RecipeQuery::new(filter, products, accounts)?;
+        let recipes = self.repo.find_recipes(&query)?;
+        self.prime_recipes(&recipes);
+        Ok(recipes)
+    }
+
+    pub fn recipe(&self, id: i32) -> Result<Option<Recipe>, db::Error> {
+        self.recipes.load(id)
+    }
+
+    /// the recipe an id sent by the client refers to, if there is one
+    pub fn find_recipe(&self, id: &ID) -> Result<Option<Recipe>, db::Error> {
+        match node::local_id(Kind::Recipe, id) {
+            Some(id) => self.recipe(id),
+            None => Ok(None),
+        }
+    }
+
+    /// the recipes linked to the video, newest first
+    pub fn recipes_for_video(&self, video_id: i32) -> Result<Vec<Recipe>, db::Error> {
+        let recipes = self.recipes_by_video.load(video_id)?;
+        self.prime_recipes(&recipes);
+        Ok(recipes)
+    }
+
+    /// the steps of the recipe in the order they're painted
+    pub fn steps_for_recipe(
+        &self,
+        recipe_id: i32,
+        products: &products::Client,
+    ) -> Result<Vec<RecipeStep>, db::Error> {
+        let steps = self.steps.load(recipe_id)?;
+
+        // the paints are usually the next thing asked for so we want them in a single batch
+        products.expect_paints(steps.iter().flat_map(|step| step.paint_ids.iter().cloned()));
+
+        Ok(steps)
+    }
+
+    /// the tags on the recipe, miniatures first
+    pub fn tags_for_recipe(&self, recipe_id: i32) -> Result<Vec<RecipeTag>, db::Error> {
+        self.tags.load(recipe_id)
+    }
+
+    /// write up a new recipe under the author's name
+    pub fn create_recipe(
+        &self,
+        author: &User,
+        input: RecipeInput,
+        products: &products::Client,
+    ) -> Result<Recipe, errors::Error> {
+        let (recipe, steps, tags) = input.into_recipe(0, author.id, Utc::now(), products)?;
+        let recipe = self.repo.insert_recipe(&recipe, &steps, &tags)?;
+
+        self.recipes.replace(recipe.id, Some(recipe.clone()));
+        self.recipes_by_video.clear();
+        Ok(recipe)
+    }
+
+    /// change a recipe, replacing its steps and tags. only its author or a curator can
+    /// change it
+    pub fn update_recipe(
+        &self,
+        editor: &User,
+        id: i32,
+        input: RecipeInput,
+        products: &products::Client,
+    ) -> Result<Recipe, errors::Error> {
+        let existing = self.editable(editor, id)?;
+        let (recipe, steps, tags) = input.into_recipe(id, existing.user_id, Utc::now(), products)?;
+        let recipe = Recipe {
+            created_at: existing.created_at,
+            ..recipe
+        };
+        if !self.repo.update_recipe(&recipe, &steps, &tags)? {
+            return Err(not_found(id));
+        }
+
+        self.recipes.replace(id, Some(recipe.clone()));
+        self.steps.replace(id, steps);
+        self.tags.replace(id, tags);
+        self.recipes_by_video.clear();
+        Ok(recipe)
+    }
+
+    /// remove a recipe. only its author or a curator can remove it
+    pub fn delete_recipe(&self, editor: &User, id: i32) -> Result<(), errors::Error> {
+        self.editable(editor, id)?;
+        if !self.repo.delete_recipe(id)? {
+            return Err(not_found(id));
+        }
+
+        self.recipes.replace(id, None);
+        self.steps.replace(id, Vec::new());
+        self.tags.replace(id, Vec::new());
+        self.recipes_by_video.clear();
+        Ok(())
+    }
+
+    /// the recipe with the id, as long as the user is allowed to change it
+    fn editable(&self, editor: &User, id: i32) -> Result<Recipe, errors::Error> {
+        let recipe = self.recipe(id)?.ok_or_else(|| not_found(id))?;
+        if recipe.user_id != editor.id && editor.role < Role::Curator {
+            return Err(errors::Error::Forbidden(
+                "only the author or a curator can change or remove a recipe".to_string(),
+            ));
+        }
+
+        Ok(recipe)
+    }
+
+    /// fill the cache with recipes we've loaded some other way
+    fn prime_recipes(&self, recipes: &[Recipe]) {
+        for recipe in recipes {
+            self.recipes.prime(recipe.id, Some(recipe.clone()));
+        }
+
+        // the steps and tags are usually the next thing asked for so we want them in a
+        // single batch
+        self.steps.expect(recipes.iter().map(|recipe| recipe.id));
+        self.tags.expect(recipes.iter().map(|recipe| recipe.id));
+    }
+}
+
+/// the error for a recipe that doesn't exist
+fn not_found(id: i32) -> errors::Error {
+    errors::Error::NotFound(format!("recipe {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+
+    /// both backends, since they each store the steps their own way. the database is a
+    /// fresh file named after the test
+    fn backends(test: &str) -> Vec<storage::Backend> {
+        let path =
+            std::env::temp_dir().join(format!("paint-server-{}-{}.db", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = db::connect(path.to_str().unwrap()).unwrap();
+
+        vec![storage::Backend::memory(), storage::Backend::Sqlite(pool)]
+    }
+
+    /// a newly registered user with the role
+    fn member(backend: &storage::Backend, name: &str, role: Role) -> User {
+        backend
+            .accounts()
+            .insert_user(&User {
+                id: 0,
+                email: format!("{}@example.com", name),
+                display_name: name.to_string(),
+                password_hash: String::new(),
+                role,
+                created_at: Utc::now(),
+            })
+            .unwrap()
+    }
+
+    fn products(backend: &storage::Backend) -> products::Client {
+        products::Client::new(
+            backend.products(),
+            products::ColorIndex::new(),
+            products::SearchIndex::new(),
+        )
+    }
+
+    /// a recipe for intercessors made up of the steps, each using a single paint
+    fn input(title: &str, steps: &[(RecipeStage, i32)]) -> RecipeInput {
+        RecipeInput {
+            title: title.to_string(),
+            description: None,
+            miniatures: Some(vec!["Intercessor".to_string()]),
+            factions: None,
+            video: None,
+            steps: steps
+                .iter()
+                .map(|&(stage, paint_id)| RecipeStepInput {
+                    stage,
+                    paints: vec![node::global_id(Kind::Paint, paint_id)],
+                    technique: None,
+                    notes: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn only_the_author_or_a_curator_can_change_a_recipe() {
+        for backend in backends("recipe-editors") {
+            let products = products(&backend);
+            let author = member(&backend, "author", Role::User);
+            let someone = member(&backend, "someone", Role::User);
+            let curator = member(&backend, "curator", Role::Curator);
+            let steps = [(RecipeStage::Basecoat, 1)];
+
+            let client = Client::new(backend.recipes());
+            let recipe = client
+                .create_recipe(&author, input("Ultramarines", &steps), &products)
+                .unwrap();
+
+            let edit = client.update_recipe(&someone, recipe.id, input("Mine", &steps), &products);
+            assert!(matches!(edit, Err(errors::Error::Forbidden(_))));
+            let removal = client.delete_recipe(&someone, recipe.id);
+            assert!(matches!(removal, Err(errors::Error::Forbidden(_))));
+            let stored = Client::new(backend.recipes()).recipe(recipe.id).unwrap();
+            assert_eq!(
+                stored.map(|recipe| recipe.title),
+                Some("Ultramarines".to_string())
+            );
+
+            let edited = client
+                .update_recipe(
+                    &author,
+                    recipe.id,
+                    input("Ultramarines, quickly", &steps),
+                    &products,
+                )
+                .unwrap();
+            assert_eq!(edited.title, "Ultramarines, quickly");
+
+            // a curator's edit doesn't take the recipe away from its author
+            let edited = client
+                .update_recipe(
+                    &curator,
+                    recipe.id,
+                    input("Ultramarines (2nd)", &steps),
+                    &products,
+                )
+                .unwrap();
+            assert_eq!(edited.user_id, author.id);
+            assert_eq!(edited.created_at, recipe.created_at);
+
+            client.delete_recipe(&curator, recipe.id).unwrap();
+            let stored = Client::new(backend.recipes()).recipe(recipe.id).unwrap();
+            assert!(stored.is_none());
+            let missing = client.delete_recipe(&author, recipe.id);
+            assert!(matches!(missing, Err(errors::Error::NotFound(_))));
+        }
+    }
+
+    #[test]
+    fn steps_keep_their_order_through_an_update() {
+        for backend in backends("recipe-steps") {
+            let products = products(&backend);
+            let author = member(&backend, "author", Role::User);
+            let first = [
+                (RecipeStage::Primer, 1),
+                (RecipeStage::Basecoat, 2),
+                (RecipeStage::Layer, 3),
+            ];
+            let second = [
+                (RecipeStage::Basecoat, 3),
+                (RecipeStage::Shade, 1),
+                (RecipeStage::Highlight, 4),
+                (RecipeStage::Glaze, 2),
+            ];
+
+            let client = Client::new(backend.recipes());
+            let recipe = client
+                .create_recipe(&author, input("Ultramarines", &first), &products)
+                .unwrap();
+            client
+                .update_recipe(
+                    &author,
+                    recipe.id,
+                    input("Ultramarines", &second),
+                    &products,
+                )
+                .unwrap();
+
+            // both what the client cached and what was stored
+            for client in &[client, Client::new(backend.recipes())] {
+                let steps: Vec<(i32, RecipeStage, Vec<i32>)> = client
+                    .steps_for_recipe(recipe.id, &products)
+                    .unwrap()
+                    .into_iter()
+                    .map(|step| (step.position, step.stage, step.paint_ids))
+                    .collect();
+                assert_eq!(
+                    steps,
+                    vec![
+                        (1, RecipeStage::Basecoat, vec![3]),
+                        (2, RecipeStage::Shade, vec![1]),
+                        (3, RecipeStage::Highlight, vec![4]),
+                        (4, RecipeStage::Glaze, vec![2]),
+                    ]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn recipes_are_found_by_tag_ignoring_case() {
+        for backend in backends("recipe-tags") {
+            let products = products(&backend);
+            let accounts = accounts::Client::new(
+                backend.accounts(),
+                accounts::Sessions::new(b"secret"),
+                None,
+                None,
+            );
+            let author = member(&backend, "author", Role::User);
+            let steps = [(RecipeStage::Basecoat, 1)];
+
+            let client = Client::new(backend.recipes());
+            let tagged = client
+                .create_recipe(&author, input("Ultramarines", &steps), &products)
+                .unwrap();
+            let untagged = RecipeInput {
+                miniatures: None,
+                ..input("Blood Angels", &steps)
+            };
+            client.create_recipe(&author, untagged, &products).unwrap();
+
+            let filter = RecipeFilter {
+                miniature: Some(" intercessor ".to_string()),
+                ..RecipeFilter::default()
+            };
+            let found = client
+                .find_recipes(Some(filter), &products, &accounts)
+                .unwrap();
+            let ids: Vec<i32> = found.iter().map(|recipe| recipe.id).collect();
+            assert_eq!(ids, vec![tagged.id]);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// external crates
+use juniper::ID;
+
+use super::schemes::trimmed;
+use crate::node::{self, Kind};
+use crate::{accounts, errors, products};
+
+/// narrows down the recipes. every condition that's given has to hold
+#[derive(juniper::GraphQLInputObject, Default)]
+pub struct RecipeFilter {
+    /// only recipes whose title or description contains the text
+    pub text: Option<String>,
+    /// only recipes written by the user
+    pub author: Option<ID>,
+    /// only recipes that call for the paint
+    pub paint: Option<ID>,
+    /// only recipes linked to the video
+    pub video: Option<ID>,
+    /// only recipes meant for the miniature, ignoring case
+    pub miniature: Option<String>,
+    /// only recipes meant for the faction, ignoring case
+    pub faction: Option<String>,
+}
+
+/// a filter for recipes in terms storage understands. leaving everything out lists every
+/// recipe
+#[derive(Clone, Debug, Default)]
+pub struct RecipeQuery {
+    /// lowercase text the title or description has to contain
+    pub text: Option<String>,
+    pub user_id: Option<i32>,
+    pub paint_id: Option<i32>,
+    pub video_id: Option<i32>,
+    pub miniature: Option<String>,
+    pub faction: Option<String>,
+}
+
+impl RecipeQuery {
+    /// check a filter sent by the client over and turn it into a query
+    pub(super) fn new(
+        filter: Option<RecipeFilter>,
+        products: &products::Client,
+        accounts: &accounts::Client,
+    ) -> Result<RecipeQuery, errors::Error> {
+        let filter = filter.unwrap_or_default();
+        let mut validation = errors::Validation::new();
+
+        let mut user_id = None;
+        if let Some(author) = &filter.author {
+            let author = match node::local_id(Kind::User, author) {
+                Some(id) => accounts.user(id)?,
+                None => None,
+            };
+            match author {
+                Some(author) => user_id = Some(author.id),
+                None => validation.add("filter.author", "does not exist"),
+            }
+        }
+        let mut paint_id = None;
+        if let Some(paint) = &filter.paint {
+            match products.find_paint(paint)? {
+                Some(paint) => paint_id = Some(paint.id),
+                None => validation.add("filter.paint", "does not exist"),
+            }
+        }
+        let mut video_id = None;
+        if let Some(video) = &filter.video {
+            match products.find_video(video)? {
+                Some(video) => video_id = Some(video.id),
+                None => validation.add("filter.video", "does not exist"),
+            }
+        }
+        validation.finish()?;
+
+        // blank text doesn't narrow anything down
+        Ok(RecipeQuery {
+            text: trimmed(filter.text).map(|text| text.to_ascii_lowercase()),
+            user_id,
+            paint_id,
+            video_id,
+            miniature: trimmed(filter.miniature),
+            faction: trimmed(filter.faction),
+        })
+    }
+}
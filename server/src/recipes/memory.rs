@@ -0,0 +1,189 @@
+// external crates
+use std::cmp::Ordering;
+
+use super::{Recipe, RecipeQuery, RecipeStep, RecipeTag, Repository, TagKind};
+use crate::{db, memory};
+
+impl Repository for memory::Store {
+    fn find_recipes(&self, query: &RecipeQuery) -> Result<Vec<Recipe>, db::Error> {
+        let tables = self.read();
+        let has_paint = |recipe: &Recipe, paint_id: i32| {
+            tables
+                .recipe_steps
+                .iter()
+                .any(|step| step.recipe_id == recipe.id && step.paint_ids.contains(&paint_id))
+        };
+        let has_tag = |recipe: &Recipe, kind: TagKind, name: &str| {
+            tables.recipe_tags.iter().any(|tag| {
+                tag.recipe_id == recipe.id && tag.kind == kind && tag.name.eq_ignore_ascii_case(name)
+            })
+        };
+        let has_text = |recipe: &Recipe, text: &str| {
+            let description = recipe.description.as_deref().unwrap_or_default();
+            format!("{} {}", recipe.title, description)
+                .to_ascii_lowercase()
+                .contains(text)
+        };
+
+        let matches = |recipe: &Recipe| {
+            let tags = [(TagKind::Miniature, &query.miniature), (TagKind::Faction, &query.faction)];
+            if let Some(text) = &query.text {
+                if !has_text(recipe, text) {
+                    return false;
+                }
+            }
+            if let Some(paint_id) = query.paint_id {
+                if !has_paint(recipe, paint_id) {
+                    return false;
+                }
+            }
+            for (kind, name) in tags.iter() {
+                if let Some(name) = name {
+                    if !has_tag(recipe, *kind, name) {
+                        return false;
+                    }
+                }
+            }
+
+            (query.user_id.is_none() || Some(recipe.user_id) == query.user_id)
+                && (query.video_id.is_none() || recipe.video_id == query.video_id)
+        };
+
+        let mut recipes: Vec<Recipe> = tables
+            .recipes
+            .iter()
+            .filter(|recipe| matches(recipe))
+            .cloned()
+            .collect();
+        recipes.sort_by(newest_first);
+
+        Ok(recipes)
+    }
+
+    fn recipes_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Recipe>>, db::Error> {
+        let tables = self.read();
+
+        Ok(ids
+            .iter()
+            .map(|id| tables.recipes.iter().find(|recipe| recipe.id == *id).cloned())
+            .collect())
+    }
+
+    fn recipes_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<Recipe>>, db::Error> {
+        let tables = self.read();
+
+        Ok(video_ids
+            .iter()
+            .map(|video_id| {
+                let mut recipes: Vec<Recipe> = tables
+                    .recipes
+                    .iter()
+                    .filter(|recipe| recipe.video_id == Some(*video_id))
+                    .cloned()
+                    .collect();
+                recipes.sort_by(newest_first);
+                recipes
+            })
+            .collect())
+    }
+
+    fn steps_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeStep>>, db::Error> {
+        let tables = self.read();
+
+        Ok(recipe_ids
+            .iter()
+            .map(|recipe_id| {
+                let mut steps: Vec<RecipeStep> = tables
+                    .recipe_steps
+                    .iter()
+                    .filter(|step| step.recipe_id == *recipe_id)
+                    .cloned()
+                    .collect();
+                steps.sort_by_key(|step| step.position);
+                steps
+            })
+            .collect())
+    }
+
+    fn tags_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeTag>>, db::Error> {
+        let tables = self.read();
+
+        // match the order the database hands tags back in
+        Ok(recipe_ids
+            .iter()
+            .map(|recipe_id| {
+                let mut tags: Vec<RecipeTag> = tables
+                    .recipe_tags
+                    .iter()
+                    .filter(|tag| tag.recipe_id == *recipe_id)
+                    .cloned()
+                    .collect();
+                tags.sort_by(|a, b| {
+                    (a.kind == TagKind::Faction, &a.name).cmp(&(b.kind == TagKind::Faction, &b.name))
+                });
+                tags
+            })
+            .collect())
+    }
+
+    fn insert_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<Recipe, db::Error> {
+        let mut tables = self.write();
+
+        let recipe = Recipe {
+            id: memory::next_id(tables.recipes.iter().map(|recipe| recipe.id)),
+            ..recipe.clone()
+        };
+        tables.recipes.push(recipe.clone());
+        insert_parts(&mut tables, recipe.id, steps, tags);
+
+        Ok(recipe)
+    }
+
+    fn update_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        let updated = memory::replace(&mut tables.recipes, recipe, |recipe| recipe.id);
+        if updated {
+            tables.recipe_steps.retain(|step| step.recipe_id != recipe.id);
+            tables.recipe_tags.retain(|tag| tag.recipe_id != recipe.id);
+            insert_parts(&mut tables, recipe.id, steps, tags);
+        }
+
+        Ok(updated)
+    }
+
+    fn delete_recipe(&self, id: i32) -> Result<bool, db::Error> {
+        let mut tables = self.write();
+
+        tables.recipe_steps.retain(|step| step.recipe_id != id);
+        tables.recipe_tags.retain(|tag| tag.recipe_id != id);
+        Ok(memory::remove(&mut tables.recipes, id, |recipe| recipe.id))
+    }
+}
+
+/// store the steps and tags of a recipe
+fn insert_parts(tables: &mut memory::Tables, recipe_id: i32, steps: &[RecipeStep], tags: &[RecipeTag]) {
+    tables.recipe_steps.extend(steps.iter().map(|step| RecipeStep {
+        recipe_id,
+        ..step.clone()
+    }));
+    tables.recipe_tags.extend(tags.iter().map(|tag| RecipeTag {
+        recipe_id,
+        ..tag.clone()
+    }));
+}
+
+/// match the order the database hands recipes back in
+fn newest_first(a: &Recipe, b: &Recipe) -> Ordering {
+    b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id))
+}
@@ -0,0 +1,223 @@
+// external crates
+use chrono::{DateTime, Utc};
+use juniper::{FieldResult, ID};
+
+use super::{RecipeStep, RecipeStepInput, RecipeTag, TagKind};
+use crate::node::{self, Kind, Node};
+use crate::{accounts, api, errors, inventory, products};
+
+/// a color scheme someone has written up for painting a miniature, step by step
+#[derive(Clone, Debug)]
+pub struct Recipe {
+    pub id: i32,
+    /// the user who wrote the recipe
+    pub user_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    /// the video that shows the recipe being painted, if there is one
+    pub video_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+    interfaces = [Node],
+)]
+impl Recipe {
+    /// the globally unique identifier of the recipe
+    fn id(&self) -> ID {
+        node::global_id(Kind::Recipe, self.id)
+    }
+
+    /// what the recipe is called (ie, Ultramarine armour)
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// anything the author wants to say about the recipe as a whole
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// the user who wrote the recipe
+    fn author(&self, context: &api::Context) -> FieldResult<accounts::User> {
+        Ok(context.accounts.user(self.user_id)?.ok_or("missing author")?)
+    }
+
+    /// the steps of the recipe in the order they're painted
+    fn steps(&self, context: &api::Context) -> FieldResult<Vec<RecipeStep>> {
+        Ok(context.recipes.steps_for_recipe(self.id, &context.products)?)
+    }
+
+    /// every paint the recipe calls for, in the order they're first used
+    fn paints(&self, context: &api::Context) -> FieldResult<Vec<products::Paint>> {
+        let mut paints = Vec::new();
+        for paint_id in self.paint_ids(context)? {
+            paints.push(context.products.paint(paint_id)?.ok_or("missing paint")?);
+        }
+
+        Ok(paints)
+    }
+
+    /// the miniatures the recipe is meant for
+    fn miniatures(&self, context: &api::Context) -> FieldResult<Vec<String>> {
+        Ok(self.tags(context, TagKind::Miniature)?)
+    }
+
+    /// the factions the recipe is meant for
+    fn factions(&self, context: &api::Context) -> FieldResult<Vec<String>> {
+        Ok(self.tags(context, TagKind::Faction)?)
+    }
+
+    /// the video that shows the recipe being painted
+    fn video(&self, context: &api::Context) -> FieldResult<Option<products::ProductVideo>> {
+        match self.video_id {
+            Some(id) => Ok(context.products.video(id)?),
+            None => Ok(None),
+        }
+    }
+
+    /// which of the paints the recipe calls for the viewer owns, and the closest thing
+    /// they own to each of the rest. nothing if they aren't signed in
+    fn inventoryCoverage(
+        &self,
+        context: &api::Context,
+    ) -> FieldResult<Option<inventory::InventoryCoverage>> {
+        let viewer = match context.accounts.viewer()? {
+            Some(viewer) => viewer,
+            None => return Ok(None),
+        };
+
+        Ok(Some(context.inventory.coverage(
+            &viewer,
+            self.paint_ids(context)?,
+            &context.products,
+        )?))
+    }
+
+    /// when the recipe was first written up
+    fn createdAt(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// when the recipe was last changed
+    fn updatedAt(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+connection!(RecipeConnection, RecipeEdge, Recipe);
+
+impl Recipe {
+    /// the ids of every paint the recipe calls for, in the order they're first used
+    fn paint_ids(&self, context: &api::Context) -> Result<Vec<i32>, errors::Error> {
+        let mut paint_ids = Vec::new();
+        for step in context.recipes.steps_for_recipe(self.id, &context.products)? {
+            for paint_id in step.paint_ids {
+                if !paint_ids.contains(&paint_id) {
+                    paint_ids.push(paint_id);
+                }
+            }
+        }
+
+        Ok(paint_ids)
+    }
+
+    /// the names of the recipe's tags of one kind
+    fn tags(&self, context: &api::Context, kind: TagKind) -> Result<Vec<String>, errors::Error> {
+        Ok(context
+            .recipes
+            .tags_for_recipe(self.id)?
+            .into_iter()
+            .filter(|tag| tag.kind == kind)
+            .map(|tag| tag.name)
+            .collect())
+    }
+}
+
+/// everything needed to write up a recipe. updating a recipe replaces all of it
+#[derive(juniper::GraphQLInputObject)]
+pub struct RecipeInput {
+    pub title: String,
+    pub description: Option<String>,
+    /// the miniatures the recipe is meant for
+    pub miniatures: Option<Vec<String>>,
+    /// the factions the recipe is meant for
+    pub factions: Option<Vec<String>>,
+    /// the id of a video that shows the recipe being painted
+    pub video: Option<ID>,
+    /// the steps in the order they're painted
+    pub steps: Vec<RecipeStepInput>,
+}
+
+impl RecipeInput {
+    /// check the input over and turn it into a recipe (along with its steps and tags) that
+    /// hasn't been stored yet
+    pub(super) fn into_recipe(
+        self,
+        id: i32,
+        user_id: i32,
+        now: DateTime<Utc>,
+        products: &products::Client,
+    ) -> Result<(Recipe, Vec<RecipeStep>, Vec<RecipeTag>), errors::Error> {
+        let mut validation = errors::Validation::new();
+        validation.require("title", &self.title);
+
+        let mut video_id = None;
+        if let Some(video) = &self.video {
+            match products.find_video(video)? {
+                Some(video) => video_id = Some(video.id),
+                None => validation.add("video", "does not exist"),
+            }
+        }
+
+        let mut tags = Vec::new();
+        let kinds = vec![
+            (TagKind::Miniature, "miniatures", self.miniatures),
+            (TagKind::Faction, "factions", self.factions),
+        ];
+        for (kind, field, names) in kinds {
+            for (index, name) in names.unwrap_or_default().into_iter().enumerate() {
+                let name = name.trim().to_string();
+                validation.require(&format!("{}.{}", field, index), &name);
+                // tags differing only in case would show up as the same thing
+                let known = tags.iter().any(|tag: &RecipeTag| {
+                    tag.kind == kind && tag.name.eq_ignore_ascii_case(&name)
+                });
+                if !name.is_empty() && !known {
+                    tags.push(RecipeTag { recipe_id: id, kind, name });
+                }
+            }
+        }
+
+        if self.steps.is_empty() {
+            validation.add("steps", "must have at least one step");
+        }
+        let mut steps = Vec::new();
+        for (index, step) in self.steps.into_iter().enumerate() {
+            if let Some(step) = step.into_step(id, index, products, &mut validation)? {
+                steps.push(step);
+            }
+        }
+        validation.finish()?;
+
+        let recipe = Recipe {
+            id,
+            user_id,
+            title: self.title.trim().to_string(),
+            description: trimmed(self.description),
+            video_id,
+            created_at: now,
+            updated_at: now,
+        };
+
+        Ok((recipe, steps, tags))
+    }
+}
+
+/// the text with the whitespace trimmed off, or nothing if that leaves it empty
+pub(super) fn trimmed(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
@@ -0,0 +1,307 @@
+// external crates
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Row, Transaction};
+
+use super::{Recipe, RecipeQuery, RecipeStage, RecipeStep, RecipeTag, Repository, TagKind};
+use crate::db;
+
+/// the columns we need to select in order to build a Recipe
+const RECIPE_COLUMNS: &str =
+    "recipes.id, recipes.user_id, recipes.title, recipes.description, recipes.video_id, \
+     recipes.created_at, recipes.updated_at";
+
+impl Repository for db::Pool {
+    fn find_recipes(&self, query: &RecipeQuery) -> Result<Vec<Recipe>, db::Error> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(text) = &query.text {
+            // lower() only folds ascii, the same as the text was folded
+            conditions.push(
+                "instr(lower(recipes.title || ' ' || coalesce(recipes.description, '')), ?) > 0",
+            );
+            values.push(Box::new(text.clone()));
+        }
+        if let Some(user_id) = query.user_id {
+            conditions.push("recipes.user_id = ?");
+            values.push(Box::new(user_id));
+        }
+        if let Some(paint_id) = query.paint_id {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM recipe_step_paints
+                         WHERE recipe_step_paints.recipe_id = recipes.id
+                         AND recipe_step_paints.paint_id = ?)",
+            );
+            values.push(Box::new(paint_id));
+        }
+        if let Some(video_id) = query.video_id {
+            conditions.push("recipes.video_id = ?");
+            values.push(Box::new(video_id));
+        }
+        for (kind, name) in &[(TagKind::Miniature, &query.miniature), (TagKind::Faction, &query.faction)] {
+            if let Some(name) = name {
+                conditions.push(
+                    "EXISTS (SELECT 1 FROM recipe_tags
+                             WHERE recipe_tags.recipe_id = recipes.id
+                             AND recipe_tags.kind = ? AND recipe_tags.name = ? COLLATE NOCASE)",
+                );
+                values.push(Box::new(*kind));
+                values.push(Box::new(name.clone()));
+            }
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let conn = self.get()?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM recipes {} ORDER BY recipes.created_at DESC, recipes.id DESC",
+            RECIPE_COLUMNS, filter
+        ))?;
+
+        let recipes = statement
+            .query_map(&values, recipe_from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(recipes)
+    }
+
+    fn recipes_by_id(&self, ids: &[i32]) -> Result<Vec<Option<Recipe>>, db::Error> {
+        db::load_by_key(
+            &*self.get()?,
+            &format!("SELECT {} FROM recipes WHERE id IN ({{keys}})", RECIPE_COLUMNS),
+            ids,
+            recipe_from_row,
+            |recipe| recipe.id,
+        )
+    }
+
+    fn recipes_by_video(&self, video_ids: &[i32]) -> Result<Vec<Vec<Recipe>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            &format!(
+                "SELECT {} FROM recipes WHERE video_id IN ({{keys}})
+                 ORDER BY created_at DESC, id DESC",
+                RECIPE_COLUMNS
+            ),
+            video_ids,
+            recipe_from_row,
+            // only recipes with a video can come back from the query
+            |recipe| recipe.video_id.unwrap_or_default(),
+        )
+    }
+
+    fn steps_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeStep>>, db::Error> {
+        let conn = self.get()?;
+        let mut steps = db::load_grouped(
+            &conn,
+            "SELECT recipe_id, position, stage, technique, notes FROM recipe_steps
+             WHERE recipe_id IN ({keys})
+             ORDER BY position",
+            recipe_ids,
+            step_from_row,
+            |step| step.recipe_id,
+        )?;
+
+        // the paints come in a query of their own and get handed out to their steps
+        let paints = db::load_grouped(
+            &conn,
+            "SELECT recipe_id, position, paint_id FROM recipe_step_paints
+             WHERE recipe_id IN ({keys})
+             ORDER BY position, paint_position",
+            recipe_ids,
+            |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?, row.get::<_, i32>(2)?)),
+            |(recipe_id, _, _)| *recipe_id,
+        )?;
+        for (steps, paints) in steps.iter_mut().zip(paints) {
+            for (_, position, paint_id) in paints {
+                if let Some(step) = steps.iter_mut().find(|step| step.position == position) {
+                    step.paint_ids.push(paint_id);
+                }
+            }
+        }
+
+        Ok(steps)
+    }
+
+    fn tags_by_recipe(&self, recipe_ids: &[i32]) -> Result<Vec<Vec<RecipeTag>>, db::Error> {
+        db::load_grouped(
+            &*self.get()?,
+            "SELECT recipe_id, kind, name FROM recipe_tags
+             WHERE recipe_id IN ({keys})
+             ORDER BY kind = 'faction', name",
+            recipe_ids,
+            |row| {
+                Ok(RecipeTag {
+                    recipe_id: row.get(0)?,
+                    kind: row.get(1)?,
+                    name: row.get(2)?,
+                })
+            },
+            |tag| tag.recipe_id,
+        )
+    }
+
+    fn insert_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<Recipe, db::Error> {
+        let mut conn = self.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO recipes (user_id, title, description, video_id, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                recipe.user_id,
+                recipe.title,
+                recipe.description,
+                recipe.video_id,
+                recipe.created_at,
+                recipe.updated_at,
+            ],
+        )?;
+        let recipe = Recipe {
+            id: tx.last_insert_rowid() as i32,
+            ..recipe.clone()
+        };
+        insert_parts(&tx, recipe.id, steps, tags)?;
+        tx.commit()?;
+
+        Ok(recipe)
+    }
+
+    fn update_recipe(
+        &self,
+        recipe: &Recipe,
+        steps: &[RecipeStep],
+        tags: &[RecipeTag],
+    ) -> Result<bool, db::Error> {
+        let mut conn = self.get()?;
+        let tx = conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE recipes
+             SET title = ?, description = ?, video_id = ?, updated_at = ?
+             WHERE id = ?",
+            params![
+                recipe.title,
+                recipe.description,
+                recipe.video_id,
+                recipe.updated_at,
+                recipe.id,
+            ],
+        )?;
+        if changed == 0 {
+            return Ok(false);
+        }
+
+        // the steps (and their paints) and tags are replaced wholesale
+        tx.execute("DELETE FROM recipe_steps WHERE recipe_id = ?", [recipe.id])?;
+        tx.execute("DELETE FROM recipe_tags WHERE recipe_id = ?", [recipe.id])?;
+        insert_parts(&tx, recipe.id, steps, tags)?;
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    fn delete_recipe(&self, id: i32) -> Result<bool, db::Error> {
+        let conn = self.get()?;
+        Ok(conn.execute("DELETE FROM recipes WHERE id = ?", [id])? > 0)
+    }
+}
+
+/// store the steps (along with their paints) and tags of a recipe
+fn insert_parts(
+    tx: &Transaction,
+    recipe_id: i32,
+    steps: &[RecipeStep],
+    tags: &[RecipeTag],
+) -> Result<(), db::Error> {
+    let mut insert_step = tx.prepare(
+        "INSERT INTO recipe_steps (recipe_id, position, stage, technique, notes)
+         VALUES (?, ?, ?, ?, ?)",
+    )?;
+    let mut insert_paint = tx.prepare(
+        "INSERT INTO recipe_step_paints (recipe_id, position, paint_position, paint_id)
+         VALUES (?, ?, ?, ?)",
+    )?;
+    for step in steps {
+        insert_step.execute(params![recipe_id, step.position, step.stage, step.technique, step.notes])?;
+        for (paint_position, paint_id) in step.paint_ids.iter().enumerate() {
+            insert_paint.execute(params![recipe_id, step.position, paint_position as i32, paint_id])?;
+        }
+    }
+
+    let mut insert_tag =
+        tx.prepare("INSERT INTO recipe_tags (recipe_id, kind, name) VALUES (?, ?, ?)")?;
+    for tag in tags {
+        insert_tag.execute(params![recipe_id, tag.kind, tag.name])?;
+    }
+
+    Ok(())
+}
+
+/// build a recipe out of a row containing RECIPE_COLUMNS
+fn recipe_from_row(row: &Row) -> rusqlite::Result<Recipe> {
+    Ok(Recipe {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        video_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// build a step without its paints out of a row containing its recipe id, position,
+/// stage, technique and notes
+fn step_from_row(row: &Row) -> rusqlite::Result<RecipeStep> {
+    Ok(RecipeStep {
+        recipe_id: row.get(0)?,
+        position: row.get(1)?,
+        stage: row.get(2)?,
+        paint_ids: Vec::new(),
+        technique: row.get(3)?,
+        notes: row.get(4)?,
+    })
+}
+
+impl ToSql for RecipeStage {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for RecipeStage {
+    fn column_result(value: ValueRef) -> FromSqlResult<RecipeStage> {
+        match value.as_str()? {
+            "primer" => Ok(RecipeStage::Primer),
+            "basecoat" => Ok(RecipeStage::Basecoat),
+            "shade" => Ok(RecipeStage::Shade),
+            "layer" => Ok(RecipeStage::Layer),
+            "highlight" => Ok(RecipeStage::Highlight),
+            "glaze" => Ok(RecipeStage::Glaze),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl ToSql for TagKind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for TagKind {
+    fn column_result(value: ValueRef) -> FromSqlResult<TagKind> {
+        match value.as_str()? {
+            "miniature" => Ok(TagKind::Miniature),
+            "faction" => Ok(TagKind::Faction),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// external crates
+use juniper::{FieldResult, ID};
+
+use super::schemes::trimmed;
+use crate::{api, db, errors, products};
+
+/// the part of a paint job a step belongs to
+#[derive(juniper::GraphQLEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RecipeStage {
+    Primer,
+    Basecoat,
+    Shade,
+    Layer,
+    Highlight,
+    Glaze,
+}
+
+impl RecipeStage {
+    /// the name we use for the stage when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecipeStage::Primer => "primer",
+            RecipeStage::Basecoat => "basecoat",
+            RecipeStage::Shade => "shade",
+            RecipeStage::Layer => "layer",
+            RecipeStage::Highlight => "highlight",
+            RecipeStage::Glaze => "glaze",
+        }
+    }
+}
+
+/// one step of a recipe
+#[derive(Clone, Debug)]
+pub struct RecipeStep {
+    pub recipe_id: i32,
+    /// where the step comes in the recipe, starting from 1
+    pub position: i32,
+    pub stage: RecipeStage,
+    /// the paints used in the step, in the order they're listed
+    pub paint_ids: Vec<i32>,
+    pub technique: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[juniper::object(
+    Context = api::Context,
+)]
+impl RecipeStep {
+    /// where the step comes in the recipe, starting from 1
+    fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// the part of the paint job the step belongs to
+    fn stage(&self) -> RecipeStage {
+        self.stage
+    }
+
+    /// the paints used in the step
+    fn paints(&self, context: &api::Context) -> FieldResult<Vec<products::Paint>> {
+        let mut paints = Vec::new();
+        for paint_id in &self.paint_ids {
+            paints.push(context.products.paint(*paint_id)?.ok_or("missing paint")?);
+        }
+
+        Ok(paints)
+    }
+
+    /// how the paints are put on (ie, drybrush or wet blend)
+    fn technique(&self) -> Option<&str> {
+        self.technique.as_deref()
+    }
+
+    /// anything else worth knowing about the step
+    fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+}
+
+/// one step of a recipe
+#[derive(juniper::GraphQLInputObject)]
+pub struct RecipeStepInput {
+    pub stage: RecipeStage,
+    /// the ids of the paints used in the step, in the order they should be listed
+    pub paints: Vec<ID>,
+    /// how the paints are put on (ie, drybrush or wet blend)
+    pub technique: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl RecipeStepInput {
+    /// check the step over, adding any problems to the recipe's validation. nothing comes
+    /// back if the step had problems
+    pub(super) fn into_step(
+        self,
+        recipe_id: i32,
+        index: usize,
+        products: &products::Client,
+        validation: &mut errors::Validation,
+    ) -> Result<Option<RecipeStep>, db::Error> {
+        let mut valid = true;
+        let mut paint_ids = Vec::new();
+        for (paint_index, paint) in self.paints.iter().enumerate() {
+            let field = format!("steps.{}.paints.{}", index, paint_index);
+            match products.find_paint(paint)? {
+                Some(paint) if paint_ids.contains(&paint.id) => {
+                    validation.add(&field, "is already listed in the step");
+                    valid = false;
+                }
+                Some(paint) => paint_ids.push(paint.id),
+                None => {
+                    validation.add(&field, "does not exist");
+                    valid = false;
+                }
+            }
+        }
+        if !valid {
+            return Ok(None);
+        }
+
+        Ok(Some(RecipeStep {
+            recipe_id,
+            position: index as i32 + 1,
+            stage: self.stage,
+            paint_ids,
+            technique: trimmed(self.technique),
+            notes: trimmed(self.notes),
+        }))
+    }
+}
+
+/// the kinds of things a recipe can be tagged with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagKind {
+    Miniature,
+    Faction,
+}
+
+impl TagKind {
+    /// the name we use for the kind of tag when storing it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagKind::Miniature => "miniature",
+            TagKind::Faction => "faction",
+        }
+    }
+}
+
+/// a miniature or faction a recipe is meant for
+#[derive(Clone, Debug)]
+pub struct RecipeTag {
+    pub recipe_id: i32,
+    pub kind: TagKind,
+    pub name: String,
+}
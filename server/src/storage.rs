@@ -0,0 +1,77 @@
+// external crates
+use std::sync::Arc;
+
+use crate::{accounts, alerts, currency, db, inventory, memory, pricing, products, recipes};
+
+/// the places the server can keep its data. every request builds its domain clients
+/// out of whichever backend the server was configured with.
+#[derive(Clone)]
+pub enum Backend {
+    /// a sqlite database shared between every request
+    Sqlite(db::Pool),
+    /// records that only live as long as the process, seeded from the fixtures
+    Memory(Arc<memory::Store>),
+}
+
+impl Backend {
+    /// a backend held entirely in memory
+    pub fn memory() -> Backend {
+        Backend::Memory(Arc::new(memory::Store::seeded()))
+    }
+
+    /// the repository that serves the products domain
+    pub fn products(&self) -> Arc<dyn products::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the inventory domain
+    pub fn inventory(&self) -> Arc<dyn inventory::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the pricing domain
+    pub fn pricing(&self) -> Arc<dyn pricing::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the currency domain
+    pub fn currency(&self) -> Arc<dyn currency::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the alerts domain
+    pub fn alerts(&self) -> Arc<dyn alerts::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the recipes domain
+    pub fn recipes(&self) -> Arc<dyn recipes::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+
+    /// the repository that serves the accounts domain
+    pub fn accounts(&self) -> Arc<dyn accounts::Repository> {
+        match self {
+            Backend::Sqlite(pool) => Arc::new(pool.clone()),
+            Backend::Memory(store) => store.clone(),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// external crates
+use std::collections::BTreeMap;
+
+/// a prefix tree over strings, each carrying a value. the children of every node are kept
+/// in order so walking the tree visits keys alphabetically, which means the first matches
+/// for a prefix are also the alphabetically first ones and we can stop as soon as we have
+/// enough.
+pub struct Trie<T> {
+    nodes: Vec<Node>,
+    values: Vec<T>,
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<char, usize>,
+    /// the values whose keys end at the node, in the order they were added
+    values: Vec<usize>,
+}
+
+impl<T> Trie<T> {
+    /// build a tree out of the given keys and values. values that share a key keep the
+    /// order they were given in
+    pub fn build(entries: Vec<(String, T)>) -> Trie<T> {
+        let mut trie = Trie {
+            nodes: vec![Node::default()],
+            values: Vec::with_capacity(entries.len()),
+        };
+
+        for (key, value) in entries {
+            let mut node = 0;
+            for c in key.chars() {
+                node = match trie.nodes[node].children.get(&c) {
+                    Some(child) => *child,
+                    None => {
+                        let child = trie.nodes.len();
+                        trie.nodes.push(Node::default());
+                        trie.nodes[node].children.insert(c, child);
+                        child
+                    }
+                };
+            }
+            trie.nodes[node].values.push(trie.values.len());
+            trie.values.push(value);
+        }
+
+        trie
+    }
+
+    /// the up to `limit` values whose keys start with the prefix and pass the filter, in
+    /// the alphabetical order of their keys
+    pub fn with_prefix<F>(&self, prefix: &str, limit: usize, mut accept: F) -> Vec<&T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut node = 0;
+        for c in prefix.chars() {
+            match self.nodes[node].children.get(&c) {
+                Some(child) => node = *child,
+                None => return Vec::new(),
+            }
+        }
+
+        // walk everything under the prefix depth first, smallest child first
+        let mut found = Vec::new();
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            for value in &self.nodes[node].values {
+                if found.len() >= limit {
+                    return found;
+                }
+                let value = &self.values[*value];
+                if accept(value) {
+                    found.push(value);
+                }
+            }
+            stack.extend(self.nodes[node].children.values().rev());
+        }
+
+        found
+    }
+}